@@ -237,6 +237,66 @@ export const api = {
     return response.json();
   },
 
+  // Drafts profile text from source material; nothing is saved
+  async draftScholarProfile(sourceText, scholarId, token) {
+    const response = await fetchWithAuth(`${API_BASE_URL}/admin/scholars/draft`, {
+      method: 'POST',
+      headers: {
+        'Authorization': `Bearer ${token}`,
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify({ sourceText, scholarId: scholarId || null }),
+    });
+    await checkResponse(response, '生成草稿失败');
+    return response.json();
+  },
+
+  // Tag and identity suggestions from the scholar's nearest embedded neighbors
+  async getScholarSuggestions(id, token) {
+    const response = await fetchWithAuth(`${API_BASE_URL}/admin/scholars/${id}/suggestions`, {
+      headers: {
+        'Authorization': `Bearer ${token}`,
+      },
+    });
+    await checkResponse(response, '获取标签建议失败');
+    return response.json();
+  },
+
+  // Card summary of a scholar; fails with 404 until one is generated or written
+  async getScholarSummary(id, token) {
+    const response = await fetchWithAuth(`${API_BASE_URL}/admin/scholars/${id}/summary`, {
+      headers: {
+        'Authorization': `Bearer ${token}`,
+      },
+    });
+    await checkResponse(response, '获取摘要失败');
+    return response.json();
+  },
+
+  async updateScholarSummary(id, data, token) {
+    const response = await fetchWithAuth(`${API_BASE_URL}/admin/scholars/${id}/summary`, {
+      method: 'PUT',
+      headers: {
+        'Authorization': `Bearer ${token}`,
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify(data),
+    });
+    await checkResponse(response, '更新摘要失败');
+    return response.json();
+  },
+
+  // Discards the summary so a new one is generated
+  async deleteScholarSummary(id, token) {
+    const response = await fetchWithAuth(`${API_BASE_URL}/admin/scholars/${id}/summary`, {
+      method: 'DELETE',
+      headers: {
+        'Authorization': `Bearer ${token}`,
+      },
+    });
+    await checkResponse(response, '重新生成摘要失败');
+  },
+
   async updateScholar(id, data, token) {
     const response = await fetchWithAuth(`${API_BASE_URL}/admin/scholars/${id}`, {
       method: 'PUT',
@@ -607,6 +667,9 @@ export const api = {
         include_hidden: options.includeHidden !== undefined ? options.includeHidden : false,
         identities: options.identities || null,
         tags: options.tags || null,
+        sticky_scholar_ids: options.stickyScholarIds || null,
+        scholar_ids: options.scholarIds || null,
+        include_pinned_news: options.includePinnedNews || false,
       }),
     });
     await checkResponse(response, '聊天失败');
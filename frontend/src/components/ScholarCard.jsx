@@ -49,6 +49,10 @@ export default function ScholarCard({ scholar }) {
             <p className="text-xs text-gray-500 mb-2 truncate">{scholar.identity.name}</p>
           )}
 
+          {scholar.summary && (
+            <p className="text-xs text-gray-600 mb-2 line-clamp-2">{scholar.summary}</p>
+          )}
+
           {scholar.tags && scholar.tags.length > 0 && (
             <div className="flex gap-1.5">
               {tagDisplayMode === 'count' && (
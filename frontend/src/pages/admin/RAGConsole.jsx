@@ -25,6 +25,9 @@ export default function RAGConsole() {
     tags: [],
   });
   const [expandedMessages, setExpandedMessages] = useState(new Set());
+  // Scholars always sent as context, whatever the question
+  const [pinnedScholars, setPinnedScholars] = useState([]);
+  const [includePinnedNews, setIncludePinnedNews] = useState(false);
   const [newSessionName, setNewSessionName] = useState('');
   const messagesEndRef = useRef(null);
 
@@ -126,9 +129,16 @@ export default function RAGConsole() {
 
     try {
       const token = localStorage.getItem('token');
+      // Keep the scholars of the previous answer in context for follow-up questions
+      const previousScholars = [...messages].reverse().find(m => m.role === 'assistant')?.scholars || [];
       const response = await api.ragChat(
         [...messages.map(m => ({ role: m.role, content: m.content })), { role: 'user', content: userMessage }],
-        filters,
+        {
+          ...filters,
+          stickyScholarIds: previousScholars.map(s => s.id),
+          scholarIds: pinnedScholars.map(s => s.id),
+          includePinnedNews,
+        },
         token
       );
 
@@ -139,6 +149,9 @@ export default function RAGConsole() {
           content: response.message,
           scholars: response.context_scholars,
           contextCount: response.context_count,
+          standaloneQuery: response.standalone_query,
+          toolCalls: response.tool_calls,
+          documents: (response.sources || []).filter(source => source.type !== 'scholar'),
         },
       ]);
     } catch (error) {
@@ -152,6 +165,14 @@ export default function RAGConsole() {
     }
   };
 
+  const togglePinned = (scholar) => {
+    setPinnedScholars(prev =>
+      prev.some(s => s.id === scholar.id)
+        ? prev.filter(s => s.id !== scholar.id)
+        : [...prev, { id: scholar.id, name: scholar.name }]
+    );
+  };
+
   const handleQuickSearch = async () => {
     if (!inputValue.trim() || loading) return;
 
@@ -347,6 +368,50 @@ export default function RAGConsole() {
                       </ReactMarkdown>
                     </div>
 
+                    {/* Database lookups the model made before answering */}
+                    {msg.toolCalls && msg.toolCalls.length > 0 && (
+                      <details className="mt-3 pt-3 border-t border-gray-300 text-xs">
+                        <summary className="cursor-pointer font-semibold text-gray-600">
+                          数据库查询 ({msg.toolCalls.length})
+                        </summary>
+                        <div className="mt-2 space-y-2">
+                          {msg.toolCalls.map((call, cidx) => (
+                            <div key={cidx} className="bg-white/40 p-2 rounded">
+                              <div className="font-mono">
+                                {call.name}({JSON.stringify(call.arguments)})
+                              </div>
+                              {call.error ? (
+                                <div className="text-red-600">{call.error}</div>
+                              ) : (
+                                <pre className="mt-1 max-h-40 overflow-auto whitespace-pre-wrap opacity-75">
+                                  {JSON.stringify(call.result, null, 2)}
+                                </pre>
+                              )}
+                            </div>
+                          ))}
+                        </div>
+                      </details>
+                    )}
+
+                    {/* News and tag descriptions retrieved alongside the scholars */}
+                    {msg.documents && msg.documents.length > 0 && (
+                      <div className="mt-3 pt-3 border-t border-gray-300 text-xs space-y-1">
+                        <div className="font-semibold text-gray-600">相关新闻与标签</div>
+                        {msg.documents.map(doc => (
+                          <div key={`${doc.type}-${doc.id}`}>
+                            {doc.type === 'news' ? (
+                              <a href={doc.url} target="_blank" rel="noopener noreferrer" className="text-blue-600 underline">
+                                [新闻] {doc.title}
+                              </a>
+                            ) : (
+                              <span>[标签] {doc.name}</span>
+                            )}
+                            <span className="ml-1 opacity-60">{(doc.similarity_score * 100).toFixed(1)}%</span>
+                          </div>
+                        ))}
+                      </div>
+                    )}
+
                     {/* Show scholars if available */}
                     {msg.scholars && msg.scholars.length > 0 && (
                       <div className="mt-3 pt-3 border-t border-gray-300">
@@ -365,7 +430,21 @@ export default function RAGConsole() {
                                 onClick={() => window.open(`/scholars/${scholar.id}`, '_blank')}
                                 className="text-sm bg-white/20 p-2 rounded cursor-pointer hover:bg-white/30 transition"
                               >
-                                <div className="font-semibold text-blue-600">{scholar.name}</div>
+                                <div className="flex items-center justify-between gap-2">
+                                  <div className="font-semibold text-blue-600">
+                                    {scholar.name}
+                                    {scholar.pinned && <span className="ml-1 text-xs text-amber-600">(已固定)</span>}
+                                  </div>
+                                  <button
+                                    onClick={(e) => {
+                                      e.stopPropagation();
+                                      togglePinned(scholar);
+                                    }}
+                                    className="text-xs text-gray-600 hover:text-blue-600"
+                                  >
+                                    {pinnedScholars.some(s => s.id === scholar.id) ? '取消固定' : '固定'}
+                                  </button>
+                                </div>
                                 <div className="text-xs opacity-90">
                                   研究领域: {scholar.field_of_research}
                                 </div>
@@ -444,6 +523,32 @@ export default function RAGConsole() {
               </label>
             </div>
 
+            {/* Pinned Scholars */}
+            <div>
+              <div className="block text-sm font-medium text-gray-700 mb-2">固定学者</div>
+              {pinnedScholars.length === 0 ? (
+                <div className="text-xs text-gray-500">在回答的相关学者中点击“固定”，对话将始终包含该学者</div>
+              ) : (
+                <div className="flex flex-wrap gap-1">
+                  {pinnedScholars.map(scholar => (
+                    <span key={scholar.id} className="inline-flex items-center gap-1 px-2 py-1 text-xs bg-amber-100 text-amber-800 rounded">
+                      {scholar.name}
+                      <button onClick={() => togglePinned(scholar)} className="hover:text-amber-950">×</button>
+                    </span>
+                  ))}
+                </div>
+              )}
+              <label className="flex items-center gap-2 cursor-pointer mt-2">
+                <input
+                  type="checkbox"
+                  checked={includePinnedNews}
+                  onChange={(e) => setIncludePinnedNews(e.target.checked)}
+                  className="rounded"
+                />
+                <span className="text-sm text-gray-700">附带固定学者的相关新闻</span>
+              </label>
+            </div>
+
             {/* Threshold Slider */}
             <div>
               <label className="block text-sm font-medium text-gray-700 mb-2">
@@ -20,12 +20,45 @@ export default function ScholarDetailAdmin() {
     url: '',
     publishDate: new Date().toISOString().split('T')[0],
   });
+  const [summary, setSummary] = useState(null);
+  const [summaryDraft, setSummaryDraft] = useState(null);
   const token = localStorage.getItem('token');
 
   useEffect(() => {
     loadScholar();
+    loadSummary();
   }, [id]);
 
+  const loadSummary = async () => {
+    try {
+      setSummary(await api.getScholarSummary(id, token));
+    } catch {
+      // Not generated yet
+      setSummary(null);
+    }
+  };
+
+  const handleSaveSummary = async (changes) => {
+    try {
+      setSummary(await api.updateScholarSummary(id, changes, token));
+      setSummaryDraft(null);
+    } catch (error) {
+      alert('保存失败: ' + error.message);
+    }
+  };
+
+  const handleRegenerateSummary = async () => {
+    if (!confirm('确定要丢弃当前摘要并重新生成吗？')) return;
+
+    try {
+      await api.deleteScholarSummary(id, token);
+      setSummary(null);
+      setSummaryDraft(null);
+    } catch (error) {
+      alert('操作失败: ' + error.message);
+    }
+  };
+
   const loadScholar = async () => {
     try {
       setLoading(true);
@@ -310,6 +343,62 @@ export default function ScholarDetailAdmin() {
             )}
           </div>
 
+          <div className="bg-white rounded-lg shadow p-6">
+            <div className="flex justify-between items-center mb-3">
+              <h3 className="text-lg font-semibold">
+                卡片摘要
+                {summary?.locked && (
+                  <span className="ml-2 text-xs font-normal px-2 py-0.5 rounded bg-yellow-100 text-yellow-800">已锁定</span>
+                )}
+                {summary?.stale && (
+                  <span className="ml-2 text-xs font-normal px-2 py-0.5 rounded bg-gray-100 text-gray-600">简介已更新</span>
+                )}
+              </h3>
+              {summary && summaryDraft === null && (
+                <div className="space-x-2 text-sm">
+                  <button onClick={() => setSummaryDraft(summary.summary)} className="text-blue-600 hover:underline">
+                    编辑
+                  </button>
+                  <button onClick={() => handleSaveSummary({ locked: !summary.locked })} className="text-blue-600 hover:underline">
+                    {summary.locked ? '解锁' : '锁定'}
+                  </button>
+                  {!summary.locked && (
+                    <button onClick={handleRegenerateSummary} className="text-blue-600 hover:underline">
+                      重新生成
+                    </button>
+                  )}
+                </div>
+              )}
+            </div>
+            {summaryDraft !== null ? (
+              <div className="space-y-2">
+                <textarea
+                  value={summaryDraft}
+                  onChange={(e) => setSummaryDraft(e.target.value)}
+                  maxLength={500}
+                  rows={3}
+                  className="w-full border rounded px-3 py-2"
+                />
+                <div className="space-x-2">
+                  <button
+                    onClick={() => handleSaveSummary({ summary: summaryDraft, locked: true })}
+                    disabled={!summaryDraft.trim()}
+                    className="px-3 py-1 bg-blue-600 text-white rounded hover:bg-blue-700 disabled:opacity-50"
+                  >
+                    保存并锁定
+                  </button>
+                  <button onClick={() => setSummaryDraft(null)} className="px-3 py-1 border rounded hover:bg-gray-50">
+                    取消
+                  </button>
+                </div>
+              </div>
+            ) : summary ? (
+              <p className="text-gray-700">{summary.summary}</p>
+            ) : (
+              <p className="text-gray-500 text-sm">摘要尚未生成，将在后台根据简介自动生成。</p>
+            )}
+          </div>
+
           <div className="bg-white rounded-lg shadow p-6">
             <h3 className="text-lg font-semibold mb-3">简介</h3>
             <div className="prose max-w-none">
@@ -25,6 +25,10 @@ export default function ScholarForm() {
   const [loading, setLoading] = useState(false);
   const [dataLoading, setDataLoading] = useState(true);
   const [user, setUser] = useState(null);
+  const [sourceText, setSourceText] = useState('');
+  const [draft, setDraft] = useState(null);
+  const [drafting, setDrafting] = useState(false);
+  const [suggestions, setSuggestions] = useState(null);
   const token = localStorage.getItem('token');
   const navigate = useNavigate();
 
@@ -78,6 +82,11 @@ export default function ScholarForm() {
           visible: scholarData.visible !== undefined ? scholarData.visible : true,
           version: scholarData.version,
         });
+
+        // Suggestions are a convenience; the form works without them
+        api.getScholarSuggestions(id, token)
+          .then(setSuggestions)
+          .catch(error => console.error('获取标签建议失败:', error));
       } else if (identitiesData.data?.length > 0) {
         setForm(f => ({ ...f, identity: identitiesData.data[0].id }));
       }
@@ -89,6 +98,29 @@ export default function ScholarForm() {
     }
   }
 
+  async function handleDraft() {
+    setDrafting(true);
+    try {
+      setDraft(await api.draftScholarProfile(sourceText, id, token));
+    } catch (error) {
+      console.error('生成草稿失败:', error);
+      alert(error.message);
+    } finally {
+      setDrafting(false);
+    }
+  }
+
+  function applyDraft() {
+    setForm(f => ({
+      ...f,
+      introduction: draft.introduction,
+      socialInfluence: draft.socialInfluence,
+      ...(draft.fieldOfResearch && { fieldOfResearch: draft.fieldOfResearch.value }),
+      ...(draft.yearOfBirth && { yearOfBirth: draft.yearOfBirth.value }),
+    }));
+    setDraft(null);
+  }
+
   async function handleSubmit(e) {
     e.preventDefault();
     setLoading(true);
@@ -124,6 +156,67 @@ export default function ScholarForm() {
         <h1 className="text-2xl font-bold">{isEditMode ? '编辑人物' : '创建人物'}</h1>
       </div>
 
+      <div className="bg-white rounded-lg shadow p-6 mb-6 space-y-4">
+        <div>
+          <label className="block mb-2 font-medium">AI 辅助撰写</label>
+          <textarea
+            value={sourceText}
+            onChange={(e) => setSourceText(e.target.value)}
+            className="w-full border rounded px-3 py-2 h-32"
+            placeholder="粘贴新闻稿、简历摘录等资料，生成简介与社会影响草稿（不会自动保存）"
+          />
+        </div>
+        <button
+          type="button"
+          onClick={handleDraft}
+          disabled={drafting || sourceText.trim().length < 20}
+          className="px-4 py-2 bg-purple-600 text-white rounded hover:bg-purple-700 disabled:bg-gray-400"
+        >
+          {drafting ? '生成中...' : '生成草稿'}
+        </button>
+
+        {draft && (
+          <div className="border rounded p-4 space-y-3 text-sm">
+            {draft.fieldOfResearch && (
+              <p>
+                <span className="font-medium">研究领域：</span>{draft.fieldOfResearch.value}
+                <span className="block text-gray-500">依据：“{draft.fieldOfResearch.sourceSentence}”</span>
+              </p>
+            )}
+            {draft.yearOfBirth && (
+              <p>
+                <span className="font-medium">出生年份：</span>{draft.yearOfBirth.value}
+                <span className="block text-gray-500">依据：“{draft.yearOfBirth.sourceSentence}”</span>
+              </p>
+            )}
+            <div>
+              <p className="font-medium">简介</p>
+              <p className="whitespace-pre-wrap text-gray-700">{draft.introduction}</p>
+            </div>
+            <div>
+              <p className="font-medium">社会影响</p>
+              <p className="whitespace-pre-wrap text-gray-700">{draft.socialInfluence}</p>
+            </div>
+            <div className="flex gap-2">
+              <button
+                type="button"
+                onClick={applyDraft}
+                className="px-4 py-2 bg-blue-600 text-white rounded hover:bg-blue-700"
+              >
+                填入表单
+              </button>
+              <button
+                type="button"
+                onClick={() => setDraft(null)}
+                className="px-4 py-2 bg-gray-600 text-white rounded hover:bg-gray-700"
+              >
+                放弃
+              </button>
+            </div>
+          </div>
+        )}
+      </div>
+
       <form onSubmit={handleSubmit} className="bg-white rounded-lg shadow p-6 space-y-6">
         <div>
           <label className="block mb-2 font-medium">姓名 *</label>
@@ -191,6 +284,22 @@ export default function ScholarForm() {
               </option>
             ))}
           </select>
+          {suggestions?.identities.some(s => !s.isCurrent) && (
+            <div className="mt-2 flex flex-wrap items-center gap-2 text-sm">
+              <span className="text-gray-500">相似人物的身份：</span>
+              {suggestions.identities.map(suggestion => (
+                <button
+                  key={suggestion.identityId}
+                  type="button"
+                  title={suggestion.explanation}
+                  onClick={() => setForm({ ...form, identity: suggestion.identityId })}
+                  className={`px-2 py-1 rounded border ${form.identity === suggestion.identityId ? 'bg-blue-50 border-blue-400' : 'hover:bg-gray-50'}`}
+                >
+                  {suggestion.name} {Math.round(suggestion.confidence * 100)}%
+                </button>
+              ))}
+            </div>
+          )}
         </div>
 
         <div>
@@ -219,6 +328,24 @@ export default function ScholarForm() {
               );
             })}
           </div>
+          {suggestions?.tags.length > 0 && (
+            <div className="mt-2 flex flex-wrap items-center gap-2 text-sm">
+              <span className="text-gray-500">建议标签：</span>
+              {suggestions.tags
+                .filter(suggestion => !form.tagIds.includes(suggestion.tagId))
+                .map(suggestion => (
+                  <button
+                    key={suggestion.tagId}
+                    type="button"
+                    title={suggestion.explanation}
+                    onClick={() => setForm({ ...form, tagIds: [...form.tagIds, suggestion.tagId] })}
+                    className="px-2 py-1 rounded border border-dashed hover:bg-gray-50"
+                  >
+                    + {suggestion.name} {Math.round(suggestion.confidence * 100)}%
+                  </button>
+                ))}
+            </div>
+          )}
         </div>
 
         <div>
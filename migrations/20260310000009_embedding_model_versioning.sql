@@ -0,0 +1,57 @@
+-- Track which embedding model produced each vector so that changing
+-- EMBEDDING_MODEL never mixes incompatible vectors in one search
+CREATE TYPE embedding_model_status AS ENUM ('active', 'migrating', 'retired');
+
+CREATE TABLE embedding_models (
+    name VARCHAR(100) PRIMARY KEY,
+    -- Filled in from the first vector the model returns
+    dimensions INTEGER CHECK (dimensions > 0),
+    status embedding_model_status NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    activated_at TIMESTAMPTZ,
+    retired_at TIMESTAMPTZ
+);
+
+-- At most one model serves queries and at most one is being migrated to
+CREATE UNIQUE INDEX idx_embedding_models_active ON embedding_models(status) WHERE status = 'active';
+CREATE UNIQUE INDEX idx_embedding_models_migrating ON embedding_models(status) WHERE status = 'migrating';
+
+ALTER TABLE scholar_embeddings
+    ADD COLUMN model VARCHAR(100),
+    ADD COLUMN dimensions INTEGER;
+
+-- Fold the never-populated legacy column into the embeddings table.
+-- The empty embedding_text makes these rows show up for re-embedding.
+INSERT INTO scholar_embeddings (id, scholar_id, embedding_text, embedding, embedded_at, created_at, updated_at)
+SELECT s.id, s.id, '', s.embedding, NOW(), NOW(), NOW()
+FROM scholars s
+WHERE s.embedding IS NOT NULL
+ON CONFLICT (scholar_id) DO NOTHING;
+
+DROP INDEX IF EXISTS idx_scholars_embedding;
+ALTER TABLE scholars DROP COLUMN IF EXISTS embedding;
+
+-- The previous code did not record which model produced a vector; label them
+-- with the default EMBEDDING_MODEL. A deployment configured with another model
+-- re-embeds them with it on its first start (see sync_embedding_model)
+UPDATE scholar_embeddings
+SET model = 'text-embedding-3-small', dimensions = vector_dims(embedding);
+
+INSERT INTO embedding_models (name, dimensions, status, activated_at)
+SELECT 'text-embedding-3-small', 1536, 'active', NOW()
+WHERE EXISTS (SELECT 1 FROM scholar_embeddings);
+
+ALTER TABLE scholar_embeddings
+    ALTER COLUMN model SET NOT NULL,
+    ALTER COLUMN dimensions SET NOT NULL;
+
+-- A scholar keeps one vector per model while a migration is in progress
+ALTER TABLE scholar_embeddings DROP CONSTRAINT scholar_embeddings_scholar_id_key;
+ALTER TABLE scholar_embeddings
+    ADD CONSTRAINT scholar_embeddings_scholar_model_key UNIQUE (scholar_id, model);
+
+-- ivfflat indexes need a fixed dimension, so the column becomes untyped
+DROP INDEX IF EXISTS idx_scholar_embeddings_embedding;
+ALTER TABLE scholar_embeddings ALTER COLUMN embedding TYPE VECTOR;
+
+CREATE INDEX idx_scholar_embeddings_model ON scholar_embeddings(model);
@@ -0,0 +1,19 @@
+-- Passage-level embeddings: long profiles are split into overlapping chunks
+-- so retrieval can match (and quote) the relevant part of a profile
+CREATE TYPE passage_field AS ENUM ('profile', 'introduction', 'social_influence');
+
+CREATE TABLE scholar_passages (
+    id CHAR(24) PRIMARY KEY,
+    scholar_id CHAR(24) NOT NULL REFERENCES scholars(id) ON DELETE CASCADE,
+    model VARCHAR(100) NOT NULL,
+    dimensions INTEGER NOT NULL,
+    -- Which part of the profile the passage was cut from
+    field passage_field NOT NULL,
+    passage_index INTEGER NOT NULL CHECK (passage_index >= 0),
+    content TEXT NOT NULL,
+    embedding VECTOR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (scholar_id, model, field, passage_index)
+);
+
+CREATE INDEX idx_scholar_passages_model ON scholar_passages(model);
@@ -0,0 +1,16 @@
+-- Hash of the text each vector was produced from, so drift can be detected
+-- when tags, identities or news change. Existing rows count as stale.
+ALTER TABLE scholar_embeddings ADD COLUMN content_hash CHAR(64);
+
+-- Durable queue of scholars whose embeddings need to be refreshed
+CREATE TABLE embedding_refresh_queue (
+    scholar_id CHAR(24) PRIMARY KEY REFERENCES scholars(id) ON DELETE CASCADE,
+    -- What triggered the refresh, e.g. 'tag_updated'
+    reason VARCHAR(50) NOT NULL,
+    queued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_embedding_refresh_queue_next_attempt_at ON embedding_refresh_queue(next_attempt_at);
@@ -0,0 +1,35 @@
+-- Batch embedding runs as a durable job processed by a background worker
+-- instead of inside the HTTP request
+CREATE TYPE embedding_job_status AS ENUM ('queued', 'running', 'completed', 'cancelled', 'failed');
+CREATE TYPE embedding_job_item_status AS ENUM ('pending', 'succeeded', 'failed');
+
+CREATE TABLE embedding_jobs (
+    id CHAR(24) PRIMARY KEY,
+    -- 'missing' or 'all'
+    mode VARCHAR(20) NOT NULL,
+    model VARCHAR(100) NOT NULL,
+    status embedding_job_status NOT NULL DEFAULT 'queued',
+    -- Why the whole job stopped, if it did
+    error TEXT,
+    created_by CHAR(24) REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_embedding_jobs_status ON embedding_jobs(status);
+
+CREATE TABLE embedding_job_items (
+    job_id CHAR(24) NOT NULL REFERENCES embedding_jobs(id) ON DELETE CASCADE,
+    scholar_id CHAR(24) NOT NULL REFERENCES scholars(id) ON DELETE CASCADE,
+    status embedding_job_item_status NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    -- Also serves as a lease: claimed items are pushed into the future so a
+    -- crashed worker's items are picked up again once it expires
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (job_id, scholar_id)
+);
+
+CREATE INDEX idx_embedding_job_items_pending ON embedding_job_items(job_id, next_attempt_at) WHERE status = 'pending';
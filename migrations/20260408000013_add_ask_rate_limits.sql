@@ -0,0 +1,7 @@
+-- Daily request counts per client IP for the public ask endpoint
+CREATE TABLE ask_rate_limits (
+    client_ip TEXT NOT NULL,
+    day DATE NOT NULL DEFAULT CURRENT_DATE,
+    requests INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (client_ip, day)
+);
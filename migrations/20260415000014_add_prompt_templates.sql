@@ -0,0 +1,35 @@
+-- System prompts and generation settings for RAG answers, editable by admins
+-- Templates are immutable versions: edits create a new version, and exactly one
+-- version per use case is active
+CREATE TYPE prompt_use_case AS ENUM ('search', 'chat', 'ask');
+
+CREATE TABLE prompt_templates (
+    id CHAR(24) PRIMARY KEY,
+    use_case prompt_use_case NOT NULL,
+    version INTEGER NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    -- May reference {context} and {query}
+    system_prompt TEXT NOT NULL,
+    temperature REAL NOT NULL,
+    max_tokens INTEGER NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT FALSE,
+    created_by CHAR(24) REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    activated_at TIMESTAMPTZ,
+    UNIQUE (use_case, version)
+);
+
+CREATE UNIQUE INDEX idx_prompt_templates_active ON prompt_templates(use_case) WHERE is_active;
+
+-- The prompts previously hard-coded in the RAG handlers
+INSERT INTO prompt_templates (id, use_case, version, name, system_prompt, temperature, max_tokens, is_active, activated_at)
+VALUES
+    ('defaultsearchprompt00001', 'search', 1, 'Default',
+     E'You are a knowledgeable assistant helping users find information about scholars. You have access to the following scholar information:\n\n{context}\n\nUse this information to answer the user''s query accurately and helpfully. If the information doesn''t directly answer the query, explain what you know about related topics. IMPORTANT: Do not include scholar IDs in your responses.',
+     0.7, 1000, TRUE, NOW()),
+    ('defaultchatprompt0000001', 'chat', 1, 'Default',
+     E'You are a knowledgeable assistant helping users learn about scholars. You have access to the following scholar information:\n\n{context}\n\nUse this information to answer questions accurately. Be helpful and provide detailed information. IMPORTANT: Do not include scholar IDs in your responses.',
+     0.7, 1500, TRUE, NOW()),
+    ('defaultaskprompt00000001', 'ask', 1, 'Default',
+     E'You are a friendly assistant helping visitors of a scholar database learn about scholars. You have access to the following scholar information:\n\n{context}\n\nAnswer only from this information and say so when it does not cover the question. IMPORTANT: Do not include scholar IDs in your responses.',
+     0.7, 1000, TRUE, NOW());
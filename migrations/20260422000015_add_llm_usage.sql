@@ -0,0 +1,21 @@
+-- Every embedding, chat and rerank API call, for cost visibility and per-user quotas
+CREATE TYPE llm_call_kind AS ENUM ('embedding', 'chat', 'rerank');
+
+CREATE TABLE llm_usage (
+    id BIGSERIAL PRIMARY KEY,
+    -- NULL for public endpoints and background work without an initiating user
+    user_id CHAR(24) REFERENCES users(id) ON DELETE SET NULL,
+    endpoint VARCHAR(100) NOT NULL,
+    kind llm_call_kind NOT NULL,
+    model VARCHAR(100) NOT NULL,
+    -- As reported by the API's usage field; NULL when it reported none
+    prompt_tokens INTEGER,
+    completion_tokens INTEGER,
+    latency_ms INTEGER NOT NULL,
+    success BOOLEAN NOT NULL,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_llm_usage_user_created ON llm_usage(user_id, created_at);
+CREATE INDEX idx_llm_usage_created ON llm_usage(created_at);
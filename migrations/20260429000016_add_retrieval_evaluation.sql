@@ -0,0 +1,32 @@
+-- Questions with the scholars retrieval should find for them, and the scored
+-- runs of those questions against past retrieval configurations
+CREATE TABLE eval_questions (
+    id CHAR(24) PRIMARY KEY,
+    question TEXT NOT NULL,
+    expected_scholar_ids CHAR(24)[] NOT NULL,
+    -- Same filters as a RAG search request
+    include_hidden BOOLEAN NOT NULL DEFAULT FALSE,
+    identities TEXT[],
+    tags TEXT[],
+    created_by CHAR(24) REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE eval_runs (
+    id CHAR(24) PRIMARY KEY,
+    -- Retrieval configuration the run measured
+    embedding_model VARCHAR(100) NOT NULL,
+    reranker VARCHAR(20) NOT NULL,
+    k INTEGER NOT NULL,
+    threshold REAL NOT NULL,
+    question_count INTEGER NOT NULL,
+    recall_at_k REAL NOT NULL,
+    mrr REAL NOT NULL,
+    -- Per-question scores, retrieved ids and misses
+    results JSONB NOT NULL,
+    created_by CHAR(24) REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_eval_runs_created ON eval_runs(created_at DESC);
@@ -0,0 +1,21 @@
+-- News items and tag descriptions embedded as retrieval documents of their own,
+-- alongside the scholar passages
+CREATE TYPE rag_document_type AS ENUM ('news', 'tag');
+
+CREATE TABLE rag_documents (
+    id CHAR(24) PRIMARY KEY,
+    doc_type rag_document_type NOT NULL,
+    -- news.id or tags.id depending on doc_type; rows of deleted or archived
+    -- sources are skipped by searches and dropped by the next sync
+    source_id CHAR(24) NOT NULL,
+    model VARCHAR(100) NOT NULL,
+    dimensions INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    -- Hash of content, so changed sources are re-embedded
+    content_hash CHAR(64) NOT NULL,
+    embedding VECTOR NOT NULL,
+    embedded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (doc_type, source_id, model)
+);
+
+CREATE INDEX idx_rag_documents_model ON rag_documents(model);
@@ -0,0 +1,17 @@
+-- Every time a RAG guardrail rejects a message or withholds an answer, for admin review
+CREATE TYPE guardrail_kind AS ENUM ('prompt_injection', 'scholar_id_leak', 'hidden_scholar_leak');
+
+CREATE TABLE guardrail_events (
+    id BIGSERIAL PRIMARY KEY,
+    -- NULL for public endpoints
+    user_id CHAR(24) REFERENCES users(id) ON DELETE SET NULL,
+    endpoint VARCHAR(100) NOT NULL,
+    kind guardrail_kind NOT NULL,
+    -- What matched: the injection pattern, or the leaked id or name
+    detail TEXT NOT NULL,
+    -- The start of the offending message or answer
+    excerpt TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_guardrail_events_created ON guardrail_events(created_at);
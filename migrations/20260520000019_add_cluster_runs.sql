@@ -0,0 +1,20 @@
+-- Topic clusterings of the scholars' profile vectors, kept for taxonomy review
+CREATE TABLE cluster_runs (
+    id CHAR(24) PRIMARY KEY,
+    embedding_model VARCHAR(100) NOT NULL,
+    k INTEGER NOT NULL,
+    -- Seeds the centroid initialisation, so a run can be reproduced
+    seed BIGINT NOT NULL,
+    scholar_count INTEGER NOT NULL,
+    iterations INTEGER NOT NULL,
+    -- Average cosine distance of a scholar to its cluster's centroid
+    mean_distance REAL NOT NULL,
+    -- Clusters without a dominant tag
+    candidate_count INTEGER NOT NULL,
+    -- Per-cluster members, centroid-nearest scholars and tag counts
+    clusters JSONB NOT NULL,
+    created_by CHAR(24) REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_cluster_runs_created ON cluster_runs(created_at DESC);
@@ -0,0 +1,15 @@
+-- Short generated summaries of scholar introductions, shown on scholar cards
+CREATE TABLE scholar_summaries (
+    scholar_id CHAR(24) PRIMARY KEY REFERENCES scholars(id) ON DELETE CASCADE,
+    summary TEXT NOT NULL,
+    -- SHA-256 of the introduction the summary was written from; a mismatch means
+    -- the profile changed and the summary is regenerated unless locked
+    content_hash CHAR(64) NOT NULL,
+    -- Locked summaries are never overwritten by generation
+    locked BOOLEAN NOT NULL DEFAULT false,
+    -- Chat model that wrote the summary; NULL once an editor has rewritten it
+    model VARCHAR(100),
+    edited_by CHAR(24) REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
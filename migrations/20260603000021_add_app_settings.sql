@@ -0,0 +1,6 @@
+-- Small key/value settings the application keeps for itself between restarts
+CREATE TABLE app_settings (
+    key VARCHAR(100) PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,10 @@
+-- Failed summary attempts, so a scholar the LLM keeps failing on backs off instead
+-- of being retried on every pass ahead of everyone else
+CREATE TABLE scholar_summary_failures (
+    scholar_id CHAR(24) PRIMARY KEY REFERENCES scholars(id) ON DELETE CASCADE,
+    -- SHA-256 of the introduction that failed; a changed introduction is tried again at once
+    content_hash CHAR(64) NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
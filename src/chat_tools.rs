@@ -0,0 +1,452 @@
+use chrono::Datelike;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+use crate::models::*;
+use crate::utils::{AppError, AppResult, AppState};
+
+/// Rows a listing tool returns when the model does not ask for a page size
+const DEFAULT_TOOL_PAGE_SIZE: i64 = 10;
+/// Most rows a listing tool returns, to keep results within the model's context
+const MAX_TOOL_PAGE_SIZE: i64 = 20;
+
+/// Appended to the system prompt when the model is offered the tools
+pub const TOOL_INSTRUCTIONS: &str = "\n\nYou can call tools to look up scholars, news, tags and \
+    identities in the database. Use them for counts, lists, dates and other exact facts the \
+    scholar context does not cover, and base such answers on the tool results only.";
+
+/// Read-only database lookups the chat model may call while answering
+/// Every tool sees exactly the scholars the caller could retrieve through RAG
+#[derive(Debug, Clone, Copy)]
+pub struct ChatTools {
+    /// Hidden scholars are visible too; deleted ones never are
+    pub include_hidden: bool,
+}
+
+/// Filters shared by the scholar listing and counting tools
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ScholarFilterArgs {
+    search: Option<String>,
+    name: Option<String>,
+    /// Identity names
+    identities: Option<Vec<String>>,
+    /// Tag names
+    tags: Option<Vec<String>>,
+    gender: Option<Gender>,
+    year_of_birth_from: Option<i32>,
+    year_of_birth_to: Option<i32>,
+    featured: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ListScholarsArgs {
+    #[serde(flatten)]
+    filters: ScholarFilterArgs,
+    sort: Option<String>,
+    order: Option<String>,
+    page: Option<i64>,
+    page_size: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetScholarArgs {
+    id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ListNewsArgs {
+    scholar_id: Option<String>,
+    page: Option<i64>,
+    page_size: Option<i64>,
+}
+
+fn tool(name: &str, description: &str, parameters: Value) -> Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": name,
+            "description": description,
+            "parameters": parameters,
+        }
+    })
+}
+
+fn scholar_filter_properties() -> Value {
+    json!({
+        "search": {"type": "string", "description": "Text matched against name, field of research, introduction, social influence and tags"},
+        "name": {"type": "string", "description": "Part of the scholar's name"},
+        "identities": {"type": "array", "items": {"type": "string"}, "description": "Identity names, as returned by list_identities"},
+        "tags": {"type": "array", "items": {"type": "string"}, "description": "Tag names, as returned by list_tags"},
+        "gender": {"type": "string", "enum": ["M", "F"]},
+        "year_of_birth_from": {"type": "integer", "description": "Earliest year of birth, inclusive"},
+        "year_of_birth_to": {"type": "integer", "description": "Latest year of birth, inclusive"},
+        "featured": {"type": "boolean"}
+    })
+}
+
+fn pagination(page: Option<i64>, page_size: Option<i64>) -> PaginationParams {
+    PaginationParams {
+        page: page.unwrap_or(1).max(1),
+        page_size: page_size
+            .unwrap_or(DEFAULT_TOOL_PAGE_SIZE)
+            .clamp(1, MAX_TOOL_PAGE_SIZE),
+    }
+}
+
+fn parse_arguments<T: DeserializeOwned>(name: &str, arguments: &str) -> AppResult<T> {
+    let arguments = if arguments.trim().is_empty() {
+        "{}"
+    } else {
+        arguments
+    };
+    serde_json::from_str(arguments)
+        .map_err(|e| AppError::BadRequest(format!("Invalid arguments for {}: {}", name, e)))
+}
+
+/// Resolve names case-insensitively to ids, naming the valid choices on a miss
+fn resolve_names(
+    kind: &str,
+    names: &[String],
+    known: &HashMap<String, String>,
+) -> AppResult<Vec<String>> {
+    names
+        .iter()
+        .map(|name| {
+            known
+                .iter()
+                .find(|(_, known_name)| known_name.eq_ignore_ascii_case(name.trim()))
+                .map(|(id, _)| id.clone())
+                .ok_or_else(|| {
+                    let mut choices: Vec<&str> = known.values().map(String::as_str).collect();
+                    choices.sort_unstable();
+                    AppError::BadRequest(format!(
+                        "Unknown {} \"{}\"; known: {}",
+                        kind,
+                        name,
+                        choices.join(", ")
+                    ))
+                })
+        })
+        .collect()
+}
+
+impl ChatTools {
+    /// OpenAI-style function definitions of the whitelisted tools
+    pub fn definitions() -> Value {
+        let filters = scholar_filter_properties();
+        let mut list_properties = filters.clone();
+        if let (Value::Object(properties), Value::Object(paging)) = (
+            &mut list_properties,
+            json!({
+                "sort": {"type": "string", "enum": ["name", "year_of_birth", "created_at"]},
+                "order": {"type": "string", "enum": ["asc", "desc"]},
+                "page": {"type": "integer", "minimum": 1},
+                "page_size": {"type": "integer", "minimum": 1, "maximum": MAX_TOOL_PAGE_SIZE}
+            }),
+        ) {
+            properties.extend(paging);
+        }
+
+        json!([
+            tool(
+                "list_scholars",
+                "List scholars matching filters, with their identity and tags.",
+                json!({"type": "object", "properties": list_properties}),
+            ),
+            tool(
+                "count_scholars",
+                "Count scholars matching filters.",
+                json!({"type": "object", "properties": filters}),
+            ),
+            tool(
+                "get_scholar",
+                "Get one scholar's full profile and linked news by id.",
+                json!({
+                    "type": "object",
+                    "properties": {"id": {"type": "string"}},
+                    "required": ["id"]
+                }),
+            ),
+            tool(
+                "list_news",
+                "List news items, newest first, optionally only those about one scholar.",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "scholar_id": {"type": "string"},
+                        "page": {"type": "integer", "minimum": 1},
+                        "page_size": {"type": "integer", "minimum": 1, "maximum": MAX_TOOL_PAGE_SIZE}
+                    }
+                }),
+            ),
+            tool(
+                "list_tags",
+                "List all tags with their descriptions.",
+                json!({"type": "object", "properties": {}}),
+            ),
+            tool(
+                "list_identities",
+                "List all identities with their descriptions.",
+                json!({"type": "object", "properties": {}}),
+            ),
+        ])
+    }
+
+    /// Run one tool call; `arguments` is the JSON object the model produced
+    /// Unknown tools and invalid arguments are BadRequest errors the model can recover from
+    pub async fn execute(
+        &self,
+        app_state: &AppState,
+        name: &str,
+        arguments: &str,
+    ) -> AppResult<Value> {
+        match name {
+            "list_scholars" => {
+                let args: ListScholarsArgs = parse_arguments(name, arguments)?;
+                self.list_scholars(app_state, args).await
+            }
+            "count_scholars" => {
+                let args: ScholarFilterArgs = parse_arguments(name, arguments)?;
+                let identities = identity_names(app_state).await?;
+                let query = scholar_query(
+                    app_state,
+                    args,
+                    &identities,
+                    PaginationParams {
+                        page: 1,
+                        page_size: 1,
+                    },
+                )
+                .await?;
+                let count = app_state
+                    .db
+                    .count_scholars(&query, !self.include_hidden)
+                    .await?;
+                Ok(json!({ "count": count }))
+            }
+            "get_scholar" => {
+                let args: GetScholarArgs = parse_arguments(name, arguments)?;
+                self.get_scholar(app_state, &args.id).await
+            }
+            "list_news" => {
+                let args: ListNewsArgs = parse_arguments(name, arguments)?;
+                self.list_news(app_state, args).await
+            }
+            "list_tags" => {
+                let tags = app_state.db.list_tags(None).await?;
+                Ok(Value::Array(
+                    tags.into_iter()
+                        .filter(|tag| tag.archived_at.is_none())
+                        .map(|tag| json!({ "name": tag.name, "description": tag.description }))
+                        .collect(),
+                ))
+            }
+            "list_identities" => {
+                let identities = app_state.db.list_identities().await?;
+                Ok(Value::Array(
+                    identities
+                        .into_iter()
+                        .filter(|identity| identity.archived_at.is_none())
+                        .map(|identity| {
+                            json!({ "name": identity.name, "description": identity.description })
+                        })
+                        .collect(),
+                ))
+            }
+            _ => Err(AppError::BadRequest(format!("Unknown tool {}", name))),
+        }
+    }
+
+    async fn list_scholars(
+        &self,
+        app_state: &AppState,
+        args: ListScholarsArgs,
+    ) -> AppResult<Value> {
+        let identities = identity_names(app_state).await?;
+        let mut query = scholar_query(
+            app_state,
+            args.filters,
+            &identities,
+            pagination(args.page, args.page_size),
+        )
+        .await?;
+        query.sort = match args.sort.as_deref() {
+            Some(sort @ ("year_of_birth" | "created_at")) => sort.to_string(),
+            _ => "name".to_string(),
+        };
+        query.order = match args.order.as_deref() {
+            Some("desc") => "desc".to_string(),
+            _ => "asc".to_string(),
+        };
+
+        let (scholars, total) = app_state
+            .db
+            .list_scholars(&query, !self.include_hidden)
+            .await?;
+        let scholars: Vec<Value> = scholars
+            .into_iter()
+            .map(|item| {
+                let scholar = item.scholar;
+                json!({
+                    "id": scholar.id,
+                    "name": scholar.name,
+                    "gender": scholar.gender,
+                    "field_of_research": scholar.field_of_research,
+                    "year_of_birth": scholar.year_of_birth,
+                    "identity": identities.get(&scholar.identity),
+                    "tags": item.tags.into_iter().map(|tag| tag.name).collect::<Vec<_>>(),
+                    "visible": scholar.visible,
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "total": total,
+            "page": query.pagination.page,
+            "page_size": query.pagination.page_size,
+            "scholars": scholars,
+        }))
+    }
+
+    /// The same lookup the scholar pages use, public unless hidden scholars are visible
+    async fn visible_scholar(&self, app_state: &AppState, id: &str) -> AppResult<ScholarResponse> {
+        app_state
+            .db
+            .get_scholar_visible(id, self.include_hidden)
+            .await
+    }
+
+    async fn get_scholar(&self, app_state: &AppState, id: &str) -> AppResult<Value> {
+        let scholar = self.visible_scholar(app_state, id).await?;
+
+        Ok(json!({
+            "id": scholar.id,
+            "name": scholar.name,
+            "gender": scholar.gender,
+            "field_of_research": scholar.field_of_research,
+            "year_of_birth": scholar.year_of_birth,
+            "identity": scholar.identity.name,
+            "tags": scholar.tags.into_iter().map(|tag| tag.name).collect::<Vec<_>>(),
+            "introduction": scholar.introduction,
+            "social_influence": scholar.social_influence,
+            "visible": scholar.visible,
+            "news": scholar
+                .news
+                .into_iter()
+                .map(|news| json!({
+                    "title": news.title,
+                    "source": news.source,
+                    "url": news.url,
+                    "publish_date": news.publish_date,
+                }))
+                .collect::<Vec<_>>(),
+        }))
+    }
+
+    async fn list_news(&self, app_state: &AppState, args: ListNewsArgs) -> AppResult<Value> {
+        // News of a scholar the caller cannot see would reveal the scholar
+        if let Some(scholar_id) = &args.scholar_id {
+            self.visible_scholar(app_state, scholar_id).await?;
+        }
+
+        let query = NewsQuery {
+            pagination: pagination(args.page, args.page_size),
+            scholar_id: args.scholar_id,
+        };
+        let (news, total) = app_state.db.list_news(&query).await?;
+
+        Ok(json!({
+            "total": total,
+            "page": query.pagination.page,
+            "page_size": query.pagination.page_size,
+            "news": news
+                .into_iter()
+                .map(|news| json!({
+                    "id": news.id,
+                    "title": news.title,
+                    "source": news.source,
+                    "url": news.url,
+                    "publish_date": news.publish_date,
+                }))
+                .collect::<Vec<_>>(),
+        }))
+    }
+}
+
+/// Identity names keyed by id
+async fn identity_names(app_state: &AppState) -> AppResult<HashMap<String, String>> {
+    Ok(app_state
+        .db
+        .list_identities()
+        .await?
+        .into_iter()
+        .map(|identity| (identity.id, identity.name))
+        .collect())
+}
+
+/// Translate the model's filters into the query the scholar listing endpoints use
+async fn scholar_query(
+    app_state: &AppState,
+    args: ScholarFilterArgs,
+    identities: &HashMap<String, String>,
+    pagination: PaginationParams,
+) -> AppResult<ScholarQuery> {
+    let identities = match &args.identities {
+        Some(names) if !names.is_empty() => Some(resolve_names("identity", names, identities)?),
+        _ => None,
+    };
+
+    let tags = match &args.tags {
+        Some(names) if !names.is_empty() => {
+            let known = app_state
+                .db
+                .list_tags(None)
+                .await?
+                .into_iter()
+                .map(|tag| (tag.id, tag.name))
+                .collect();
+            Some(resolve_names("tag", names, &known)?)
+        }
+        _ => None,
+    };
+
+    // The listing filters on exact years, so a range becomes the list of its years
+    let years_of_birth = if args.year_of_birth_from.is_some() || args.year_of_birth_to.is_some() {
+        let from = args
+            .year_of_birth_from
+            .unwrap_or(MIN_YEAR_OF_BIRTH)
+            .max(MIN_YEAR_OF_BIRTH);
+        let to = args
+            .year_of_birth_to
+            .unwrap_or_else(|| chrono::Utc::now().year())
+            .min(chrono::Utc::now().year());
+        if from > to {
+            return Err(AppError::BadRequest(format!(
+                "No scholars can be born between {} and {}",
+                from, to
+            )));
+        }
+        Some((from..=to).collect())
+    } else {
+        None
+    };
+
+    Ok(ScholarQuery {
+        pagination,
+        search: args.search,
+        name: args.name,
+        tags,
+        identities,
+        years_of_birth,
+        gender: args.gender,
+        news: None,
+        featured: args.featured,
+        sort: "name".to_string(),
+        order: "asc".to_string(),
+    })
+}
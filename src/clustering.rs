@@ -0,0 +1,305 @@
+use chrono::Utc;
+use std::collections::HashMap;
+
+use crate::handlers::rag::active_embedding_model;
+use crate::models::{
+    ClusterMember, ClusterRun, ClusterRunRequest, ClusterRunSummary, ClusterTagCount, MAX_CLUSTERS,
+    ScholarCluster, ScholarVector, TagListItem,
+};
+use crate::utils::{AppError, AppResult, AppState};
+
+/// Assignment rounds before k-means gives up on converging
+const MAX_ITERATIONS: usize = 100;
+/// Members listed as a cluster's most typical scholars
+const CENTROID_NEAREST: usize = 5;
+const TOP_TAGS: usize = 5;
+/// Share of members a tag needs to describe a cluster
+const DOMINANT_TAG_SHARE: f32 = 0.5;
+/// Smaller clusters without a dominant tag are too thin to suggest a new tag
+const MIN_CANDIDATE_SIZE: usize = 3;
+
+/// Cluster the profile vectors of every embedded scholar with the active model and
+/// store the run, with each cluster's typical scholars and most common tags
+pub async fn run_scholar_clustering(
+    app_state: &AppState,
+    created_by: Option<&str>,
+    request: &ClusterRunRequest,
+) -> AppResult<ClusterRun> {
+    let model = active_embedding_model(app_state).await?;
+    let scholars = app_state.db.get_scholar_vectors(&model, true).await?;
+    if scholars.len() < 2 {
+        return Err(AppError::BadRequest(
+            "At least two embedded scholars are needed for clustering".to_string(),
+        ));
+    }
+
+    let k = request.k.unwrap_or_else(|| default_k(scholars.len()));
+    if !(2..=MAX_CLUSTERS.min(scholars.len())).contains(&k) {
+        return Err(AppError::ValidationError(format!(
+            "k must be between 2 and {}",
+            MAX_CLUSTERS.min(scholars.len())
+        )));
+    }
+    let seed = request
+        .seed
+        .unwrap_or_else(|| Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64);
+
+    // Thousands of high-dimensional vectors take a while; keep them off the async workers
+    let points: Vec<Vec<f32>> = scholars
+        .iter()
+        .map(|scholar| normalize(scholar.embedding.clone()))
+        .collect();
+    let result = tokio::task::spawn_blocking(move || {
+        let result = spherical_kmeans(&points, k, seed);
+        let distances: Vec<f32> = points
+            .iter()
+            .zip(&result.assignments)
+            .map(|(point, &cluster)| 1.0 - dot(point, &result.centroids[cluster]))
+            .collect();
+        (result, distances)
+    })
+    .await
+    .map_err(|e| AppError::InternalError(format!("Clustering failed: {}", e)))?;
+    let (result, distances) = result;
+
+    let ids: Vec<String> = scholars.iter().map(|scholar| scholar.id.clone()).collect();
+    let tags = app_state.db.get_active_tags_by_scholar(&ids).await?;
+    let mut clusters = build_clusters(&scholars, &result.assignments, &distances, k, &tags);
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.size));
+    for (index, cluster) in clusters.iter_mut().enumerate() {
+        cluster.index = index as i32;
+    }
+
+    let summary = ClusterRunSummary {
+        id: cuid2::create_id(),
+        embedding_model: model,
+        k: k as i32,
+        seed: seed as i64,
+        scholar_count: scholars.len() as i32,
+        iterations: result.iterations as i32,
+        mean_distance: distances.iter().sum::<f32>() / distances.len() as f32,
+        candidate_count: clusters.iter().filter(|c| c.tag_candidate).count() as i32,
+        created_by: created_by.map(str::to_string),
+        created_at: Utc::now(),
+    };
+    app_state.db.create_cluster_run(&summary, &clusters).await?;
+
+    Ok(ClusterRun {
+        summary,
+        clusters: sqlx::types::Json(clusters),
+    })
+}
+
+/// Rule-of-thumb cluster count: the square root of half the scholars
+fn default_k(scholars: usize) -> usize {
+    ((scholars as f64 / 2.0).sqrt().round() as usize)
+        .clamp(2, MAX_CLUSTERS)
+        .min(scholars)
+}
+
+fn build_clusters(
+    scholars: &[ScholarVector],
+    assignments: &[usize],
+    distances: &[f32],
+    k: usize,
+    tags: &HashMap<String, Vec<TagListItem>>,
+) -> Vec<ScholarCluster> {
+    let mut members: Vec<Vec<ClusterMember>> = vec![Vec::new(); k];
+    for ((scholar, &cluster), &distance) in scholars.iter().zip(assignments).zip(distances) {
+        members[cluster].push(ClusterMember {
+            id: scholar.id.clone(),
+            name: scholar.name.clone(),
+            distance,
+        });
+    }
+
+    members
+        .into_iter()
+        .filter(|members| !members.is_empty())
+        .map(|mut members| {
+            members.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+            let size = members.len();
+
+            let mut counts: HashMap<&str, (&TagListItem, i32)> = HashMap::new();
+            for member in &members {
+                for tag in tags.get(&member.id).into_iter().flatten() {
+                    counts.entry(tag.id.as_str()).or_insert((tag, 0)).1 += 1;
+                }
+            }
+            let mut top_tags: Vec<ClusterTagCount> = counts
+                .into_values()
+                .map(|(tag, count)| ClusterTagCount {
+                    tag_id: tag.id.clone(),
+                    name: tag.name.clone(),
+                    count,
+                    share: count as f32 / size as f32,
+                })
+                .collect();
+            top_tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+            top_tags.truncate(TOP_TAGS);
+
+            let dominant_tag = top_tags
+                .first()
+                .filter(|tag| tag.share >= DOMINANT_TAG_SHARE)
+                .cloned();
+            ScholarCluster {
+                index: 0,
+                size: size as i32,
+                centroid_nearest: members.iter().take(CENTROID_NEAREST).cloned().collect(),
+                top_tags,
+                tag_candidate: dominant_tag.is_none() && size >= MIN_CANDIDATE_SIZE,
+                dominant_tag,
+                members,
+            }
+        })
+        .collect()
+}
+
+struct KMeansResult {
+    assignments: Vec<usize>,
+    /// Unit length, like the points
+    centroids: Vec<Vec<f32>>,
+    iterations: usize,
+}
+
+/// k-means on unit vectors with cosine similarity (spherical k-means), seeded with
+/// k-means++; a cluster left empty takes over the point farthest from its centroid
+fn spherical_kmeans(points: &[Vec<f32>], k: usize, seed: u64) -> KMeansResult {
+    let mut rng = SplitMix64(seed);
+    let mut centroids = kmeans_plus_plus(points, k, &mut rng);
+    let mut assignments = vec![usize::MAX; points.len()];
+    let mut iterations = 0;
+
+    while iterations < MAX_ITERATIONS {
+        iterations += 1;
+        let mut changed = false;
+        for (point, assignment) in points.iter().zip(assignments.iter_mut()) {
+            let nearest = nearest_centroid(point, &centroids);
+            if *assignment != nearest {
+                *assignment = nearest;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+
+        let dimensions = points[0].len();
+        let mut sums = vec![vec![0.0f32; dimensions]; k];
+        let mut sizes = vec![0usize; k];
+        for (point, &cluster) in points.iter().zip(&assignments) {
+            sizes[cluster] += 1;
+            for (sum, value) in sums[cluster].iter_mut().zip(point) {
+                *sum += value;
+            }
+        }
+        for cluster in 0..k {
+            if sizes[cluster] > 0 {
+                centroids[cluster] = normalize(std::mem::take(&mut sums[cluster]));
+                continue;
+            }
+            // The worst-fitting point starts the empty cluster over
+            let farthest = (0..points.len())
+                .filter(|&i| sizes[assignments[i]] > 1)
+                .min_by(|&a, &b| {
+                    dot(&points[a], &centroids[assignments[a]])
+                        .total_cmp(&dot(&points[b], &centroids[assignments[b]]))
+                });
+            if let Some(point) = farthest {
+                sizes[assignments[point]] -= 1;
+                sizes[cluster] = 1;
+                assignments[point] = cluster;
+                centroids[cluster] = points[point].clone();
+            }
+        }
+    }
+
+    KMeansResult {
+        assignments,
+        centroids,
+        iterations,
+    }
+}
+
+/// Centroids spread out by drawing each next one with probability proportional to
+/// its squared distance from the ones already chosen
+fn kmeans_plus_plus(points: &[Vec<f32>], k: usize, rng: &mut SplitMix64) -> Vec<Vec<f32>> {
+    let mut centroids = vec![points[rng.below(points.len())].clone()];
+    let mut distances: Vec<f32> = points
+        .iter()
+        .map(|point| squared_distance(point, &centroids[0]))
+        .collect();
+
+    while centroids.len() < k {
+        let total: f64 = distances.iter().map(|&d| d as f64).sum();
+        let next = if total > 0.0 {
+            let mut target = rng.next_f64() * total;
+            distances
+                .iter()
+                .position(|&d| {
+                    target -= d as f64;
+                    target <= 0.0
+                })
+                .unwrap_or(points.len() - 1)
+        } else {
+            // Every point sits on a centroid already; duplicates are all that is left
+            rng.below(points.len())
+        };
+
+        let centroid = points[next].clone();
+        for (distance, point) in distances.iter_mut().zip(points) {
+            *distance = distance.min(squared_distance(point, &centroid));
+        }
+        centroids.push(centroid);
+    }
+
+    centroids
+}
+
+fn nearest_centroid(point: &[f32], centroids: &[Vec<f32>]) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| dot(point, a).total_cmp(&dot(point, b)))
+        .map(|(index, _)| index)
+        .unwrap_or_default()
+}
+
+pub(crate) fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// For unit vectors, twice the cosine distance
+fn squared_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+pub(crate) fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = dot(&vector, &vector).sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|value| *value /= norm);
+    }
+    vector
+}
+
+/// Small seeded generator, so a run's initialisation can be reproduced from its seed
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
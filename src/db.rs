@@ -1,13 +1,22 @@
 use sqlx::{PgPool, postgres::PgPoolOptions};
 use std::time::Duration;
 
+pub use vector_index::VectorIndexSettings;
+
 #[derive(Clone)]
 pub struct Database {
     pub pool: PgPool,
+    pub vector_index: VectorIndexSettings,
+    /// Whether pgvector can keep scanning an HNSW index until a filtered query has
+    /// enough rows (0.8 and later)
+    pub iterative_scan: bool,
 }
 
 impl Database {
-    pub async fn new(database_url: &str) -> Result<Database, sqlx::Error> {
+    pub async fn new(
+        database_url: &str,
+        vector_index: VectorIndexSettings,
+    ) -> Result<Database, sqlx::Error> {
         let pool = PgPoolOptions::new()
             .max_connections(20)
             .min_connections(5)
@@ -24,15 +33,43 @@ impl Database {
             .expect("Failed to run database migrations");
         log::info!("Database migrations completed successfully");
 
-        Ok(Database { pool })
+        let (pgvector_version,): (String,) =
+            sqlx::query_as("SELECT extversion FROM pg_extension WHERE extname = 'vector'")
+                .fetch_one(&pool)
+                .await?;
+        let iterative_scan = vector_index::supports_iterative_scan(&pgvector_version);
+        if !iterative_scan {
+            log::warn!(
+                "pgvector {} has no iterative index scans; filtered vector searches scan exactly",
+                pgvector_version
+            );
+        }
+
+        Ok(Database {
+            pool,
+            vector_index,
+            iterative_scan,
+        })
     }
 }
 
+pub mod clusters;
+pub mod embedding_jobs;
+pub mod evaluations;
+pub mod guardrails;
 pub mod identities;
 pub mod images;
+pub mod llm_usage;
 pub mod news;
+pub mod prompt_templates;
 pub mod rag;
+pub mod rag_documents;
+pub mod rag_inspection;
+pub mod rate_limits;
 pub mod refresh_tokens;
+pub mod scholar_map;
+pub mod scholar_summaries;
 pub mod scholars;
 pub mod tags;
 pub mod users;
+pub mod vector_index;
@@ -0,0 +1,85 @@
+use sqlx::types::Json;
+
+use crate::models::*;
+use crate::utils::{AppError, AppResult};
+
+/// Run columns without the clusters
+const RUN_SUMMARY_COLUMNS: &str = "id, embedding_model, k, seed, scholar_count, iterations,
+    mean_distance, candidate_count, created_by, created_at";
+
+impl super::Database {
+    /// Profile vectors of every non-deleted scholar for one model
+    pub async fn get_scholar_vectors(
+        &self,
+        model: &str,
+        include_hidden: bool,
+    ) -> AppResult<Vec<ScholarVector>> {
+        let rows: Vec<(String, String, pgvector::Vector)> = sqlx::query_as(
+            "SELECT s.id, s.name, se.embedding
+            FROM scholar_embeddings se
+            INNER JOIN scholars s ON s.id = se.scholar_id
+            WHERE se.model = $1 AND s.deleted = false AND (s.visible = true OR $2)
+            ORDER BY s.id",
+        )
+        .bind(model)
+        .bind(include_hidden)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, embedding)| ScholarVector {
+                id,
+                name,
+                embedding: embedding.to_vec(),
+            })
+            .collect())
+    }
+
+    pub async fn create_cluster_run(
+        &self,
+        summary: &ClusterRunSummary,
+        clusters: &[ScholarCluster],
+    ) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO cluster_runs (id, embedding_model, k, seed, scholar_count, iterations, mean_distance, candidate_count, clusters, created_by, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+        )
+        .bind(&summary.id)
+        .bind(&summary.embedding_model)
+        .bind(summary.k)
+        .bind(summary.seed)
+        .bind(summary.scholar_count)
+        .bind(summary.iterations)
+        .bind(summary.mean_distance)
+        .bind(summary.candidate_count)
+        .bind(Json(clusters))
+        .bind(&summary.created_by)
+        .bind(summary.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Most recent runs first
+    pub async fn list_cluster_runs(&self, limit: i64) -> AppResult<Vec<ClusterRunSummary>> {
+        let runs = sqlx::query_as::<_, ClusterRunSummary>(&format!(
+            "SELECT {} FROM cluster_runs ORDER BY created_at DESC LIMIT $1",
+            RUN_SUMMARY_COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(runs)
+    }
+
+    pub async fn get_cluster_run(&self, id: &str) -> AppResult<ClusterRun> {
+        sqlx::query_as::<_, ClusterRun>("SELECT * FROM cluster_runs WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Cluster run {} not found", id)))
+    }
+}
@@ -0,0 +1,272 @@
+use crate::models::*;
+use crate::utils::{AppError, AppResult};
+
+/// Jobs with their progress counted from the items
+const JOB_SELECT: &str = "SELECT j.*,
+        COUNT(i.scholar_id) AS total,
+        COUNT(i.scholar_id) FILTER (WHERE i.status = 'pending') AS pending,
+        COUNT(i.scholar_id) FILTER (WHERE i.status = 'succeeded') AS succeeded,
+        COUNT(i.scholar_id) FILTER (WHERE i.status = 'failed') AS failed
+    FROM embedding_jobs j
+    LEFT JOIN embedding_job_items i ON i.job_id = j.id";
+
+impl super::Database {
+    /// Create a job with one pending item per scholar
+    /// A job without scholars is completed right away
+    pub async fn create_embedding_job(
+        &self,
+        mode: &str,
+        model: &str,
+        scholar_ids: &[String],
+        created_by: &str,
+    ) -> AppResult<EmbeddingJob> {
+        let id = cuid2::create_id();
+        let mut tx = self.pool.begin().await?;
+
+        let status = if scholar_ids.is_empty() {
+            EmbeddingJobStatus::Completed
+        } else {
+            EmbeddingJobStatus::Queued
+        };
+
+        sqlx::query(
+            "INSERT INTO embedding_jobs (id, mode, model, status, created_by, created_at, finished_at)
+            VALUES ($1, $2, $3, $4, $5, NOW(), CASE WHEN $4 = 'completed'::embedding_job_status THEN NOW() END)"
+        )
+        .bind(&id)
+        .bind(mode)
+        .bind(model)
+        .bind(status)
+        .bind(created_by)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO embedding_job_items (job_id, scholar_id)
+            SELECT $1, unnest($2::char(24)[])",
+        )
+        .bind(&id)
+        .bind(scholar_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.get_embedding_job(&id).await
+    }
+
+    pub async fn get_embedding_job(&self, id: &str) -> AppResult<EmbeddingJob> {
+        let job = sqlx::query_as::<_, EmbeddingJob>(&format!(
+            "{} WHERE j.id = $1 GROUP BY j.id",
+            JOB_SELECT
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Embedding job {} not found", id)))?;
+
+        Ok(job)
+    }
+
+    /// Most recent jobs first
+    pub async fn list_embedding_jobs(&self, limit: i64) -> AppResult<Vec<EmbeddingJob>> {
+        let jobs = sqlx::query_as::<_, EmbeddingJob>(&format!(
+            "{} GROUP BY j.id ORDER BY j.created_at DESC LIMIT $1",
+            JOB_SELECT
+        ))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(jobs)
+    }
+
+    /// Items that failed for good or are waiting for a retry
+    pub async fn get_embedding_job_failures(
+        &self,
+        job_id: &str,
+    ) -> AppResult<Vec<EmbeddingJobItemFailure>> {
+        let failures = sqlx::query_as::<_, EmbeddingJobItemFailure>(
+            "SELECT i.scholar_id, s.name AS scholar_name, i.status, i.attempts, i.last_error, i.next_attempt_at
+            FROM embedding_job_items i
+            INNER JOIN scholars s ON s.id = i.scholar_id
+            WHERE i.job_id = $1 AND i.last_error IS NOT NULL
+            ORDER BY i.status DESC, s.name"
+        )
+        .bind(job_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(failures)
+    }
+
+    /// Stop a queued or running job; items already embedded are kept
+    pub async fn cancel_embedding_job(&self, id: &str) -> AppResult<EmbeddingJob> {
+        let result = sqlx::query(
+            "UPDATE embedding_jobs SET status = 'cancelled', finished_at = NOW()
+            WHERE id = $1 AND status IN ('queued', 'running')",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        let job = self.get_embedding_job(id).await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::Conflict(format!(
+                "Embedding job {} has already finished",
+                id
+            )));
+        }
+
+        Ok(job)
+    }
+
+    pub async fn get_embedding_job_status(&self, id: &str) -> AppResult<EmbeddingJobStatus> {
+        let (status,): (EmbeddingJobStatus,) =
+            sqlx::query_as("SELECT status FROM embedding_jobs WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Embedding job {} not found", id)))?;
+
+        Ok(status)
+    }
+
+    /// Oldest unfinished job that has items due for processing
+    pub async fn get_next_embedding_job(&self) -> AppResult<Option<EmbeddingJob>> {
+        let id: Option<(String,)> = sqlx::query_as(
+            "SELECT j.id FROM embedding_jobs j
+            WHERE j.status IN ('queued', 'running')
+            AND EXISTS (
+                SELECT 1 FROM embedding_job_items i
+                WHERE i.job_id = j.id AND i.status = 'pending' AND i.next_attempt_at <= NOW()
+            )
+            ORDER BY j.created_at
+            LIMIT 1",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match id {
+            Some((id,)) => Ok(Some(self.get_embedding_job(&id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn start_embedding_job(&self, id: &str) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE embedding_jobs SET status = 'running', started_at = NOW()
+            WHERE id = $1 AND status = 'queued'",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Claim due items of a job, leasing them for `lease_secs` so that concurrent
+    /// workers skip them and a crashed worker's items become due again
+    pub async fn claim_embedding_job_items(
+        &self,
+        job_id: &str,
+        limit: i64,
+        lease_secs: i64,
+    ) -> AppResult<Vec<EmbeddingJobItem>> {
+        let items = sqlx::query_as::<_, EmbeddingJobItem>(
+            "UPDATE embedding_job_items SET
+                next_attempt_at = NOW() + $3 * INTERVAL '1 second',
+                updated_at = NOW()
+            WHERE job_id = $1 AND scholar_id IN (
+                SELECT scholar_id FROM embedding_job_items
+                WHERE job_id = $1 AND status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at, scholar_id
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING scholar_id, attempts",
+        )
+        .bind(job_id)
+        .bind(limit)
+        .bind(lease_secs as f64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    pub async fn complete_embedding_job_item(
+        &self,
+        job_id: &str,
+        scholar_id: &str,
+    ) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE embedding_job_items SET status = 'succeeded', updated_at = NOW()
+            WHERE job_id = $1 AND scholar_id = $2",
+        )
+        .bind(job_id)
+        .bind(scholar_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt; the item is retried after `retry_in_secs`, or fails for good without it
+    pub async fn fail_embedding_job_item(
+        &self,
+        job_id: &str,
+        scholar_id: &str,
+        error: &str,
+        retry_in_secs: Option<i64>,
+    ) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE embedding_job_items SET
+                attempts = attempts + 1,
+                last_error = $3,
+                status = CASE WHEN $4::float8 IS NULL THEN 'failed'::embedding_job_item_status ELSE status END,
+                next_attempt_at = NOW() + COALESCE($4::float8, 0) * INTERVAL '1 second',
+                updated_at = NOW()
+            WHERE job_id = $1 AND scholar_id = $2"
+        )
+        .bind(job_id)
+        .bind(scholar_id)
+        .bind(error)
+        .bind(retry_in_secs.map(|secs| secs as f64))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark a running job completed once none of its items are pending
+    pub async fn finish_embedding_job_if_done(&self, id: &str) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE embedding_jobs SET status = 'completed', finished_at = NOW()
+            WHERE id = $1 AND status = 'running'
+            AND NOT EXISTS (
+                SELECT 1 FROM embedding_job_items
+                WHERE job_id = $1 AND status = 'pending'
+            )",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Stop a job that cannot continue, e.g. because its model was retired
+    pub async fn fail_embedding_job(&self, id: &str, error: &str) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE embedding_jobs SET status = 'failed', error = $2, finished_at = NOW()
+            WHERE id = $1 AND status IN ('queued', 'running')",
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use sqlx::types::Json;
+
+use crate::models::*;
+use crate::utils::{AppError, AppResult};
+
+/// Run columns without the per-question results
+const RUN_SUMMARY_COLUMNS: &str = "id, embedding_model, reranker, k, threshold, question_count,
+    recall_at_k, mrr, created_by, created_at";
+
+impl super::Database {
+    pub async fn list_eval_questions(&self) -> AppResult<Vec<EvalQuestion>> {
+        let questions =
+            sqlx::query_as::<_, EvalQuestion>("SELECT * FROM eval_questions ORDER BY created_at")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(questions)
+    }
+
+    pub async fn get_eval_question(&self, id: &str) -> AppResult<EvalQuestion> {
+        sqlx::query_as::<_, EvalQuestion>("SELECT * FROM eval_questions WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Evaluation question {} not found", id)))
+    }
+
+    pub async fn create_eval_question(
+        &self,
+        request: &EvalQuestionRequest,
+        created_by: &str,
+    ) -> AppResult<EvalQuestion> {
+        let question = sqlx::query_as::<_, EvalQuestion>(
+            "INSERT INTO eval_questions (id, question, expected_scholar_ids, include_hidden, identities, tags, created_by, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW(), NOW())
+            RETURNING *",
+        )
+        .bind(cuid2::create_id())
+        .bind(&request.question)
+        .bind(&request.expected_scholar_ids)
+        .bind(request.include_hidden)
+        .bind(&request.identities)
+        .bind(&request.tags)
+        .bind(created_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(question)
+    }
+
+    pub async fn update_eval_question(
+        &self,
+        id: &str,
+        request: &EvalQuestionRequest,
+    ) -> AppResult<EvalQuestion> {
+        sqlx::query_as::<_, EvalQuestion>(
+            "UPDATE eval_questions SET question = $1, expected_scholar_ids = $2, include_hidden = $3,
+            identities = $4, tags = $5, updated_at = NOW()
+            WHERE id = $6
+            RETURNING *",
+        )
+        .bind(&request.question)
+        .bind(&request.expected_scholar_ids)
+        .bind(request.include_hidden)
+        .bind(&request.identities)
+        .bind(&request.tags)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Evaluation question {} not found", id)))
+    }
+
+    pub async fn delete_eval_question(&self, id: &str) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM eval_questions WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound(format!(
+                "Evaluation question {} not found",
+                id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Ids among `scholar_ids` that do not belong to a scholar
+    pub async fn find_unknown_scholar_ids(&self, scholar_ids: &[String]) -> AppResult<Vec<String>> {
+        let unknown: Vec<(String,)> = sqlx::query_as(
+            "SELECT id FROM unnest($1::char(24)[]) AS ids(id)
+            WHERE NOT EXISTS (SELECT 1 FROM scholars s WHERE s.id = ids.id)",
+        )
+        .bind(scholar_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(unknown.into_iter().map(|(id,)| id).collect())
+    }
+
+    pub async fn create_eval_run(
+        &self,
+        summary: &EvalRunSummary,
+        results: &[EvalQuestionResult],
+    ) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO eval_runs (id, embedding_model, reranker, k, threshold, question_count, recall_at_k, mrr, results, created_by, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+        )
+        .bind(&summary.id)
+        .bind(&summary.embedding_model)
+        .bind(&summary.reranker)
+        .bind(summary.k)
+        .bind(summary.threshold)
+        .bind(summary.question_count)
+        .bind(summary.recall_at_k)
+        .bind(summary.mrr)
+        .bind(Json(results))
+        .bind(&summary.created_by)
+        .bind(summary.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Most recent runs first
+    pub async fn list_eval_runs(&self, limit: i64) -> AppResult<Vec<EvalRunSummary>> {
+        let runs = sqlx::query_as::<_, EvalRunSummary>(&format!(
+            "SELECT {} FROM eval_runs ORDER BY created_at DESC LIMIT $1",
+            RUN_SUMMARY_COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(runs)
+    }
+
+    pub async fn get_eval_run(&self, id: &str) -> AppResult<EvalRun> {
+        sqlx::query_as::<_, EvalRun>("SELECT * FROM eval_runs WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Evaluation run {} not found", id)))
+    }
+}
@@ -0,0 +1,70 @@
+use crate::models::*;
+use crate::utils::AppResult;
+
+impl super::Database {
+    pub async fn record_guardrail_event(&self, record: &GuardrailEventRecord<'_>) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO guardrail_events (user_id, endpoint, kind, detail, excerpt)
+            VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(record.context.user_id)
+        .bind(record.context.endpoint)
+        .bind(record.kind)
+        .bind(record.detail)
+        .bind(record.excerpt)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Most recent guardrail events, optionally of one kind
+    pub async fn list_guardrail_events(
+        &self,
+        kind: Option<GuardrailKind>,
+        limit: i64,
+    ) -> AppResult<Vec<GuardrailEvent>> {
+        let events = sqlx::query_as::<_, GuardrailEvent>(
+            "SELECT g.id, g.user_id, u.name AS user_name, g.endpoint, g.kind, g.detail, g.excerpt, g.created_at
+            FROM guardrail_events g
+            LEFT JOIN users u ON u.id = g.user_id
+            WHERE $1::guardrail_kind IS NULL OR g.kind = $1
+            ORDER BY g.created_at DESC, g.id DESC
+            LIMIT $2",
+        )
+        .bind(kind)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Which of the given strings are ids of scholars, deleted ones included
+    pub async fn find_scholar_ids(&self, candidates: &[String]) -> AppResult<Vec<String>> {
+        let ids: Vec<(String,)> = sqlx::query_as("SELECT id FROM scholars WHERE id = ANY($1)")
+            .bind(candidates)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(ids.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Names of hidden scholars that are not part of any visible scholar's name, since
+    /// mentioning the visible scholar would otherwise match them
+    pub async fn get_hidden_scholar_names(&self) -> AppResult<Vec<String>> {
+        let names: Vec<(String,)> = sqlx::query_as(
+            "SELECT DISTINCT s.name FROM scholars s
+            WHERE s.visible = false AND s.deleted = false
+              AND NOT EXISTS (
+                SELECT 1 FROM scholars v
+                WHERE position(lower(s.name) in lower(v.name)) > 0
+                  AND v.visible = true AND v.deleted = false
+              )",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(names.into_iter().map(|(name,)| name).collect())
+    }
+}
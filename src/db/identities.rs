@@ -86,4 +86,14 @@ impl super::Database {
 
         Ok(())
     }
+
+    pub async fn get_identity_scholar_ids(&self, id: &str) -> AppResult<Vec<String>> {
+        let scholars: Vec<(String,)> =
+            sqlx::query_as("SELECT id FROM scholars WHERE identity = $1 AND deleted = false")
+                .bind(id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(scholars.into_iter().map(|(id,)| id).collect())
+    }
 }
@@ -0,0 +1,100 @@
+use chrono::NaiveDate;
+
+use crate::models::*;
+use crate::utils::AppResult;
+
+/// Aggregates over the usage log, joined to the users it belongs to
+const USAGE_SUMMARY_SELECT: &str = "SELECT u.user_id, us.name AS user_name, us.email AS user_email,
+        COUNT(*) AS calls,
+        COUNT(*) FILTER (WHERE NOT u.success) AS failed_calls,
+        COALESCE(SUM(u.prompt_tokens), 0)::BIGINT AS prompt_tokens,
+        COALESCE(SUM(u.completion_tokens), 0)::BIGINT AS completion_tokens,
+        AVG(u.latency_ms)::FLOAT8 AS average_latency_ms";
+
+impl super::Database {
+    pub async fn record_llm_usage(&self, record: &LlmUsageRecord<'_>) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO llm_usage (user_id, endpoint, kind, model, prompt_tokens, completion_tokens, latency_ms, success, error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+        .bind(record.context.user_id)
+        .bind(record.context.endpoint)
+        .bind(record.kind)
+        .bind(record.model)
+        .bind(record.tokens.prompt_tokens)
+        .bind(record.tokens.completion_tokens)
+        .bind(record.latency_ms)
+        .bind(record.error.is_none())
+        .bind(&record.error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Prompt and completion tokens a user has spent since midnight UTC
+    pub async fn get_user_tokens_today(&self, user_id: &str) -> AppResult<i64> {
+        let (tokens,): (i64,) = sqlx::query_as(
+            "SELECT COALESCE(SUM(COALESCE(prompt_tokens, 0) + COALESCE(completion_tokens, 0)), 0)::BIGINT
+            FROM llm_usage
+            WHERE user_id = $1 AND created_at >= date_trunc('day', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(tokens)
+    }
+
+    /// Totals per user between two days, inclusive, heaviest first
+    pub async fn get_llm_usage_by_user(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        user_id: Option<&str>,
+    ) -> AppResult<Vec<LlmUsageSummary>> {
+        let summaries = sqlx::query_as::<_, LlmUsageSummary>(&format!(
+            "{}, NULL::DATE AS day
+            FROM llm_usage u
+            LEFT JOIN users us ON us.id = u.user_id
+            WHERE (u.created_at AT TIME ZONE 'UTC')::DATE BETWEEN $1 AND $2
+                AND ($3::TEXT IS NULL OR u.user_id = $3)
+            GROUP BY u.user_id, us.name, us.email
+            ORDER BY SUM(COALESCE(u.prompt_tokens, 0) + COALESCE(u.completion_tokens, 0)) DESC",
+            USAGE_SUMMARY_SELECT
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(summaries)
+    }
+
+    /// Usage per user per UTC day between two days, inclusive, most recent first
+    pub async fn get_llm_usage_by_day(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        user_id: Option<&str>,
+    ) -> AppResult<Vec<LlmUsageSummary>> {
+        let summaries = sqlx::query_as::<_, LlmUsageSummary>(&format!(
+            "{}, (u.created_at AT TIME ZONE 'UTC')::DATE AS day
+            FROM llm_usage u
+            LEFT JOIN users us ON us.id = u.user_id
+            WHERE (u.created_at AT TIME ZONE 'UTC')::DATE BETWEEN $1 AND $2
+                AND ($3::TEXT IS NULL OR u.user_id = $3)
+            GROUP BY day, u.user_id, us.name, us.email
+            ORDER BY day DESC, us.name",
+            USAGE_SUMMARY_SELECT
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(summaries)
+    }
+}
@@ -0,0 +1,136 @@
+use crate::models::*;
+use crate::utils::{AppError, AppResult};
+
+impl super::Database {
+    /// Newest versions first, optionally for one use case
+    pub async fn list_prompt_templates(
+        &self,
+        use_case: Option<PromptUseCase>,
+    ) -> AppResult<Vec<PromptTemplate>> {
+        let templates = sqlx::query_as::<_, PromptTemplate>(
+            "SELECT * FROM prompt_templates
+            WHERE $1::prompt_use_case IS NULL OR use_case = $1
+            ORDER BY use_case, version DESC",
+        )
+        .bind(use_case)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(templates)
+    }
+
+    pub async fn get_prompt_template(&self, id: &str) -> AppResult<PromptTemplate> {
+        sqlx::query_as::<_, PromptTemplate>("SELECT * FROM prompt_templates WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Prompt template {} not found", id)))
+    }
+
+    /// The version RAG answers for `use_case` are generated with
+    pub async fn get_active_prompt_template(
+        &self,
+        use_case: PromptUseCase,
+    ) -> AppResult<PromptTemplate> {
+        sqlx::query_as::<_, PromptTemplate>(
+            "SELECT * FROM prompt_templates WHERE use_case = $1 AND is_active",
+        )
+        .bind(use_case)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::InternalError(format!("No active prompt template for {:?}", use_case))
+        })
+    }
+
+    /// Store the request as the next version for its use case
+    pub async fn create_prompt_template(
+        &self,
+        request: &PromptTemplateRequest,
+        created_by: &str,
+    ) -> AppResult<PromptTemplate> {
+        let id = cuid2::create_id();
+        let mut tx = self.pool.begin().await?;
+
+        // Serialize concurrent version numbering per use case
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('prompt_templates:' || $1::text))")
+            .bind(request.use_case)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO prompt_templates (id, use_case, version, name, system_prompt, temperature, max_tokens, created_by, created_at)
+            SELECT $1, $2, COALESCE(MAX(version), 0) + 1, $3, $4, $5, $6, $7, NOW()
+            FROM prompt_templates WHERE use_case = $2",
+        )
+        .bind(&id)
+        .bind(request.use_case)
+        .bind(&request.name)
+        .bind(&request.system_prompt)
+        .bind(request.temperature)
+        .bind(request.max_tokens)
+        .bind(created_by)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        if request.activate {
+            return self.activate_prompt_template(&id).await;
+        }
+        self.get_prompt_template(&id).await
+    }
+
+    /// Make a version the active one, deactivating the previous one of its use case
+    pub async fn activate_prompt_template(&self, id: &str) -> AppResult<PromptTemplate> {
+        let mut tx = self.pool.begin().await?;
+
+        let template = sqlx::query_as::<_, PromptTemplate>(
+            "SELECT * FROM prompt_templates WHERE id = $1 FOR UPDATE",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Prompt template {} not found", id)))?;
+
+        sqlx::query(
+            "UPDATE prompt_templates SET is_active = FALSE
+            WHERE use_case = $1 AND is_active AND id <> $2",
+        )
+        .bind(template.use_case)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        let template = sqlx::query_as::<_, PromptTemplate>(
+            "UPDATE prompt_templates SET is_active = TRUE, activated_at = NOW()
+            WHERE id = $1
+            RETURNING *",
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(template)
+    }
+
+    /// Delete an inactive version; the active one must be replaced first
+    pub async fn delete_prompt_template(&self, id: &str) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM prompt_templates WHERE id = $1 AND NOT is_active")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            // Distinguish a missing template from the active one
+            self.get_prompt_template(id).await?;
+            return Err(AppError::Conflict(format!(
+                "Prompt template {} is active; activate another version first",
+                id
+            )));
+        }
+        Ok(())
+    }
+}
@@ -1,197 +1,940 @@
-use sqlx::{Row, QueryBuilder};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, QueryBuilder, Row};
+use std::collections::HashMap;
+
+use super::vector_index::vector_cast;
 use crate::models::*;
-use crate::utils::AppResult;
+use crate::utils::{AppError, AppResult};
+
+/// Rows per multi-row insert, well below Postgres' 65535 bind parameters
+const STORE_CHUNK_ROWS: usize = 1000;
+/// Nearest vectors fetched from the index per wanted result, leaving room for
+/// scholars with many close passages and for rows dropped after the scan
+pub(super) const CANDIDATE_MULTIPLIER: i64 = 4;
 
 impl super::Database {
     /// Search for scholars with filtering support
-    /// Supports filtering by identity, tags, and visibility
+    /// Supports filtering by identity, tags, scholar ids, and visibility
+    /// Scholars are ranked by their best matching passage and carry their top passages
+    /// The nearest passages of matching scholars come from the HNSW index, which keeps
+    /// scanning until the filters leave enough of them; a scholar id filter, or identity
+    /// and tag filters on a pgvector without iterative scans, are searched exactly
     pub async fn search_scholars_by_embedding_filtered(
         &self,
+        model: &str,
         embedding: &[f32],
         limit: i64,
         similarity_threshold: f32,
-        include_hidden: bool,
-        identities: Option<&[String]>,
-        tags: Option<&[String]>,
+        passages_per_scholar: i64,
+        filters: RAGFilters<'_>,
     ) -> AppResult<Vec<RAGScholarResult>> {
-        // Convert embedding to string format for SQL
-        let embedding_str = format!("[{}]", 
-            embedding.iter()
-                .map(|v| v.to_string())
-                .collect::<Vec<_>>()
-                .join(",")
-        );
+        let vector = to_vector_literal(embedding);
+        let cast = vector_cast(embedding.len());
+        let candidates = limit * passages_per_scholar * CANDIDATE_MULTIPLIER;
+        let identities = filters.identities.filter(|list| !list.is_empty());
+        let tags = filters.tags.filter(|list| !list.is_empty());
 
         let mut query_builder = QueryBuilder::new(
-            "SELECT 
-                s.id,
-                s.name,
-                s.field_of_research,
-                s.introduction,
-                s.social_influence,
-                (1 - (se.embedding <=> "
-        );
-        query_builder.push_bind(embedding_str.clone());
-        query_builder.push(
-            "::vector)) as similarity_score
-            FROM scholars s
-            LEFT JOIN scholar_embeddings se ON s.id = se.scholar_id
-            WHERE s.deleted = false"
+            "WITH nearest AS (
+                SELECT
+                    sp.scholar_id,
+                    sp.field,
+                    sp.passage_index,
+                    sp.content,
+                    sp.embedding",
         );
+        query_builder.push(format!("{} <=> ", cast));
+        query_builder.push_bind(vector.clone());
+        query_builder.push(format!(
+            "{} AS distance
+                FROM scholar_passages sp
+                INNER JOIN scholars s ON s.id = sp.scholar_id
+                WHERE sp.dimensions = {} AND s.deleted = false",
+            cast,
+            embedding.len()
+        ));
+
+        // Only vectors from the same model are comparable
+        query_builder.push(" AND sp.model = ");
+        query_builder.push_bind(model.to_string());
 
         // Visibility filter
-        if !include_hidden {
+        if !filters.include_hidden {
             query_builder.push(" AND s.visible = true");
         }
 
-        query_builder.push(" AND se.embedding IS NOT NULL");
-
-        // Similarity threshold
-        query_builder.push(" AND (1 - (se.embedding <=> ");
-        query_builder.push_bind(embedding_str);
-        query_builder.push("::vector)) > ");
-        query_builder.push_bind(similarity_threshold as f64);
-
         // Identity filter
         if let Some(identity_list) = identities {
-            if !identity_list.is_empty() {
-                query_builder.push(" AND s.identity = ANY(");
-                query_builder.push_bind(identity_list);
-                query_builder.push(")");
-            }
+            query_builder.push(" AND s.identity = ANY(");
+            query_builder.push_bind(identity_list);
+            query_builder.push(")");
         }
 
         // Tag filter
         if let Some(tag_list) = tags {
-            if !tag_list.is_empty() {
-                query_builder.push(
-                    " AND EXISTS (
-                        SELECT 1
-                        FROM scholar_tags st
-                        INNER JOIN tags t ON t.id = st.tag
-                        WHERE st.scholar = s.id
-                        AND t.name = ANY("
-                );
-                query_builder.push_bind(tag_list);
-                query_builder.push("))");
-            }
+            query_builder.push(
+                " AND EXISTS (
+                    SELECT 1
+                    FROM scholar_tags st
+                    INNER JOIN tags t ON t.id = st.tag
+                    WHERE st.scholar = s.id
+                    AND t.name = ANY(",
+            );
+            query_builder.push_bind(tag_list);
+            query_builder.push("))");
+        }
+
+        // Scholar filter; pinned sets are small, so they skip the approximate index
+        if let Some(scholar_ids) = filters.scholar_ids {
+            query_builder.push(" AND sp.scholar_id = ANY(");
+            query_builder.push_bind(scholar_ids);
+            query_builder.push(")");
+        } else if self.iterative_scan || (identities.is_none() && tags.is_none()) {
+            query_builder.push(format!(" ORDER BY sp.embedding{} <=> ", cast));
+            query_builder.push_bind(vector);
+            query_builder.push(format!("{} LIMIT ", cast));
+            query_builder.push_bind(candidates);
         }
 
-        query_builder.push(" ORDER BY similarity_score DESC");
-        query_builder.push(" LIMIT ");
+        // Similarity threshold on the already computed distance
+        query_builder.push(
+            "
+            ),
+            passage_hits AS (
+                SELECT
+                    n.scholar_id,
+                    n.field,
+                    n.passage_index,
+                    n.content,
+                    1 - n.distance AS similarity
+                FROM nearest n
+                WHERE n.distance < ",
+        );
+        query_builder.push_bind(1.0 - similarity_threshold as f64);
+
+        // Rank passages within each scholar
+        query_builder.push(
+            "
+            ),
+            ranked AS (
+                SELECT
+                    *,
+                    ROW_NUMBER() OVER (PARTITION BY scholar_id ORDER BY similarity DESC, passage_index) AS passage_rank,
+                    MAX(similarity) OVER (PARTITION BY scholar_id) AS best_similarity
+                FROM passage_hits
+            ),
+            top_scholars AS (
+                SELECT scholar_id, best_similarity
+                FROM ranked
+                WHERE passage_rank = 1
+                ORDER BY best_similarity DESC
+                LIMIT "
+        );
         query_builder.push_bind(limit);
+        query_builder.push(
+            "
+            )
+            SELECT
+                s.id,
+                s.name,
+                s.field_of_research,
+                s.introduction,
+                s.social_influence,
+                ts.best_similarity,
+                r.field,
+                r.content,
+                r.similarity
+            FROM top_scholars ts
+            INNER JOIN scholars s ON s.id = ts.scholar_id
+            INNER JOIN ranked r ON r.scholar_id = ts.scholar_id AND r.passage_rank <= ",
+        );
+        query_builder.push_bind(passages_per_scholar);
+        query_builder.push(" ORDER BY ts.best_similarity DESC, s.id, r.passage_rank");
+
+        let mut tx = self.begin_vector_search(candidates).await?;
+        let rows = query_builder.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+
+        // Rows arrive grouped per scholar, best scholar first
+        let mut results: Vec<RAGScholarResult> = Vec::new();
+        for row in rows {
+            let id: String = row.get("id");
+            let similarity: f64 = row.get("similarity");
+            let passage = RAGPassage {
+                field: row.get("field"),
+                content: row.get("content"),
+                similarity_score: similarity as f32,
+            };
 
-        let query = query_builder.build();
-        let rows = query.fetch_all(&self.pool).await?;
-
-        let results = rows.iter().map(|row| {
-            let similarity_score: f64 = row.get("similarity_score");
-            RAGScholarResult {
-                id: row.get("id"),
-                name: row.get("name"),
-                field_of_research: row.get("field_of_research"),
-                introduction: row.get("introduction"),
-                social_influence: row.get("social_influence"),
-                similarity_score: similarity_score as f32,
+            match results.last_mut() {
+                Some(last) if last.id == id => last.passages.push(passage),
+                _ => {
+                    let best_similarity: f64 = row.get("best_similarity");
+                    results.push(RAGScholarResult {
+                        id,
+                        name: row.get("name"),
+                        field_of_research: row.get("field_of_research"),
+                        introduction: row.get("introduction"),
+                        social_influence: row.get("social_influence"),
+                        similarity_score: best_similarity as f32,
+                        rerank_score: None,
+                        passages: vec![passage],
+                        pinned: false,
+                    });
+                }
             }
-        }).collect();
+        }
 
         Ok(results)
-
     }
 
-    /// Store scholar embedding in the database
-    pub async fn store_scholar_embedding(
+    /// Store embedded scholars for one model in a single transaction
+    /// Each scholar's passages are replaced and its profile vector upserted, using
+    /// multi-row statements; scholar ids must be unique
+    pub async fn store_scholar_embeddings(
         &self,
-        scholar_id: &str,
-        embedding_text: &str,
-        embedding: &[f32],
+        model: &str,
+        scholars: &[EmbeddedScholar],
     ) -> AppResult<()> {
-        let id = cuid2::cuid();
-        let embedding_str = format!("[{}]", 
-            embedding.iter()
-                .map(|v| v.to_string())
-                .collect::<Vec<_>>()
-                .join(",")
-        );
+        let Some(first) = scholars.first() else {
+            return Ok(());
+        };
+
+        let dimensions = first.embedding.len();
+        let consistent = scholars.iter().all(|scholar| {
+            scholar.embedding.len() == dimensions
+                && scholar
+                    .passages
+                    .iter()
+                    .all(|(_, embedding)| embedding.len() == dimensions)
+        });
+        if !consistent {
+            return Err(AppError::InternalError(format!(
+                "Embedding model {} returned vectors of differing dimensions",
+                model
+            )));
+        }
+        self.set_embedding_model_dimensions(model, dimensions as i32)
+            .await?;
+
+        let scholar_ids: Vec<String> = scholars.iter().map(|s| s.scholar_id.clone()).collect();
+        let passages: Vec<(&str, &ScholarPassage, &Vec<f32>)> = scholars
+            .iter()
+            .flat_map(|scholar| {
+                scholar
+                    .passages
+                    .iter()
+                    .map(|(passage, embedding)| (scholar.scholar_id.as_str(), passage, embedding))
+            })
+            .collect();
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM scholar_passages WHERE scholar_id = ANY($1) AND model = $2")
+            .bind(&scholar_ids)
+            .bind(model)
+            .execute(&mut *tx)
+            .await?;
+
+        // Chunked to stay below Postgres' limit on bind parameters per statement
+        for chunk in passages.chunks(STORE_CHUNK_ROWS) {
+            let mut query_builder = QueryBuilder::new(
+                "INSERT INTO scholar_passages (id, scholar_id, model, dimensions, field, passage_index, content, embedding) ",
+            );
+            query_builder.push_values(chunk, |mut row, (scholar_id, passage, embedding)| {
+                row.push_bind(cuid2::create_id())
+                    .push_bind(scholar_id.to_string())
+                    .push_bind(model.to_string())
+                    .push_bind(dimensions as i32)
+                    .push_bind(passage.field)
+                    .push_bind(passage.passage_index)
+                    .push_bind(passage.content.clone())
+                    .push_bind(to_vector_literal(embedding))
+                    .push_unseparated("::vector");
+            });
+            query_builder.build().execute(&mut *tx).await?;
+        }
+
+        for chunk in scholars.chunks(STORE_CHUNK_ROWS) {
+            let mut query_builder = QueryBuilder::new(
+                "INSERT INTO scholar_embeddings (id, scholar_id, model, dimensions, embedding_text, content_hash, embedding, embedded_at, created_at, updated_at) ",
+            );
+            query_builder.push_values(chunk, |mut row, scholar| {
+                row.push_bind(cuid2::create_id())
+                    .push_bind(scholar.scholar_id.clone())
+                    .push_bind(model.to_string())
+                    .push_bind(dimensions as i32)
+                    .push_bind(scholar.embedding_text.clone())
+                    .push_bind(hash_embedding_text(&scholar.embedding_text))
+                    .push_bind(to_vector_literal(&scholar.embedding))
+                    .push_unseparated("::vector")
+                    .push("NOW()")
+                    .push("NOW()")
+                    .push("NOW()");
+            });
+            query_builder.push(
+                " ON CONFLICT (scholar_id, model) DO UPDATE SET
+                    dimensions = EXCLUDED.dimensions,
+                    embedding_text = EXCLUDED.embedding_text,
+                    content_hash = EXCLUDED.content_hash,
+                    embedding = EXCLUDED.embedding,
+                    embedded_at = NOW(),
+                    updated_at = NOW()",
+            );
+            query_builder.build().execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Get all visible scholars without embeddings from the given model (for batch processing)
+    pub async fn get_scholars_without_embeddings(&self, model: &str) -> AppResult<Vec<Scholar>> {
+        let scholars = sqlx::query_as::<_, Scholar>(
+            "SELECT s.* FROM scholars s
+            LEFT JOIN scholar_embeddings se ON s.id = se.scholar_id AND se.model = $1
+            WHERE s.deleted = false 
+              AND s.visible = true
+              AND (
+                se.embedding IS NULL
+                OR se.embedding_text = ''
+                OR NOT EXISTS (
+                    SELECT 1 FROM scholar_passages sp
+                    WHERE sp.scholar_id = s.id AND sp.model = $1
+                )
+              )
+            ORDER BY s.created_at DESC",
+        )
+        .bind(model)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(scholars)
+    }
+
+    /// Load everything that goes into the embeddings of several scholars at once
+    /// (works for all scholars, including hidden)
+    async fn load_scholar_embedding_sources(
+        &self,
+        scholar_ids: &[String],
+    ) -> AppResult<Vec<ScholarEmbeddingSource>> {
+        if scholar_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Get basic scholar info and identity (doesn't require visibility)
+        let rows = sqlx::query(
+            "SELECT s.*, i.name AS identity_name
+            FROM scholars s
+            LEFT JOIN identities i ON i.id = s.identity
+            WHERE s.id = ANY($1)
+            ORDER BY s.created_at DESC",
+        )
+        .bind(scholar_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // Get tags, in a stable order so the text hash only changes with the content
+        let tags: Vec<(String, String)> = sqlx::query_as(
+            "SELECT st.scholar, t.name FROM tags t
+            INNER JOIN scholar_tags st ON t.id = st.tag
+            WHERE st.scholar = ANY($1)
+            ORDER BY t.display_order, t.name",
+        )
+        .bind(scholar_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // Get news
+        let news: Vec<(String, String)> = sqlx::query_as(
+            "SELECT ns.scholar, n.title FROM news n
+            INNER JOIN news_scholars ns ON n.id = ns.news
+            WHERE ns.scholar = ANY($1)
+            ORDER BY n.publish_date DESC, n.id",
+        )
+        .bind(scholar_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tags_map: HashMap<String, Vec<String>> = HashMap::new();
+        for (scholar_id, name) in tags {
+            tags_map.entry(scholar_id).or_default().push(name);
+        }
+
+        let mut news_map: HashMap<String, Vec<String>> = HashMap::new();
+        for (scholar_id, title) in news {
+            news_map.entry(scholar_id).or_default().push(title);
+        }
+
+        rows.iter()
+            .map(|row| {
+                let scholar = Scholar::from_row(row)?;
+                let identity_name: Option<String> = row.get("identity_name");
+                Ok(ScholarEmbeddingSource {
+                    tags: tags_map.remove(&scholar.id).unwrap_or_default(),
+                    news: news_map.remove(&scholar.id).unwrap_or_default(),
+                    identity_name: identity_name.unwrap_or_else(|| "Unknown".to_string()),
+                    scholar,
+                })
+            })
+            .collect()
+    }
+
+    /// Build embedding texts for several scholars, keyed by scholar id
+    pub async fn build_scholar_embedding_texts(
+        &self,
+        scholar_ids: &[String],
+    ) -> AppResult<HashMap<String, String>> {
+        let sources = self.load_scholar_embedding_sources(scholar_ids).await?;
+        Ok(sources
+            .into_iter()
+            .map(|source| (source.scholar.id.clone(), source.embedding_text()))
+            .collect())
+    }
+
+    /// Build the profile text and retrieval passages of several scholars (including hidden ones)
+    pub async fn build_scholar_embedding_inputs(
+        &self,
+        scholar_ids: &[String],
+        max_chars: usize,
+        overlap_chars: usize,
+    ) -> AppResult<Vec<ScholarEmbeddingInput>> {
+        let sources = self.load_scholar_embedding_sources(scholar_ids).await?;
+        Ok(sources
+            .into_iter()
+            .map(|source| ScholarEmbeddingInput {
+                scholar_id: source.scholar.id.clone(),
+                embedding_text: source.embedding_text(),
+                passages: source.passages(max_chars, overlap_chars),
+            })
+            .collect())
+    }
+
+    /// Get all visible scholars (for reindexing)
+    pub async fn get_all_visible_scholars(&self) -> AppResult<Vec<Scholar>> {
+        let scholars = sqlx::query_as::<_, Scholar>(
+            "SELECT s.* FROM scholars s
+            WHERE s.deleted = false 
+              AND s.visible = true
+            ORDER BY s.created_at DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(scholars)
+    }
+
+    pub async fn list_embedding_models(&self) -> AppResult<Vec<EmbeddingModel>> {
+        let models = sqlx::query_as::<_, EmbeddingModel>(
+            "SELECT * FROM embedding_models ORDER BY created_at DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(models)
+    }
+
+    pub async fn get_embedding_model_by_status(
+        &self,
+        status: EmbeddingModelStatus,
+    ) -> AppResult<Option<EmbeddingModel>> {
+        let model =
+            sqlx::query_as::<_, EmbeddingModel>("SELECT * FROM embedding_models WHERE status = $1")
+                .bind(status)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(model)
+    }
+
+    /// The EMBEDDING_MODEL value seen at the last startup, if recorded
+    pub async fn get_synced_embedding_model(&self) -> AppResult<Option<String>> {
+        let name = sqlx::query_scalar::<_, String>(
+            "SELECT value FROM app_settings WHERE key = 'synced_embedding_model'",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
 
+        Ok(name)
+    }
+
+    pub async fn set_synced_embedding_model(&self, name: &str) -> AppResult<()> {
         sqlx::query(
-            "INSERT INTO scholar_embeddings (id, scholar_id, embedding_text, embedding, embedded_at, created_at, updated_at)
-            VALUES ($1, $2, $3, $4::vector, NOW(), NOW(), NOW())
-            ON CONFLICT (scholar_id) DO UPDATE SET
-                embedding_text = $3,
-                embedding = $4::vector,
-                embedded_at = NOW(),
+            "INSERT INTO app_settings (key, value)
+            VALUES ('synced_embedding_model', $1)
+            ON CONFLICT (key) DO UPDATE SET
+                value = EXCLUDED.value,
                 updated_at = NOW()"
         )
-        .bind(&id)
-        .bind(scholar_id)
-        .bind(embedding_text)
-        .bind(embedding_str)
+        .bind(name)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    /// Get all visible scholars without embeddings (for batch processing)
-    pub async fn get_scholars_without_embeddings(&self) -> AppResult<Vec<Scholar>> {
+    /// Register a model as the active one (used on a fresh database)
+    pub async fn activate_embedding_model(&self, name: &str) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO embedding_models (name, status, activated_at)
+            VALUES ($1, 'active', NOW())
+            ON CONFLICT (name) DO UPDATE SET
+                status = 'active',
+                activated_at = NOW(),
+                retired_at = NULL",
+        )
+        .bind(name)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark a model as the migration target
+    /// Only one migration may run at a time
+    pub async fn start_embedding_model_migration(&self, name: &str) -> AppResult<EmbeddingModel> {
+        let mut tx = self.pool.begin().await?;
+
+        let current: Vec<EmbeddingModel> = sqlx::query_as(
+            "SELECT * FROM embedding_models WHERE status IN ('active', 'migrating') FOR UPDATE",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for model in &current {
+            if model.status == EmbeddingModelStatus::Active && model.name == name {
+                return Err(AppError::Conflict(format!(
+                    "Embedding model {} is already active",
+                    name
+                )));
+            }
+            if model.status == EmbeddingModelStatus::Migrating && model.name != name {
+                return Err(AppError::Conflict(format!(
+                    "A migration to embedding model {} is already in progress",
+                    model.name
+                )));
+            }
+        }
+
+        let model = sqlx::query_as::<_, EmbeddingModel>(
+            "INSERT INTO embedding_models (name, status)
+            VALUES ($1, 'migrating')
+            ON CONFLICT (name) DO UPDATE SET
+                status = 'migrating',
+                retired_at = NULL
+            RETURNING *",
+        )
+        .bind(name)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(model)
+    }
+
+    /// Abandon the running migration and drop the vectors it produced
+    pub async fn cancel_embedding_model_migration(&self) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let model: Option<(String,)> = sqlx::query_as(
+            "UPDATE embedding_models SET status = 'retired', retired_at = NOW()
+            WHERE status = 'migrating'
+            RETURNING name",
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let (name,) = model.ok_or_else(|| {
+            AppError::NotFound("No embedding model migration in progress".to_string())
+        })?;
+
+        sqlx::query("DELETE FROM scholar_embeddings WHERE model = $1")
+            .bind(&name)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM scholar_passages WHERE model = $1")
+            .bind(&name)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM rag_documents WHERE model = $1")
+            .bind(&name)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        self.drop_unused_vector_indexes().await;
+
+        Ok(())
+    }
+
+    /// Switch queries over to the migrated model and drop vectors from every other model
+    pub async fn complete_embedding_model_migration(&self, name: &str) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE embedding_models SET status = 'retired', retired_at = NOW()
+            WHERE status = 'active'",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let result = sqlx::query(
+            "UPDATE embedding_models SET status = 'active', activated_at = NOW()
+            WHERE name = $1 AND status = 'migrating'",
+        )
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound(format!(
+                "Embedding model {} is not being migrated to",
+                name
+            )));
+        }
+
+        sqlx::query("DELETE FROM scholar_embeddings WHERE model <> $1")
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM scholar_passages WHERE model <> $1")
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM rag_documents WHERE model <> $1")
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        self.drop_unused_vector_indexes().await;
+
+        Ok(())
+    }
+
+    /// Record the dimensions of a model on first use and reject vectors that don't match
+    /// The first vectors of a new size also get their HNSW indexes built
+    pub(super) async fn set_embedding_model_dimensions(
+        &self,
+        name: &str,
+        dimensions: i32,
+    ) -> AppResult<()> {
+        let stored: Option<(Option<i32>, Option<i32>)> = sqlx::query_as(
+            "UPDATE embedding_models m SET dimensions = COALESCE(m.dimensions, $2)
+            FROM embedding_models previous
+            WHERE m.name = $1 AND previous.name = m.name
+            RETURNING m.dimensions, previous.dimensions",
+        )
+        .bind(name)
+        .bind(dimensions)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match stored {
+            None => Err(AppError::BadRequest(format!(
+                "Embedding model {} is not registered",
+                name
+            ))),
+            Some((Some(expected), _)) if expected != dimensions => {
+                Err(AppError::InternalError(format!(
+                    "Embedding model {} returned {} dimensions, expected {}",
+                    name, dimensions, expected
+                )))
+            }
+            Some((_, None)) => self.ensure_vector_indexes_for(dimensions).await,
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Indexes of retired models' dimensions are only dead weight; failing to drop
+    /// them must not fail the model switch that already committed
+    async fn drop_unused_vector_indexes(&self) {
+        if let Err(e) = self.ensure_vector_indexes().await {
+            log::error!("Failed to update vector indexes: {}", e);
+        }
+    }
+
+    /// Count non-deleted scholars and how many of them are fully embedded with the given model
+    pub async fn get_embedding_progress(&self, model: &str) -> AppResult<(i64, i64)> {
+        let (embedded, total): (i64, i64) = sqlx::query_as(
+            "SELECT
+                COUNT(*) FILTER (
+                    WHERE EXISTS (
+                        SELECT 1 FROM scholar_embeddings se
+                        WHERE se.scholar_id = s.id AND se.model = $1
+                    )
+                    AND EXISTS (
+                        SELECT 1 FROM scholar_passages sp
+                        WHERE sp.scholar_id = s.id AND sp.model = $1
+                    )
+                ),
+                COUNT(*)
+            FROM scholars s
+            WHERE s.deleted = false",
+        )
+        .bind(model)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((embedded, total))
+    }
+
+    /// Get non-deleted scholars (including hidden) lacking a profile vector or passages from the given model
+    pub async fn get_scholars_pending_embedding(&self, model: &str) -> AppResult<Vec<Scholar>> {
         let scholars = sqlx::query_as::<_, Scholar>(
             "SELECT s.* FROM scholars s
-            LEFT JOIN scholar_embeddings se ON s.id = se.scholar_id
-            WHERE s.deleted = false 
-              AND s.visible = true
-              AND (se.embedding IS NULL OR se.embedding_text IS NULL)
+            WHERE s.deleted = false
+              AND (
+                NOT EXISTS (
+                    SELECT 1 FROM scholar_embeddings se
+                    WHERE se.scholar_id = s.id AND se.model = $1
+                )
+                OR NOT EXISTS (
+                    SELECT 1 FROM scholar_passages sp
+                    WHERE sp.scholar_id = s.id AND sp.model = $1
+                )
+              )
             ORDER BY s.created_at DESC"
         )
+        .bind(model)
         .fetch_all(&self.pool)
         .await?;
 
         Ok(scholars)
     }
 
-    /// Build text for embedding from scholar data (works for all scholars, including hidden)
-    pub async fn build_scholar_embedding_text(&self, scholar_id: &str) -> AppResult<String> {
-        // Get basic scholar info (doesn't require visibility)
-        let scholar: Scholar = sqlx::query_as(
-            "SELECT * FROM scholars WHERE id = $1"
+    /// Ids of all non-deleted scholars
+    pub async fn get_active_scholar_ids(&self) -> AppResult<Vec<String>> {
+        let ids: Vec<(String,)> = sqlx::query_as(
+            "SELECT id FROM scholars WHERE deleted = false ORDER BY created_at DESC",
         )
-        .bind(scholar_id)
-        .fetch_one(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
 
-        // Get identity
-        let identity: Option<(String,)> = sqlx::query_as(
-            "SELECT name FROM identities WHERE id = $1"
+        Ok(ids.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Content hashes of the stored profile vectors for one model, keyed by scholar id
+    /// Vectors stored before hashes were recorded have no hash
+    pub async fn get_embedding_hashes(
+        &self,
+        model: &str,
+        scholar_ids: &[String],
+    ) -> AppResult<HashMap<String, Option<String>>> {
+        let rows: Vec<(String, Option<String>)> = sqlx::query_as(
+            "SELECT scholar_id, content_hash FROM scholar_embeddings
+            WHERE model = $1 AND scholar_id = ANY($2)",
         )
-        .bind(&scholar.identity)
-        .fetch_optional(&self.pool)
+        .bind(model)
+        .bind(scholar_ids)
+        .fetch_all(&self.pool)
         .await?;
 
-        let identity_name = identity.map(|(n,)| n).unwrap_or_else(|| "Unknown".to_string());
+        Ok(rows.into_iter().collect())
+    }
 
-        // Get tags
-        let tags: Vec<(String,)> = sqlx::query_as(
-            "SELECT t.name FROM tags t
-            INNER JOIN scholar_tags st ON t.id = st.tag
-            WHERE st.scholar = $1"
+    /// Queue scholars for re-embedding
+    /// With `reset_existing`, already queued scholars are retried right away instead of
+    /// waiting out their backoff
+    pub async fn queue_embedding_refresh(
+        &self,
+        scholar_ids: &[String],
+        reason: &str,
+        reset_existing: bool,
+    ) -> AppResult<u64> {
+        if scholar_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let result = sqlx::query(
+            "INSERT INTO embedding_refresh_queue (scholar_id, reason, queued_at, next_attempt_at)
+            SELECT unnest($1::char(24)[]), $2, NOW(), NOW()
+            ON CONFLICT (scholar_id) DO UPDATE SET
+                reason = $2,
+                queued_at = NOW(),
+                attempts = 0,
+                last_error = NULL,
+                next_attempt_at = NOW()
+            WHERE $3",
         )
-        .bind(scholar_id)
+        .bind(scholar_ids)
+        .bind(reason)
+        .bind(reset_existing)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Queued scholars whose next attempt is due, oldest first
+    pub async fn get_due_embedding_refreshes(
+        &self,
+        limit: i64,
+    ) -> AppResult<Vec<EmbeddingRefresh>> {
+        let refreshes = sqlx::query_as::<_, EmbeddingRefresh>(
+            "SELECT * FROM embedding_refresh_queue
+            WHERE next_attempt_at <= NOW()
+            ORDER BY queued_at
+            LIMIT $1",
+        )
+        .bind(limit)
         .fetch_all(&self.pool)
         .await?;
 
-        // Get news
-        let news: Vec<(String,)> = sqlx::query_as(
-            "SELECT n.title FROM news n
-            INNER JOIN news_scholars ns ON n.id = ns.news
-            WHERE ns.scholar = $1"
+        Ok(refreshes)
+    }
+
+    /// Remove a processed entry unless the scholar was queued again in the meantime
+    pub async fn complete_embedding_refresh(
+        &self,
+        scholar_id: &str,
+        queued_at: DateTime<Utc>,
+    ) -> AppResult<()> {
+        sqlx::query("DELETE FROM embedding_refresh_queue WHERE scholar_id = $1 AND queued_at = $2")
+            .bind(scholar_id)
+            .bind(queued_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt and back off exponentially, up to an hour
+    pub async fn fail_embedding_refresh(&self, scholar_id: &str, error: &str) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE embedding_refresh_queue SET
+                attempts = attempts + 1,
+                last_error = $2,
+                next_attempt_at = NOW() + LEAST(POWER(2, attempts), 60) * INTERVAL '1 minute'
+            WHERE scholar_id = $1",
         )
         .bind(scholar_id)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn count_queued_embedding_refreshes(&self) -> AppResult<i64> {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM embedding_refresh_queue")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Non-deleted scholars closest to a scholar's profile vector, most similar first
+    /// None when the scholar has no vector from `model`
+    pub async fn get_scholar_neighbors(
+        &self,
+        scholar_id: &str,
+        model: &str,
+        limit: i64,
+    ) -> AppResult<Option<Vec<ScholarNeighbor>>> {
+        let target: Option<(String, i32)> = sqlx::query_as(
+            "SELECT embedding::text, dimensions FROM scholar_embeddings
+            WHERE scholar_id = $1 AND model = $2",
+        )
+        .bind(scholar_id)
+        .bind(model)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some((embedding, dimensions)) = target else {
+            return Ok(None);
+        };
+
+        // Nearest vectors through the index first, then drop the scholar itself and
+        // deleted scholars from the candidates
+        let candidates = (limit + 1) * CANDIDATE_MULTIPLIER;
+        let cast = vector_cast(dimensions as usize);
+        let mut tx = self.begin_vector_search(candidates).await?;
+        let neighbors = sqlx::query_as::<_, ScholarNeighbor>(&format!(
+            "WITH nearest AS (
+                SELECT se.scholar_id, se.embedding{cast} <=> $1{cast} AS distance
+                FROM scholar_embeddings se
+                WHERE se.model = $2 AND se.dimensions = {dimensions}
+                ORDER BY se.embedding{cast} <=> $1{cast}
+                LIMIT $3
+            )
+            SELECT
+                s.id,
+                s.name,
+                i.id AS identity_id,
+                i.name AS identity_name,
+                i.archived_at IS NOT NULL AS identity_archived,
+                (1 - n.distance)::real AS similarity
+            FROM nearest n
+            INNER JOIN scholars s ON s.id = n.scholar_id
+            INNER JOIN identities i ON i.id = s.identity
+            WHERE n.scholar_id <> $4 AND s.deleted = false
+            ORDER BY n.distance
+            LIMIT $5"
+        ))
+        .bind(embedding)
+        .bind(model)
+        .bind(candidates)
+        .bind(scholar_id)
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some(neighbors))
+    }
+
+    /// Unarchived tags of the given scholars, keyed by scholar id
+    pub async fn get_active_tags_by_scholar(
+        &self,
+        scholar_ids: &[String],
+    ) -> AppResult<HashMap<String, Vec<TagListItem>>> {
+        let rows = sqlx::query(
+            "SELECT st.scholar, t.id, t.name, t.color, t.featured, t.display_order
+            FROM scholar_tags st
+            INNER JOIN tags t ON t.id = st.tag
+            WHERE st.scholar = ANY($1) AND t.archived_at IS NULL
+            ORDER BY t.display_order, t.name",
+        )
+        .bind(scholar_ids)
         .fetch_all(&self.pool)
         .await?;
 
-        // Combine all relevant text fields
-        let text = format!(
+        let mut tags: HashMap<String, Vec<TagListItem>> = HashMap::new();
+        for row in rows {
+            tags.entry(row.get("scholar"))
+                .or_default()
+                .push(TagListItem {
+                    id: row.get("id"),
+                    name: row.get("name"),
+                    color: row.get("color"),
+                    featured: row.get("featured"),
+                    display_order: row.get("display_order"),
+                });
+        }
+
+        Ok(tags)
+    }
+}
+
+struct ScholarEmbeddingSource {
+    scholar: Scholar,
+    identity_name: String,
+    tags: Vec<String>,
+    news: Vec<String>,
+}
+
+impl ScholarEmbeddingSource {
+    /// Combine all relevant text fields
+    fn embedding_text(&self) -> String {
+        let scholar = &self.scholar;
+        format!(
             "Scholar: {} ({}). Field of Research: {}. Born: {}. Introduction: {}. Social Influence: {}. Identity: {}. Tags: {}. News: {}",
             scholar.name,
             scholar.id,
@@ -199,31 +942,134 @@ impl super::Database {
             scholar.year_of_birth,
             scholar.introduction,
             scholar.social_influence,
-            identity_name,
-            tags.iter()
-                .map(|(n,)| n.clone())
-                .collect::<Vec<_>>()
-                .join(", "),
-            news.iter()
-                .map(|(n,)| n.clone())
-                .collect::<Vec<_>>()
-                .join(", ")
+            self.identity_name,
+            self.tags.join(", "),
+            self.news.join(", ")
+        )
+    }
+
+    /// Split the profile into overlapping passages for retrieval
+    /// The profile passage carries the short facts; long text fields are chunked separately
+    fn passages(&self, max_chars: usize, overlap_chars: usize) -> Vec<ScholarPassage> {
+        let scholar = &self.scholar;
+
+        let profile = format!(
+            "Scholar: {}. Field of Research: {}. Born: {}. Identity: {}. Tags: {}. News: {}",
+            scholar.name,
+            scholar.field_of_research,
+            scholar.year_of_birth,
+            self.identity_name,
+            self.tags.join(", "),
+            self.news.join(", ")
         );
 
-        Ok(text)
+        let fields = [
+            (PassageField::Profile, profile.as_str()),
+            (PassageField::Introduction, scholar.introduction.as_str()),
+            (
+                PassageField::SocialInfluence,
+                scholar.social_influence.as_str(),
+            ),
+        ];
+
+        let mut passages = Vec::new();
+        for (field, text) in fields {
+            for (index, content) in split_into_passages(text, max_chars, overlap_chars)
+                .into_iter()
+                .enumerate()
+            {
+                // Prefix the name so each passage is self-contained for the embedding model
+                let embedding_input = match field {
+                    PassageField::Profile => content.clone(),
+                    _ => format!("{} - {}: {}", scholar.name, field.label(), content),
+                };
+                passages.push(ScholarPassage {
+                    field,
+                    passage_index: index as i32,
+                    content,
+                    embedding_input,
+                });
+            }
+        }
+
+        passages
     }
+}
 
-    /// Get all visible scholars (for reindexing)
-    pub async fn get_all_visible_scholars(&self) -> AppResult<Vec<Scholar>> {
-        let scholars = sqlx::query_as::<_, Scholar>(
-            "SELECT s.* FROM scholars s
-            WHERE s.deleted = false 
-              AND s.visible = true
-            ORDER BY s.created_at DESC"
-        )
-        .fetch_all(&self.pool)
-        .await?;
+/// Hash of the text an embedding was produced from, used to detect stale vectors
+pub fn hash_embedding_text(text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(text.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
 
-        Ok(scholars)
+/// Format an embedding as a pgvector literal
+pub(super) fn to_vector_literal(embedding: &[f32]) -> String {
+    format!(
+        "[{}]",
+        embedding
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+/// Split text into passages of at most `max_chars` characters
+/// Breaks at sentence boundaries where possible; each passage repeats up to
+/// `overlap_chars` of trailing sentences from the previous one
+fn split_into_passages(text: &str, max_chars: usize, overlap_chars: usize) -> Vec<String> {
+    const SENTENCE_ENDINGS: &[char] = &['。', '！', '？', '；', '.', '!', '?', ';', '\n'];
+
+    // Cut into sentences, hard-splitting any sentence longer than a passage
+    let mut sentences: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        current.push(c);
+        if SENTENCE_ENDINGS.contains(&c) || current.chars().count() >= max_chars {
+            sentences.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.trim().is_empty() {
+        sentences.push(current);
     }
+
+    let mut passages = Vec::new();
+    let mut window: Vec<String> = Vec::new();
+    let mut window_chars = 0;
+
+    for sentence in sentences {
+        let sentence_chars = sentence.chars().count();
+
+        if window_chars + sentence_chars > max_chars && !window.is_empty() {
+            passages.push(window.concat().trim().to_string());
+
+            // Carry the tail of the finished passage into the next one
+            let mut overlap = Vec::new();
+            let mut overlap_len = 0;
+            for previous in window.iter().rev() {
+                let len = previous.chars().count();
+                if overlap_len + len > overlap_chars
+                    || overlap_len + len + sentence_chars > max_chars
+                {
+                    break;
+                }
+                overlap_len += len;
+                overlap.push(previous.clone());
+            }
+            overlap.reverse();
+            window = overlap;
+            window_chars = overlap_len;
+        }
+
+        window_chars += sentence_chars;
+        window.push(sentence);
+    }
+
+    if !window.is_empty() {
+        passages.push(window.concat().trim().to_string());
+    }
+
+    passages.retain(|passage| !passage.is_empty());
+    passages
 }
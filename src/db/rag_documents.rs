@@ -0,0 +1,312 @@
+use sqlx::{QueryBuilder, Row};
+use std::collections::HashMap;
+
+use super::rag::{CANDIDATE_MULTIPLIER, hash_embedding_text, to_vector_literal};
+use super::vector_index::vector_cast;
+use crate::models::*;
+use crate::utils::{AppError, AppResult};
+
+impl super::Database {
+    /// Current text of every retrievable news item and described tag
+    /// Archived sources are left out, so their documents get dropped on the next sync
+    pub async fn build_rag_document_inputs(&self) -> AppResult<Vec<RAGDocumentInput>> {
+        let news = sqlx::query(
+            "SELECT n.id, n.title, n.source, n.publish_date,
+                COALESCE(
+                    STRING_AGG(s.name, ', ' ORDER BY s.name) FILTER (WHERE s.id IS NOT NULL),
+                    ''
+                ) AS scholar_names
+            FROM news n
+            LEFT JOIN news_scholars ns ON ns.news = n.id
+            LEFT JOIN scholars s ON s.id = ns.scholar AND s.deleted = false
+            WHERE n.archived_at IS NULL
+            GROUP BY n.id
+            ORDER BY n.publish_date DESC, n.id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let tags: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT id, name, description FROM tags
+            WHERE archived_at IS NULL AND COALESCE(TRIM(description), '') <> ''
+            ORDER BY display_order, name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut documents = Vec::with_capacity(news.len() + tags.len());
+        for row in news {
+            let publish_date: chrono::DateTime<chrono::Utc> = row.get("publish_date");
+            let scholar_names: String = row.get("scholar_names");
+            documents.push(RAGDocumentInput {
+                doc_type: RAGDocumentType::News,
+                source_id: row.get("id"),
+                content: format!(
+                    "News: {}. Source: {}. Published: {}. Scholars: {}",
+                    row.get::<String, _>("title"),
+                    row.get::<String, _>("source"),
+                    publish_date.format("%Y-%m-%d"),
+                    scholar_names
+                ),
+            });
+        }
+        for (id, name, description) in tags {
+            documents.push(RAGDocumentInput {
+                doc_type: RAGDocumentType::Tag,
+                source_id: id,
+                content: format!("Tag: {}. {}", name, description.trim()),
+            });
+        }
+
+        Ok(documents)
+    }
+
+    /// Content hashes of the documents stored for one model, keyed by type and source id
+    pub async fn get_rag_document_hashes(
+        &self,
+        model: &str,
+    ) -> AppResult<HashMap<(RAGDocumentType, String), String>> {
+        let rows: Vec<(RAGDocumentType, String, String)> = sqlx::query_as(
+            "SELECT doc_type, source_id, content_hash FROM rag_documents WHERE model = $1",
+        )
+        .bind(model)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(doc_type, source_id, hash)| ((doc_type, source_id), hash))
+            .collect())
+    }
+
+    /// Upsert embedded documents for one model
+    pub async fn store_rag_documents(
+        &self,
+        model: &str,
+        documents: &[(&RAGDocumentInput, Vec<f32>)],
+    ) -> AppResult<()> {
+        let Some((_, first)) = documents.first() else {
+            return Ok(());
+        };
+
+        let dimensions = first.len();
+        if documents
+            .iter()
+            .any(|(_, embedding)| embedding.len() != dimensions)
+        {
+            return Err(AppError::InternalError(format!(
+                "Embedding model {} returned vectors of differing dimensions",
+                model
+            )));
+        }
+        self.set_embedding_model_dimensions(model, dimensions as i32)
+            .await?;
+
+        let mut query_builder = QueryBuilder::new(
+            "INSERT INTO rag_documents (id, doc_type, source_id, model, dimensions, content, content_hash, embedding, embedded_at) ",
+        );
+        query_builder.push_values(documents, |mut row, (document, embedding)| {
+            row.push_bind(cuid2::create_id())
+                .push_bind(document.doc_type)
+                .push_bind(document.source_id.clone())
+                .push_bind(model.to_string())
+                .push_bind(dimensions as i32)
+                .push_bind(document.content.clone())
+                .push_bind(hash_embedding_text(&document.content))
+                .push_bind(to_vector_literal(embedding))
+                .push_unseparated("::vector")
+                .push("NOW()");
+        });
+        query_builder.push(
+            " ON CONFLICT (doc_type, source_id, model) DO UPDATE SET
+                dimensions = EXCLUDED.dimensions,
+                content = EXCLUDED.content,
+                content_hash = EXCLUDED.content_hash,
+                embedding = EXCLUDED.embedding,
+                embedded_at = NOW()",
+        );
+        query_builder.build().execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    /// Drop a model's documents whose source is no longer among `current`, returning
+    /// the dropped sources' ids
+    pub async fn delete_orphaned_rag_documents(
+        &self,
+        model: &str,
+        current: &[RAGDocumentInput],
+    ) -> AppResult<Vec<String>> {
+        let ids_of = |doc_type: RAGDocumentType| -> Vec<String> {
+            current
+                .iter()
+                .filter(|document| document.doc_type == doc_type)
+                .map(|document| document.source_id.clone())
+                .collect()
+        };
+
+        let removed: Vec<String> = sqlx::query_scalar(
+            "DELETE FROM rag_documents
+            WHERE model = $1
+              AND (
+                (doc_type = 'news' AND source_id <> ALL($2))
+                OR (doc_type = 'tag' AND source_id <> ALL($3))
+              )
+            RETURNING source_id",
+        )
+        .bind(model)
+        .bind(ids_of(RAGDocumentType::News))
+        .bind(ids_of(RAGDocumentType::Tag))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(removed)
+    }
+
+    /// Nearest news and tag documents to a query vector, most similar first
+    /// Candidates come from the HNSW index, which keeps scanning until visibility and
+    /// the filters leave enough of them; identity and tag filters on a pgvector without
+    /// iterative scans are searched exactly
+    /// Without `include_hidden`, news linked to a hidden scholar is never returned;
+    /// identity and tag filters limit news to items about a matching scholar and
+    /// tag documents to the filtered tags
+    pub async fn search_rag_documents(
+        &self,
+        model: &str,
+        embedding: &[f32],
+        limit: i64,
+        similarity_threshold: f32,
+        filters: RAGFilters<'_>,
+    ) -> AppResult<Vec<RAGSource>> {
+        let vector = to_vector_literal(embedding);
+        let cast = vector_cast(embedding.len());
+        let candidates = limit * CANDIDATE_MULTIPLIER;
+        let identities = filters.identities.filter(|list| !list.is_empty());
+        let tags = filters.tags.filter(|list| !list.is_empty());
+
+        // Nearest matching documents through the index
+        let mut query_builder = QueryBuilder::new(
+            "WITH nearest AS (
+                SELECT
+                    d.doc_type,
+                    d.source_id,
+                    n.title AS news_title,
+                    n.source AS news_source,
+                    n.url AS news_url,
+                    n.publish_date AS news_publish_date,
+                    t.name AS tag_name,
+                    t.description AS tag_description,
+                    d.embedding",
+        );
+        query_builder.push(format!("{} <=> ", cast));
+        query_builder.push_bind(vector.clone());
+        query_builder.push(format!(
+            "{} AS distance
+                FROM rag_documents d
+                LEFT JOIN news n ON d.doc_type = 'news' AND n.id = d.source_id AND n.archived_at IS NULL
+                LEFT JOIN tags t ON d.doc_type = 'tag' AND t.id = d.source_id AND t.archived_at IS NULL
+                WHERE d.dimensions = {} AND (n.id IS NOT NULL OR t.id IS NOT NULL) AND d.model = ",
+            cast,
+            embedding.len()
+        ));
+        query_builder.push_bind(model.to_string());
+
+        if !filters.include_hidden {
+            query_builder.push(
+                " AND NOT EXISTS (
+                    SELECT 1 FROM news_scholars ns
+                    INNER JOIN scholars s ON s.id = ns.scholar
+                    WHERE ns.news = n.id AND s.visible = false AND s.deleted = false
+                )",
+            );
+        }
+
+        if identities.is_some() || tags.is_some() {
+            query_builder.push(
+                " AND (d.doc_type <> 'news' OR EXISTS (
+                    SELECT 1 FROM news_scholars ns
+                    INNER JOIN scholars s ON s.id = ns.scholar
+                    WHERE ns.news = n.id AND s.deleted = false",
+            );
+            if !filters.include_hidden {
+                query_builder.push(" AND s.visible = true");
+            }
+            if let Some(identity_list) = identities {
+                query_builder.push(" AND s.identity = ANY(");
+                query_builder.push_bind(identity_list);
+                query_builder.push(")");
+            }
+            if let Some(tag_list) = tags {
+                query_builder.push(
+                    " AND EXISTS (
+                        SELECT 1 FROM scholar_tags st
+                        INNER JOIN tags ft ON ft.id = st.tag
+                        WHERE st.scholar = s.id AND ft.name = ANY(",
+                );
+                query_builder.push_bind(tag_list);
+                query_builder.push("))");
+            }
+            query_builder.push("))");
+        }
+        if let Some(tag_list) = tags {
+            query_builder.push(" AND (d.doc_type <> 'tag' OR t.name = ANY(");
+            query_builder.push_bind(tag_list);
+            query_builder.push("))");
+        }
+
+        if self.iterative_scan || (identities.is_none() && tags.is_none()) {
+            query_builder.push(format!(" ORDER BY d.embedding{} <=> ", cast));
+            query_builder.push_bind(vector);
+            query_builder.push(format!("{} LIMIT ", cast));
+            query_builder.push_bind(candidates);
+        }
+
+        query_builder.push(
+            "
+            )
+            SELECT
+                doc_type,
+                source_id,
+                (1 - distance)::real AS similarity,
+                news_title,
+                news_source,
+                news_url,
+                news_publish_date,
+                tag_name,
+                tag_description
+            FROM nearest
+            WHERE distance < ",
+        );
+        query_builder.push_bind(1.0 - similarity_threshold as f64);
+        query_builder.push(" ORDER BY distance LIMIT ");
+        query_builder.push_bind(limit);
+
+        let mut tx = self.begin_vector_search(candidates).await?;
+        let rows = query_builder.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let id: String = row.get("source_id");
+                let similarity_score: f32 = row.get("similarity");
+                match row.get::<RAGDocumentType, _>("doc_type") {
+                    RAGDocumentType::News => RAGSource::News {
+                        id,
+                        title: row.get("news_title"),
+                        source: row.get("news_source"),
+                        url: row.get("news_url"),
+                        publish_date: row.get("news_publish_date"),
+                        similarity_score,
+                    },
+                    RAGDocumentType::Tag => RAGSource::Tag {
+                        id,
+                        name: row.get("tag_name"),
+                        description: row.get("tag_description"),
+                        similarity_score,
+                    },
+                }
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,129 @@
+use super::rag::to_vector_literal;
+use crate::models::*;
+use crate::utils::AppResult;
+
+// The plain `embedding <=> ...` comparisons below cannot use the per-dimension HNSW
+// indexes, so these debugging queries always scan exactly
+
+impl super::Database {
+    pub async fn get_stored_embedding(
+        &self,
+        scholar_id: &str,
+        model: &str,
+    ) -> AppResult<Option<StoredEmbedding>> {
+        let embedding = sqlx::query_as::<_, StoredEmbedding>(
+            "SELECT model, dimensions, embedding_text, content_hash, embedded_at
+            FROM scholar_embeddings
+            WHERE scholar_id = $1 AND model = $2",
+        )
+        .bind(scholar_id)
+        .bind(model)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(embedding)
+    }
+
+    pub async fn get_stored_passages(
+        &self,
+        scholar_id: &str,
+        model: &str,
+    ) -> AppResult<Vec<StoredPassage>> {
+        let passages = sqlx::query_as::<_, StoredPassage>(
+            "SELECT field, passage_index, content
+            FROM scholar_passages
+            WHERE scholar_id = $1 AND model = $2
+            ORDER BY field, passage_index",
+        )
+        .bind(scholar_id)
+        .bind(model)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(passages)
+    }
+
+    pub async fn is_embedding_refresh_queued(&self, scholar_id: &str) -> AppResult<bool> {
+        let (queued,): (bool,) = sqlx::query_as(
+            "SELECT EXISTS (SELECT 1 FROM embedding_refresh_queue WHERE scholar_id = $1)",
+        )
+        .bind(scholar_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(queued)
+    }
+
+    /// Non-deleted scholars, hidden ones included, nearest to a scholar's profile vector
+    pub async fn get_exact_scholar_neighbors(
+        &self,
+        scholar_id: &str,
+        model: &str,
+        limit: i64,
+    ) -> AppResult<Vec<EmbeddingNeighbor>> {
+        let neighbors = sqlx::query_as::<_, EmbeddingNeighbor>(
+            "WITH target AS (
+                SELECT embedding FROM scholar_embeddings WHERE scholar_id = $1 AND model = $2
+            )
+            SELECT
+                s.id,
+                s.name,
+                s.visible,
+                se.embedding <=> t.embedding AS distance,
+                1 - (se.embedding <=> t.embedding) AS similarity
+            FROM target t
+            INNER JOIN scholar_embeddings se ON se.model = $2 AND se.scholar_id <> $1
+            INNER JOIN scholars s ON s.id = se.scholar_id
+            WHERE s.deleted = false
+            ORDER BY distance, s.id
+            LIMIT $3",
+        )
+        .bind(scholar_id)
+        .bind(model)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(neighbors)
+    }
+
+    /// Every non-deleted scholar's best passage for a query vector, hidden ones included
+    pub async fn get_raw_query_similarities(
+        &self,
+        model: &str,
+        embedding: &[f32],
+        limit: i64,
+    ) -> AppResult<Vec<RawScholarSimilarity>> {
+        let scholars = sqlx::query_as::<_, RawScholarSimilarity>(
+            "WITH best AS (
+                SELECT DISTINCT ON (sp.scholar_id)
+                    sp.scholar_id,
+                    sp.field,
+                    sp.content,
+                    1 - (sp.embedding <=> $1::vector) AS similarity
+                FROM scholar_passages sp
+                WHERE sp.model = $2
+                ORDER BY sp.scholar_id, sp.embedding <=> $1::vector, sp.passage_index
+            )
+            SELECT
+                s.id,
+                s.name,
+                s.visible,
+                b.similarity,
+                b.field AS best_field,
+                b.content AS best_passage
+            FROM best b
+            INNER JOIN scholars s ON s.id = b.scholar_id
+            WHERE s.deleted = false
+            ORDER BY b.similarity DESC, s.id
+            LIMIT $3",
+        )
+        .bind(to_vector_literal(embedding))
+        .bind(model)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(scholars)
+    }
+}
@@ -0,0 +1,27 @@
+use crate::utils::AppResult;
+
+impl super::Database {
+    /// Count a public ask request for the client and return today's total
+    pub async fn increment_ask_requests(&self, client_ip: &str) -> AppResult<i32> {
+        let (requests,): (i32,) = sqlx::query_as(
+            "INSERT INTO ask_rate_limits (client_ip, day, requests)
+            VALUES ($1, CURRENT_DATE, 1)
+            ON CONFLICT (client_ip, day) DO UPDATE SET requests = ask_rate_limits.requests + 1
+            RETURNING requests",
+        )
+        .bind(client_ip)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(requests)
+    }
+
+    /// Remove counters from previous days
+    pub async fn cleanup_ask_rate_limits(&self) -> AppResult<u64> {
+        let result = sqlx::query("DELETE FROM ask_rate_limits WHERE day < CURRENT_DATE")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
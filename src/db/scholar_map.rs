@@ -0,0 +1,40 @@
+use crate::models::*;
+use crate::utils::AppResult;
+
+impl super::Database {
+    /// Changes whenever a visible scholar's vector is stored or a scholar gains or
+    /// loses its place on the map
+    pub async fn get_scholar_map_fingerprint(&self, model: &str) -> AppResult<String> {
+        let fingerprint: String = sqlx::query_scalar(
+            "SELECT md5(COALESCE(string_agg(se.scholar_id || ':' || se.updated_at::text, ',' ORDER BY se.scholar_id), ''))
+            FROM scholar_embeddings se
+            INNER JOIN scholars s ON s.id = se.scholar_id
+            WHERE se.model = $1 AND s.visible = true AND s.deleted = false"
+        )
+        .bind(model)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(fingerprint)
+    }
+
+    /// Names, identities and images of visible scholars, in the order of the ids
+    pub async fn get_scholar_map_details(
+        &self,
+        scholar_ids: &[String],
+    ) -> AppResult<Vec<ScholarMapDetails>> {
+        let details = sqlx::query_as::<_, ScholarMapDetails>(
+            "SELECT s.id, s.name, s.identity, i.filename AS image_filename
+            FROM UNNEST($1::text[]) WITH ORDINALITY AS ids(id, position)
+            INNER JOIN scholars s ON s.id = ids.id
+            LEFT JOIN images i ON i.id = s.image
+            WHERE s.visible = true AND s.deleted = false
+            ORDER BY ids.position",
+        )
+        .bind(scholar_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(details)
+    }
+}
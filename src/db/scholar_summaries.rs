@@ -0,0 +1,184 @@
+use crate::models::*;
+use crate::utils::{AppError, AppResult};
+
+/// SHA-256 of a scholar's introduction, matching `hash_embedding_text` on the Rust side
+const INTRODUCTION_HASH: &str = "encode(sha256(convert_to(s.introduction, 'UTF8')), 'hex')";
+
+impl super::Database {
+    pub async fn get_scholar_summary(&self, scholar_id: &str) -> AppResult<ScholarSummary> {
+        sqlx::query_as::<_, ScholarSummary>(&format!(
+            "SELECT ss.scholar_id, ss.summary, ss.locked, ss.model, ss.edited_by,
+                ss.content_hash <> {} AS stale, ss.created_at, ss.updated_at
+            FROM scholar_summaries ss
+            INNER JOIN scholars s ON s.id = ss.scholar_id
+            WHERE ss.scholar_id = $1 AND s.deleted = false",
+            INTRODUCTION_HASH
+        ))
+        .bind(scholar_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Summary of scholar {} not found", scholar_id)))
+    }
+
+    /// Scholars with an introduction but no summary, or an unlocked summary written
+    /// from an older introduction; ones that failed before wait out their backoff and
+    /// come after the rest, otherwise the most recently updated come first
+    pub async fn get_pending_scholar_summaries(
+        &self,
+        limit: i64,
+    ) -> AppResult<Vec<PendingScholarSummary>> {
+        let pending = sqlx::query_as::<_, PendingScholarSummary>(&format!(
+            "SELECT s.id, s.name, s.introduction
+            FROM scholars s
+            LEFT JOIN scholar_summaries ss ON ss.scholar_id = s.id
+            LEFT JOIN scholar_summary_failures f ON f.scholar_id = s.id AND f.content_hash = {0}
+            WHERE s.deleted = false AND btrim(s.introduction) <> ''
+                AND (ss.scholar_id IS NULL OR (ss.locked = false AND ss.content_hash <> {0}))
+                AND (f.scholar_id IS NULL OR f.next_attempt_at <= NOW())
+            ORDER BY COALESCE(f.attempts, 0), s.updated_at DESC
+            LIMIT $1",
+            INTRODUCTION_HASH
+        ))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(pending)
+    }
+
+    /// Store a generated summary unless an editor locked the scholar's summary meanwhile
+    pub async fn save_generated_summary(
+        &self,
+        scholar_id: &str,
+        summary: &str,
+        content_hash: &str,
+        model: &str,
+    ) -> AppResult<bool> {
+        let result = sqlx::query(
+            "INSERT INTO scholar_summaries (scholar_id, summary, content_hash, model)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (scholar_id) DO UPDATE SET
+                summary = EXCLUDED.summary,
+                content_hash = EXCLUDED.content_hash,
+                model = EXCLUDED.model,
+                edited_by = NULL,
+                updated_at = NOW()
+            WHERE scholar_summaries.locked = false",
+        )
+        .bind(scholar_id)
+        .bind(summary)
+        .bind(content_hash)
+        .bind(model)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record a failed attempt for an introduction and back off exponentially, up to
+    /// a day; a failure for an older introduction starts the count over
+    pub async fn fail_scholar_summary(
+        &self,
+        scholar_id: &str,
+        content_hash: &str,
+        error: &str,
+    ) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO scholar_summary_failures (scholar_id, content_hash, attempts, last_error, next_attempt_at)
+            VALUES ($1, $2, 1, $3, NOW() + INTERVAL '1 minute')
+            ON CONFLICT (scholar_id) DO UPDATE SET
+                content_hash = EXCLUDED.content_hash,
+                attempts = CASE WHEN scholar_summary_failures.content_hash = EXCLUDED.content_hash
+                    THEN scholar_summary_failures.attempts + 1 ELSE 1 END,
+                last_error = EXCLUDED.last_error,
+                next_attempt_at = CASE WHEN scholar_summary_failures.content_hash = EXCLUDED.content_hash
+                    THEN NOW() + LEAST(POWER(2, scholar_summary_failures.attempts), 1440) * INTERVAL '1 minute'
+                    ELSE EXCLUDED.next_attempt_at END",
+        )
+        .bind(scholar_id)
+        .bind(content_hash)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forget the failed attempts of a scholar that has been summarised
+    pub async fn clear_scholar_summary_failures(&self, scholar_id: &str) -> AppResult<()> {
+        sqlx::query("DELETE FROM scholar_summary_failures WHERE scholar_id = $1")
+            .bind(scholar_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Rewrite and/or lock a summary; rewritten text counts as written from the
+    /// current introduction
+    pub async fn update_scholar_summary(
+        &self,
+        scholar_id: &str,
+        input: &ScholarSummaryRequest,
+        user_id: &str,
+    ) -> AppResult<ScholarSummary> {
+        let result = match &input.summary {
+            Some(summary) => {
+                sqlx::query(&format!(
+                    "INSERT INTO scholar_summaries (scholar_id, summary, content_hash, locked, edited_by)
+                    SELECT s.id, $2, {}, COALESCE($3, false), $4
+                    FROM scholars s
+                    WHERE s.id = $1 AND s.deleted = false
+                    ON CONFLICT (scholar_id) DO UPDATE SET
+                        summary = EXCLUDED.summary,
+                        content_hash = EXCLUDED.content_hash,
+                        locked = COALESCE($3, scholar_summaries.locked),
+                        model = NULL,
+                        edited_by = EXCLUDED.edited_by,
+                        updated_at = NOW()",
+                    INTRODUCTION_HASH
+                ))
+                .bind(scholar_id)
+                .bind(summary.trim())
+                .bind(input.locked)
+                .bind(user_id)
+                .execute(&self.pool)
+                .await?
+            }
+            None => {
+                sqlx::query(
+                    "UPDATE scholar_summaries SET locked = COALESCE($2, locked), updated_at = NOW()
+                    WHERE scholar_id = $1",
+                )
+                .bind(scholar_id)
+                .bind(input.locked)
+                .execute(&self.pool)
+                .await?
+            }
+        };
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound(format!(
+                "Summary of scholar {} not found",
+                scholar_id
+            )));
+        }
+        self.get_scholar_summary(scholar_id).await
+    }
+
+    /// Drop a summary so it is generated afresh
+    pub async fn delete_scholar_summary(&self, scholar_id: &str) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM scholar_summaries WHERE scholar_id = $1")
+            .bind(scholar_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound(format!(
+                "Summary of scholar {} not found",
+                scholar_id
+            )));
+        }
+        Ok(())
+    }
+}
@@ -125,6 +125,16 @@ impl super::Database {
         }
     }
 
+    /// Number of scholars matching the query's filters, ignoring pagination
+    pub async fn count_scholars(&self, query: &ScholarQuery, public: bool) -> AppResult<i64> {
+        let mut count_builder = QueryBuilder::new("SELECT COUNT(*) FROM scholars s WHERE 1=1");
+        Self::build_scholar_filters(&mut count_builder, query, public);
+
+        let total: (i64,) = count_builder.build_query_as().fetch_one(&self.pool).await?;
+
+        Ok(total.0)
+    }
+
     pub async fn list_scholars(
         &self,
         query: &ScholarQuery,
@@ -135,12 +145,9 @@ impl super::Database {
             .validate()
             .map_err(|e| AppError::ValidationError(e))?;
 
-        let mut count_builder = QueryBuilder::new("SELECT COUNT(*) FROM scholars s WHERE 1=1");
-        Self::build_scholar_filters(&mut count_builder, query, public);
-
-        let total: (i64,) = count_builder.build_query_as().fetch_one(&self.pool).await?;
+        let total = self.count_scholars(query, public).await?;
 
-        if total.0 == 0 {
+        if total == 0 {
             return Ok((Vec::new(), 0));
         }
 
@@ -148,9 +155,10 @@ impl super::Database {
             "SELECT s.id, s.name, s.gender, s.field_of_research, s.year_of_birth,
                     s.image, s.featured, s.visible, s.deleted, s.identity, s.version,
                     s.created_at, s.updated_at,
-                    i.filename as image_filename
+                    i.filename as image_filename, ss.summary
              FROM scholars s
              LEFT JOIN images i ON s.image = i.id
+             LEFT JOIN scholar_summaries ss ON ss.scholar_id = s.id
              WHERE 1=1",
         );
         Self::build_scholar_filters(&mut query_builder, query, public);
@@ -175,6 +183,7 @@ impl super::Database {
         let mut scholars = Vec::with_capacity(row_count);
         let mut scholar_ids = Vec::with_capacity(row_count);
         let mut image_filenames = std::collections::HashMap::with_capacity(row_count);
+        let mut summaries = std::collections::HashMap::with_capacity(row_count);
 
         for row in rows {
             let scholar_id: String = row.get("id");
@@ -184,6 +193,10 @@ impl super::Database {
             if let Some(filename) = image_filename {
                 image_filenames.insert(scholar_id.clone(), filename);
             }
+            let summary: Option<String> = row.get("summary");
+            if let Some(summary) = summary {
+                summaries.insert(scholar_id.clone(), summary);
+            }
 
             scholars.push(ScholarListItem {
                 id: scholar_id,
@@ -234,15 +247,17 @@ impl super::Database {
             .map(|scholar| {
                 let tags = tags_map.remove(&scholar.id).unwrap_or_default();
                 let image_filename = image_filenames.remove(&scholar.id);
+                let summary = summaries.remove(&scholar.id);
                 ScholarListItemExt {
                     scholar,
                     tags,
                     image_filename,
+                    summary,
                 }
             })
             .collect();
 
-        Ok((results, total.0))
+        Ok((results, total))
     }
 
     pub async fn get_scholar(&self, id: &str) -> AppResult<ScholarResponse> {
@@ -287,6 +302,25 @@ impl super::Database {
         self.build_scholar_response(row).await
     }
 
+    /// A non-deleted scholar, or only a visible one unless `include_hidden` is set
+    pub async fn get_scholar_visible(
+        &self,
+        id: &str,
+        include_hidden: bool,
+    ) -> AppResult<ScholarResponse> {
+        if !include_hidden {
+            return self.get_scholar_public(id).await;
+        }
+        let scholar = self.get_scholar(id).await?;
+        if scholar.deleted {
+            return Err(AppError::NotFound(format!(
+                "Scholar with id {} not found",
+                id
+            )));
+        }
+        Ok(scholar)
+    }
+
     async fn build_scholar_response(&self, row: sqlx::postgres::PgRow) -> AppResult<ScholarResponse> {
         use sqlx::Row;
 
@@ -545,6 +579,27 @@ impl super::Database {
         Ok(scholars.into_iter().map(|s| (s.id.clone(), s)).collect())
     }
 
+    /// Recently edited featured profiles, as examples of the house style for AI drafting
+    pub async fn get_style_example_scholars(
+        &self,
+        limit: i64,
+        exclude_id: Option<&str>,
+    ) -> AppResult<Vec<Scholar>> {
+        let scholars = sqlx::query_as::<_, Scholar>(
+            "SELECT * FROM scholars
+             WHERE featured = true AND visible = true AND deleted = false
+                AND ($2::TEXT IS NULL OR id <> $2)
+             ORDER BY updated_at DESC
+             LIMIT $1",
+        )
+        .bind(limit)
+        .bind(exclude_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(scholars)
+    }
+
     pub async fn delete_scholar(&self, scholar_id: &str) -> AppResult<()> {
         let result = sqlx::query("UPDATE scholars SET deleted = true WHERE id = $1")
             .bind(scholar_id)
@@ -0,0 +1,180 @@
+use sqlx::{Postgres, Transaction};
+
+use crate::utils::AppResult;
+
+/// Tables holding untyped VECTOR columns alongside a `dimensions` column
+const VECTOR_TABLES: [&str; 3] = ["scholar_passages", "scholar_embeddings", "rag_documents"];
+/// pgvector cannot build HNSW indexes on wider `vector` columns
+const HNSW_MAX_DIMENSIONS: i32 = 2000;
+/// Highest ef_search pgvector accepts
+const HNSW_MAX_EF_SEARCH: i64 = 1000;
+
+/// HNSW build and query parameters
+/// Changing `m` or `ef_construction` rebuilds the indexes on the next start
+#[derive(Debug, Clone, Copy)]
+pub struct VectorIndexSettings {
+    pub m: i32,
+    pub ef_construction: i32,
+    /// Candidate list size per query; searches that post-filter ask for more
+    pub ef_search: i64,
+}
+
+impl VectorIndexSettings {
+    /// Read HNSW_M, HNSW_EF_CONSTRUCTION and HNSW_EF_SEARCH, clamped to what pgvector accepts
+    pub fn from_env() -> Self {
+        let setting = |key: &str, default: i64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        };
+
+        let m = setting("HNSW_M", 16).clamp(2, 100) as i32;
+        // pgvector requires ef_construction of at least twice m
+        let ef_construction = setting("HNSW_EF_CONSTRUCTION", 64).clamp(2 * m as i64, 1000) as i32;
+        let ef_search = setting("HNSW_EF_SEARCH", 100).clamp(1, HNSW_MAX_EF_SEARCH);
+
+        Self {
+            m,
+            ef_construction,
+            ef_search,
+        }
+    }
+
+    fn index_name(&self, table: &str, dimensions: i32) -> String {
+        format!(
+            "idx_{}_hnsw_{}_m{}_ef{}",
+            table, dimensions, self.m, self.ef_construction
+        )
+    }
+}
+
+/// Cast that lets a query over an untyped VECTOR column use the index for `dimensions`
+pub(super) fn vector_cast(dimensions: usize) -> String {
+    format!("::vector({})", dimensions)
+}
+
+/// pgvector gained `hnsw.iterative_scan` in 0.8.0
+pub(super) fn supports_iterative_scan(version: &str) -> bool {
+    let mut parts = version
+        .split('.')
+        .map(|part| part.parse::<u32>().unwrap_or(0));
+    let major = parts.next().unwrap_or(0);
+    let minor = parts.next().unwrap_or(0);
+    (major, minor) >= (0, 8)
+}
+
+impl super::Database {
+    /// Build the HNSW indexes for every dimension in use and drop the rest
+    /// Vector columns are untyped so models of any size can share them; each dimension
+    /// gets its own partial expression index, which queries reach through `vector_cast`
+    pub async fn ensure_vector_indexes(&self) -> AppResult<()> {
+        let dimensions: Vec<(i32,)> = sqlx::query_as(
+            "SELECT DISTINCT dimensions FROM embedding_models
+            WHERE dimensions IS NOT NULL AND status <> 'retired'",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let dimensions: Vec<i32> = dimensions.into_iter().map(|(d,)| d).collect();
+
+        let existing: Vec<(String,)> = sqlx::query_as(
+            "SELECT indexname FROM pg_indexes
+            WHERE schemaname = current_schema() AND indexname LIKE 'idx\\_%\\_hnsw\\_%'",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let wanted: Vec<String> = VECTOR_TABLES
+            .iter()
+            .flat_map(|table| {
+                dimensions
+                    .iter()
+                    .filter(|&&d| d <= HNSW_MAX_DIMENSIONS)
+                    .map(|&d| self.vector_index.index_name(table, d))
+            })
+            .collect();
+        for (name,) in existing {
+            if !wanted.contains(&name) {
+                sqlx::query(&format!("DROP INDEX IF EXISTS {}", name))
+                    .execute(&self.pool)
+                    .await?;
+                log::info!("Dropped vector index {}", name);
+            }
+        }
+
+        for &d in &dimensions {
+            self.ensure_vector_indexes_for(d).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Build the HNSW indexes for one dimension if they don't exist yet
+    pub(super) async fn ensure_vector_indexes_for(&self, dimensions: i32) -> AppResult<()> {
+        if dimensions > HNSW_MAX_DIMENSIONS {
+            log::warn!(
+                "Vectors with {} dimensions exceed pgvector's HNSW limit of {}; searches scan them exactly",
+                dimensions,
+                HNSW_MAX_DIMENSIONS
+            );
+            return Ok(());
+        }
+
+        for table in VECTOR_TABLES {
+            let name = self.vector_index.index_name(table, dimensions);
+            let (exists,): (bool,) = sqlx::query_as(
+                "SELECT EXISTS (
+                    SELECT 1 FROM pg_indexes
+                    WHERE schemaname = current_schema() AND indexname = $1
+                )",
+            )
+            .bind(&name)
+            .fetch_one(&self.pool)
+            .await?;
+            if exists {
+                continue;
+            }
+
+            // Identifiers and integers only, so formatting the statement is safe
+            sqlx::query(&format!(
+                "CREATE INDEX IF NOT EXISTS {name} ON {table}
+                USING hnsw ((embedding{cast}) vector_cosine_ops)
+                WITH (m = {m}, ef_construction = {ef_construction})
+                WHERE dimensions = {dimensions}",
+                cast = vector_cast(dimensions as usize),
+                m = self.vector_index.m,
+                ef_construction = self.vector_index.ef_construction,
+            ))
+            .execute(&self.pool)
+            .await?;
+            log::info!("Built vector index {}", name);
+        }
+
+        Ok(())
+    }
+
+    /// Open a transaction whose HNSW scans consider at least `candidates` vectors and,
+    /// where pgvector supports it, keep scanning until filtered queries fill their limit
+    pub(super) async fn begin_vector_search(
+        &self,
+        candidates: i64,
+    ) -> AppResult<Transaction<'_, Postgres>> {
+        let ef_search = candidates
+            .max(self.vector_index.ef_search)
+            .min(HNSW_MAX_EF_SEARCH);
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT set_config('hnsw.ef_search', $1, true)")
+            .bind(ef_search.to_string())
+            .execute(&mut *tx)
+            .await?;
+        if self.iterative_scan {
+            // Callers order the results themselves, so strict ordering buys nothing
+            sqlx::query("SELECT set_config('hnsw.iterative_scan', 'relaxed_order', true)")
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        Ok(tx)
+    }
+}
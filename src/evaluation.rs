@@ -0,0 +1,114 @@
+use chrono::Utc;
+use std::collections::HashSet;
+
+use crate::handlers::rag::{
+    RetrievalQuery, active_embedding_model, get_query_embedding, retrieve_scholars,
+};
+use crate::models::{
+    EvalQuestion, EvalQuestionResult, EvalRun, EvalRunSummary, RAGFilters, UsageContext,
+};
+use crate::utils::{AppError, AppResult, AppState};
+
+/// Run every stored question through retrieval as currently configured (active embedding
+/// model, filters and reranker) and store the scored run
+pub async fn run_retrieval_evaluation(
+    app_state: &AppState,
+    usage: UsageContext<'_>,
+    k: i64,
+    threshold: f32,
+) -> AppResult<EvalRun> {
+    let questions = app_state.db.list_eval_questions().await?;
+    if questions.is_empty() {
+        return Err(AppError::BadRequest(
+            "No evaluation questions to run".to_string(),
+        ));
+    }
+
+    let model = active_embedding_model(app_state).await?;
+    let mut results = Vec::with_capacity(questions.len());
+    for question in &questions {
+        results.push(evaluate_question(app_state, usage, question, &model, k, threshold).await?);
+    }
+
+    let count = results.len() as f32;
+    let summary = EvalRunSummary {
+        id: cuid2::create_id(),
+        embedding_model: model,
+        reranker: app_state.reranker.name().to_string(),
+        k: k as i32,
+        threshold,
+        question_count: results.len() as i32,
+        recall_at_k: results.iter().map(|r| r.recall).sum::<f32>() / count,
+        mrr: results.iter().map(|r| r.reciprocal_rank).sum::<f32>() / count,
+        created_by: usage.user_id.map(str::to_string),
+        created_at: Utc::now(),
+    };
+    app_state.db.create_eval_run(&summary, &results).await?;
+
+    Ok(EvalRun {
+        summary,
+        results: sqlx::types::Json(results),
+    })
+}
+
+async fn evaluate_question(
+    app_state: &AppState,
+    usage: UsageContext<'_>,
+    question: &EvalQuestion,
+    model: &str,
+    k: i64,
+    threshold: f32,
+) -> AppResult<EvalQuestionResult> {
+    let embedding = get_query_embedding(&question.question, model, false, usage, app_state).await?;
+    let filters = RAGFilters {
+        include_hidden: question.include_hidden,
+        identities: question.identities.as_deref(),
+        tags: question.tags.as_deref(),
+        scholar_ids: None,
+    };
+    let retrieved = retrieve_scholars(
+        app_state,
+        usage,
+        RetrievalQuery {
+            text: &question.question,
+            model,
+            embedding: &embedding,
+        },
+        k,
+        threshold,
+        filters,
+    )
+    .await?;
+    let retrieved_ids: Vec<String> = retrieved.into_iter().map(|s| s.id).collect();
+
+    let expected: HashSet<&str> = question
+        .expected_scholar_ids
+        .iter()
+        .map(String::as_str)
+        .collect();
+    let found = retrieved_ids
+        .iter()
+        .filter(|id| expected.contains(id.as_str()))
+        .count();
+    let reciprocal_rank = retrieved_ids
+        .iter()
+        .position(|id| expected.contains(id.as_str()))
+        .map(|rank| 1.0 / (rank + 1) as f32)
+        .unwrap_or(0.0);
+    let missed = question
+        .expected_scholar_ids
+        .iter()
+        .filter(|id| !retrieved_ids.contains(id))
+        .cloned()
+        .collect();
+
+    Ok(EvalQuestionResult {
+        question_id: question.id.clone(),
+        question: question.question.clone(),
+        expected_scholar_ids: question.expected_scholar_ids.clone(),
+        recall: found as f32 / expected.len().max(1) as f32,
+        reciprocal_rank,
+        retrieved_scholar_ids: retrieved_ids,
+        missed_scholar_ids: missed,
+    })
+}
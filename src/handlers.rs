@@ -1,8 +1,15 @@
 pub mod auth;
+pub mod clustering;
+pub mod evaluation;
 pub mod identities;
 pub mod images;
 pub mod news;
+pub mod profile_drafts;
+pub mod prompt_templates;
 pub mod rag;
+pub mod rag_inspection;
+pub mod scholar_suggestions;
+pub mod scholar_summaries;
 pub mod scholars;
 pub mod tags;
 pub mod users;
@@ -0,0 +1,56 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+
+use crate::clustering::run_scholar_clustering;
+use crate::middleware::{extract_claims, require_admin};
+use crate::models::*;
+use crate::utils::{AppResult, AppState};
+
+/// Cluster the scholars' profile vectors into topics and compare them with the tags
+/// Clusters no tag dominates are flagged as candidates for new tags
+/// Requires admin permission
+pub async fn run_clustering(
+    app_state: web::Data<AppState>,
+    body: web::Json<ClusterRunRequest>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let run = run_scholar_clustering(&app_state, Some(&claims.user_id), &body).await?;
+    log::info!(
+        "Clustering {} by {}: {} scholars into {} clusters, {} tag candidates",
+        run.summary.id,
+        claims.user_id,
+        run.summary.scholar_count,
+        run.summary.k,
+        run.summary.candidate_count
+    );
+
+    Ok(HttpResponse::Created().json(run))
+}
+
+/// Past runs, most recent first, without their clusters
+pub async fn list_cluster_runs(
+    app_state: web::Data<AppState>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let runs = app_state.db.list_cluster_runs(100).await?;
+
+    Ok(HttpResponse::Ok().json(runs))
+}
+
+pub async fn get_cluster_run(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let run = app_state.db.get_cluster_run(&path.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(run))
+}
@@ -0,0 +1,154 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+
+use crate::evaluation::run_retrieval_evaluation;
+use crate::middleware::{extract_claims, require_admin, validate_input};
+use crate::models::*;
+use crate::usage::check_quota;
+use crate::utils::{AppError, AppResult, AppState};
+
+/// Expected scholars must exist, or the question could never be answered
+async fn validate_eval_question(
+    app_state: &AppState,
+    input: &EvalQuestionRequest,
+) -> AppResult<()> {
+    validate_input(input)?;
+
+    let unknown = app_state
+        .db
+        .find_unknown_scholar_ids(&input.expected_scholar_ids)
+        .await?;
+    if !unknown.is_empty() {
+        return Err(AppError::ValidationError(format!(
+            "Unknown scholar ids: {}",
+            unknown.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+pub async fn list_eval_questions(
+    app_state: web::Data<AppState>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let questions = app_state.db.list_eval_questions().await?;
+
+    Ok(HttpResponse::Ok().json(questions))
+}
+
+pub async fn get_eval_question(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let question = app_state.db.get_eval_question(&path.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(question))
+}
+
+pub async fn create_eval_question(
+    app_state: web::Data<AppState>,
+    input: web::Json<EvalQuestionRequest>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+    validate_eval_question(&app_state, &input).await?;
+
+    let question = app_state
+        .db
+        .create_eval_question(&input, &claims.user_id)
+        .await?;
+
+    Ok(HttpResponse::Created().json(question))
+}
+
+pub async fn update_eval_question(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    input: web::Json<EvalQuestionRequest>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+    validate_eval_question(&app_state, &input).await?;
+
+    let question = app_state
+        .db
+        .update_eval_question(&path.into_inner(), &input)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(question))
+}
+
+pub async fn delete_eval_question(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    app_state
+        .db
+        .delete_eval_question(&path.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Score retrieval as currently configured against every stored question
+/// The run is kept for comparison with earlier and later ones
+pub async fn run_eval(
+    app_state: web::Data<AppState>,
+    body: web::Json<EvalRunRequest>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+    check_quota(&app_state, &claims).await?;
+
+    let usage = UsageContext::user(&claims.user_id, "retrieval_eval");
+    let run = run_retrieval_evaluation(&app_state, usage, body.k, body.threshold).await?;
+    log::info!(
+        "Retrieval evaluation {} by {}: recall@{} {:.3}, MRR {:.3}",
+        run.summary.id,
+        claims.user_id,
+        run.summary.k,
+        run.summary.recall_at_k,
+        run.summary.mrr
+    );
+
+    Ok(HttpResponse::Created().json(run))
+}
+
+/// Past runs, most recent first, without per-question results
+pub async fn list_eval_runs(
+    app_state: web::Data<AppState>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let runs = app_state.db.list_eval_runs(100).await?;
+
+    Ok(HttpResponse::Ok().json(runs))
+}
+
+pub async fn get_eval_run(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let run = app_state.db.get_eval_run(&path.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(run))
+}
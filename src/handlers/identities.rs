@@ -56,6 +56,10 @@ pub async fn update_identity(
         .update_identity(&identity_id, &input, &claims.user_id)
         .await?;
 
+    let scholar_ids = app_state.db.get_identity_scholar_ids(&identity.id).await?;
+    crate::handlers::rag::queue_embedding_refresh(&app_state, &scholar_ids, "identity_updated")
+        .await;
+
     app_state.cache.invalidate_pattern("/api/identities").await;
     app_state.cache.invalidate_pattern("/api/scholars").await;
 
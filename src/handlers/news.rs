@@ -79,6 +79,7 @@ pub async fn create_news(
 
     let news = app_state.db.create_news(&input, &claims.user_id).await?;
     let scholar_ids = app_state.db.get_news_scholars(&news.id).await?;
+    crate::handlers::rag::queue_embedding_refresh(&app_state, &scholar_ids, "news_linked").await;
     let scholars_map = app_state.db.get_scholars_info(&scholar_ids).await?;
 
     let scholars: Vec<ScholarInfo> = scholar_ids
@@ -87,6 +88,7 @@ pub async fn create_news(
         .collect();
 
     app_state.cache.invalidate_pattern("/api/news").await;
+    crate::handlers::rag::queue_rag_document_sync(&app_state, std::slice::from_ref(&news.id));
 
     Ok(HttpResponse::Created().json(NewsResponse { news, scholars }))
 }
@@ -101,11 +103,24 @@ pub async fn update_news(
     let news_id = path.into_inner();
     validate_input(&*input)?;
 
+    // Scholars unlinked by the update need their embeddings refreshed too
+    let previous_scholar_ids = app_state.db.get_news_scholars(&news_id).await?;
     let news = app_state
         .db
         .update_news(&news_id, &input, &claims.user_id)
         .await?;
     let scholar_ids = app_state.db.get_news_scholars(&news_id).await?;
+
+    let mut affected_scholar_ids = previous_scholar_ids;
+    affected_scholar_ids.extend(scholar_ids.iter().cloned());
+    affected_scholar_ids.sort();
+    affected_scholar_ids.dedup();
+    crate::handlers::rag::queue_embedding_refresh(
+        &app_state,
+        &affected_scholar_ids,
+        "news_updated",
+    )
+    .await;
     let scholars_map = app_state.db.get_scholars_info(&scholar_ids).await?;
 
     let scholars: Vec<ScholarInfo> = scholar_ids
@@ -114,6 +129,7 @@ pub async fn update_news(
         .collect();
 
     app_state.cache.invalidate_pattern("/api/news").await;
+    crate::handlers::rag::queue_rag_document_sync(&app_state, &[news_id]);
 
     Ok(HttpResponse::Ok().json(NewsResponse { news, scholars }))
 }
@@ -127,9 +143,12 @@ pub async fn delete_news(
     require_admin(&claims)?;
 
     let news_id = path.into_inner();
+    let scholar_ids = app_state.db.get_news_scholars(&news_id).await?;
     app_state.db.delete_news(&news_id).await?;
+    crate::handlers::rag::queue_embedding_refresh(&app_state, &scholar_ids, "news_deleted").await;
 
     app_state.cache.invalidate_pattern("/api/news").await;
+    crate::handlers::rag::queue_rag_document_sync(&app_state, &[news_id]);
 
     Ok(HttpResponse::NoContent().finish())
 }
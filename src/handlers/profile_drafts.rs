@@ -0,0 +1,137 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use chrono::Datelike;
+use serde::Deserialize;
+
+use crate::handlers::rag::call_llm_conversation;
+use crate::middleware::{extract_claims, require_ai_access, validate_input};
+use crate::models::*;
+use crate::usage::check_quota;
+use crate::utils::{AppError, AppResult, AppState};
+
+/// Published profiles shown to the model as examples of the house style
+const STYLE_EXAMPLES: i64 = 2;
+
+/// Shape the model is asked to reply with
+#[derive(Debug, Deserialize)]
+struct DraftReply {
+    introduction: String,
+    social_influence: String,
+    field_of_research: Option<DraftSuggestion<String>>,
+    year_of_birth: Option<DraftSuggestion<i32>>,
+}
+
+/// Whitespace-insensitive containment, so quotes that reflow line breaks still count
+fn quotes_source(source: &str, sentence: &str) -> bool {
+    let normalize = |text: &str| text.split_whitespace().collect::<String>();
+    let sentence = normalize(sentence);
+    !sentence.is_empty() && normalize(source).contains(&sentence)
+}
+
+/// Keep a suggestion only when its supporting sentence is really in the source
+fn supported<T>(
+    suggestion: Option<DraftSuggestion<T>>,
+    source: &str,
+) -> Option<DraftSuggestion<T>> {
+    suggestion.filter(|suggestion| {
+        let found = quotes_source(source, &suggestion.source_sentence);
+        if !found {
+            log::info!("Dropping draft suggestion not quoted from the source");
+        }
+        found
+    })
+}
+
+/// Draft an introduction and social influence section from pasted source material,
+/// with suggested field of research and year of birth quoted from the source
+/// Nothing is saved; the editor reviews the draft in the scholar form
+/// Requires editor or higher permission
+pub async fn draft_scholar_profile(
+    app_state: web::Data<AppState>,
+    body: web::Json<ProfileDraftRequest>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_ai_access(&claims)?;
+    validate_input(&*body)?;
+    check_quota(&app_state, &claims).await?;
+
+    let existing = match &body.scholar_id {
+        Some(id) => Some(app_state.db.get_scholar(id).await?),
+        None => None,
+    };
+    let examples = app_state
+        .db
+        .get_style_example_scholars(STYLE_EXAMPLES, body.scholar_id.as_deref())
+        .await?;
+
+    let style = examples
+        .iter()
+        .map(|example| {
+            format!(
+                "## {}\nIntroduction:\n{}\n\nSocial influence:\n{}",
+                example.name, example.introduction, example.social_influence
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let system_prompt = format!(
+        "You draft scholar profiles for a scholar database from source material supplied by an editor. \
+        Write an introduction and a social influence section in Markdown, matching the language, length, \
+        tone and structure of these published profiles:\n\n{}\n\n\
+        Use only facts stated in the source or in the current profile; never invent dates, titles or awards. \
+        Also suggest the scholar's field of research (a short phrase) and year of birth, each with the \
+        sentence of the source that supports it, copied verbatim. Leave a suggestion null when the source \
+        does not state it.\n\
+        Reply with JSON only: {{\"introduction\": string, \"social_influence\": string, \
+        \"field_of_research\": {{\"value\": string, \"source_sentence\": string}} | null, \
+        \"year_of_birth\": {{\"value\": number, \"source_sentence\": string}} | null}}",
+        if style.is_empty() {
+            "No published profiles yet; write in Chinese, in a concise encyclopedic tone."
+                .to_string()
+        } else {
+            style
+        }
+    );
+
+    let mut user_message = String::new();
+    if let Some(scholar) = &existing {
+        user_message.push_str(&format!(
+            "Current profile of {} (revise and extend it with the source):\nIntroduction:\n{}\n\nSocial influence:\n{}\n\n",
+            scholar.name, scholar.introduction, scholar.social_influence
+        ));
+    }
+    user_message.push_str(&format!("Source material:\n{}", body.source_text));
+    let messages = [ChatMessage {
+        role: "user".to_string(),
+        content: user_message,
+    }];
+
+    let usage = UsageContext::user(&claims.user_id, "profile_draft");
+    let reply =
+        call_llm_conversation(&system_prompt, &messages, 0.3, 2500, usage, &app_state).await?;
+
+    // Tolerate prose or code fences around the object
+    let draft: DraftReply = reply
+        .find('{')
+        .zip(reply.rfind('}'))
+        .and_then(|(start, end)| serde_json::from_str(reply.get(start..=end)?).ok())
+        .ok_or_else(|| {
+            // The reply quotes the source material; keep it in the log, out of the error
+            log::warn!("Invalid draft reply: {}", reply);
+            AppError::UpstreamInvalidResponse("LLM API sent an unreadable draft".to_string())
+        })?;
+
+    let current_year = chrono::Utc::now().year();
+    let year_of_birth = supported(draft.year_of_birth, &body.source_text)
+        .filter(|year| (MIN_YEAR_OF_BIRTH..=current_year).contains(&year.value));
+    let field_of_research = supported(draft.field_of_research, &body.source_text)
+        .filter(|field| !field.value.trim().is_empty());
+
+    Ok(HttpResponse::Ok().json(ProfileDraftResponse {
+        introduction: draft.introduction,
+        social_influence: draft.social_influence,
+        field_of_research,
+        year_of_birth,
+    }))
+}
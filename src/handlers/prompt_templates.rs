@@ -0,0 +1,182 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+
+use crate::handlers::rag::{
+    RetrievalQuery, active_embedding_model, fit_context_budget, fit_document_budget,
+    get_query_embedding, render_system_prompt, retrieve_documents, retrieve_scholars,
+};
+use crate::middleware::{extract_claims, require_admin, validate_input};
+use crate::models::*;
+use crate::usage::check_quota;
+use crate::utils::{AppError, AppResult, AppState};
+
+/// Reject references to variables that are never substituted, and prompts that
+/// would leave the retrieved scholars out
+fn check_prompt_variables(system_prompt: &str) -> AppResult<()> {
+    let mut rest = system_prompt;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('}') else { break };
+        let name = &rest[..end];
+        let is_variable =
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if is_variable && !PROMPT_VARIABLES.contains(&name) {
+            return Err(AppError::ValidationError(format!(
+                "Unknown prompt variable {{{}}}; available: {}",
+                name,
+                PROMPT_VARIABLES
+                    .iter()
+                    .map(|v| format!("{{{}}}", v))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+    }
+
+    if !system_prompt.contains("{context}") {
+        return Err(AppError::ValidationError(
+            "System prompt must include {context}".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn list_prompt_templates(
+    app_state: web::Data<AppState>,
+    query: web::Query<PromptTemplateQuery>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let templates = app_state.db.list_prompt_templates(query.use_case).await?;
+
+    Ok(HttpResponse::Ok().json(templates))
+}
+
+pub async fn get_prompt_template(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let template = app_state.db.get_prompt_template(&path.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(template))
+}
+
+/// Templates are immutable: editing one means creating its next version
+pub async fn create_prompt_template(
+    app_state: web::Data<AppState>,
+    input: web::Json<PromptTemplateRequest>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+    validate_input(&*input)?;
+    check_prompt_variables(&input.system_prompt)?;
+
+    let template = app_state
+        .db
+        .create_prompt_template(&input, &claims.user_id)
+        .await?;
+    log::info!(
+        "Prompt template {:?} v{} created by {}",
+        template.use_case,
+        template.version,
+        claims.user_id
+    );
+
+    Ok(HttpResponse::Created().json(template))
+}
+
+/// Switch a use case over to this version
+pub async fn activate_prompt_template(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let template = app_state
+        .db
+        .activate_prompt_template(&path.into_inner())
+        .await?;
+    log::info!(
+        "Prompt template {:?} v{} activated by {}",
+        template.use_case,
+        template.version,
+        claims.user_id
+    );
+
+    Ok(HttpResponse::Ok().json(template))
+}
+
+pub async fn delete_prompt_template(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    app_state
+        .db
+        .delete_prompt_template(&path.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Render a template against a sample query without calling the chat model
+/// Retrieval runs as it would for the template's use case, over visible scholars only
+pub async fn preview_prompt_template(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    body: web::Json<PromptPreviewRequest>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    if body.query.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "A sample query is required".to_string(),
+        ));
+    }
+
+    let template = app_state.db.get_prompt_template(&path.into_inner()).await?;
+
+    check_quota(&app_state, &claims).await?;
+    let usage = UsageContext::user(&claims.user_id, "prompt_preview");
+    let model = active_embedding_model(&app_state).await?;
+    let query_embedding =
+        get_query_embedding(&body.query, &model, false, usage, &app_state).await?;
+    let filters = RAGFilters {
+        include_hidden: false,
+        identities: None,
+        tags: None,
+        scholar_ids: None,
+    };
+    let query = RetrievalQuery {
+        text: &body.query,
+        model: &model,
+        embedding: &query_embedding,
+    };
+    let scholars = retrieve_scholars(&app_state, usage, query, body.limit, 0.0, filters).await?;
+    let documents = retrieve_documents(&app_state, query, 0.0, filters).await?;
+    let (scholars, mut context_truncation) = fit_context_budget(scholars, &app_state);
+    let documents = fit_document_budget(documents, &mut context_truncation);
+
+    Ok(HttpResponse::Ok().json(PromptPreviewResponse {
+        template: template.version_ref(),
+        system_prompt: render_system_prompt(&template, &scholars, &documents, &body.query),
+        temperature: template.temperature,
+        max_tokens: template.max_tokens,
+        retrieved_scholars: scholars,
+        retrieved_documents: documents,
+        context_truncation,
+    }))
+}
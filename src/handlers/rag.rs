@@ -1,424 +1,2005 @@
 use actix_web::{HttpRequest, HttpResponse, web};
 use serde_json::json;
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::atomic::Ordering;
 
+use crate::chat_tools::{ChatTools, TOOL_INSTRUCTIONS};
+use crate::db::rag::hash_embedding_text;
+use crate::guardrails::{self, CONTEXT_CLOSE, CONTEXT_NOTICE, CONTEXT_OPEN, escape_context};
 use crate::middleware::extract_claims;
 use crate::models::{
-    RAGChatRequest, RAGChatResponse, RAGScholarResult, RAGSearchRequest,
-    RAGSearchResponse,
+    AskRequest, ChatMessage, ContextTruncation, EmbeddedScholar, EmbeddingJobDetail,
+    EmbeddingJobStatus, EmbeddingMigrationProgress, EmbeddingMigrationRequest,
+    EmbeddingModelStatus, EmbeddingModelsResponse, EmbeddingStatusResponse, GuardrailEventQuery,
+    LlmCallKind, LlmUsageQuery, LlmUsageResponse, MAX_RAG_LIMIT, PassageField, PromptTemplate,
+    PromptUseCase, RAGChatRequest, RAGChatResponse, RAGDocumentInput, RAGFilters, RAGPassage,
+    RAGScholarResult, RAGSearchRequest, RAGSearchResponse, RAGSource, ScholarResponse, TokenUsage,
+    ToolCallRecord, UsageContext,
 };
+use crate::rag_cache::RAGCache;
+use crate::rerank::{Reranker, rerank};
+use crate::usage::{check_quota, metered};
 use crate::utils::{AppError, AppResult, AppState};
 
-/// Generate embeddings for text using OpenAI API
+/// Rough token count for budgeting requests: about four characters per token
+/// for ASCII text and one token per character for CJK and other scripts
+pub(crate) fn estimate_tokens(text: &str) -> usize {
+    let ascii = text.chars().filter(|c| c.is_ascii()).count();
+    let other = text.chars().count() - ascii;
+    ascii.div_ceil(4) + other
+}
+
+/// Longest prefix of `text` estimated at no more than `max_tokens`, cut at a char boundary
+fn truncate_to_tokens(text: &str, max_tokens: usize) -> &str {
+    let (mut ascii, mut other) = (0usize, 0);
+    for (index, c) in text.char_indices() {
+        if c.is_ascii() {
+            ascii += 1;
+        } else {
+            other += 1;
+        }
+        if ascii.div_ceil(4) + other > max_tokens {
+            return &text[..index];
+        }
+    }
+    text
+}
+
+/// Generate an embedding for a single text
 async fn get_embedding(
     text: &str,
-    app_state: &web::Data<AppState>,
+    model: &str,
+    usage: UsageContext<'_>,
+    app_state: &AppState,
 ) -> AppResult<Vec<f32>> {
+    get_embeddings(&[text.to_string()], model, usage, app_state)
+        .await?
+        .pop()
+        .ok_or_else(|| {
+            AppError::UpstreamInvalidResponse("Embedding API returned no vectors".to_string())
+        })
+}
+
+/// Embed a search query, reusing a cached vector unless `fresh` is set
+pub(crate) async fn get_query_embedding(
+    text: &str,
+    model: &str,
+    fresh: bool,
+    usage: UsageContext<'_>,
+    app_state: &AppState,
+) -> AppResult<Vec<f32>> {
+    if !fresh && let Some(embedding) = app_state.rag_cache.get_embedding(model, text).await {
+        return Ok(embedding);
+    }
+
+    let embedding = get_embedding(text, model, usage, app_state).await?;
+    app_state
+        .rag_cache
+        .insert_embedding(model, text, embedding.clone())
+        .await;
+    Ok(embedding)
+}
+
+/// Generate embeddings for many texts, batching them into as few requests as the
+/// configured input count and token budget allow; vectors come back in input order
+async fn get_embeddings(
+    texts: &[String],
+    model: &str,
+    usage: UsageContext<'_>,
+    app_state: &AppState,
+) -> AppResult<Vec<Vec<f32>>> {
+    let mut embeddings = Vec::with_capacity(texts.len());
+    let mut batch: Vec<&String> = Vec::new();
+    let mut batch_tokens = 0;
+
+    for text in texts {
+        let tokens = estimate_tokens(text);
+        let full = batch.len() >= app_state.embedding_batch_size
+            || batch_tokens + tokens > app_state.embedding_batch_tokens;
+        // An oversized input still goes out, alone in its own request
+        if full && !batch.is_empty() {
+            embeddings.extend(request_embeddings(&batch, model, usage, app_state).await?);
+            batch.clear();
+            batch_tokens = 0;
+        }
+        batch.push(text);
+        batch_tokens += tokens;
+    }
+
+    if !batch.is_empty() {
+        embeddings.extend(request_embeddings(&batch, model, usage, app_state).await?);
+    }
+
+    Ok(embeddings)
+}
+
+/// Call the OpenAI-compatible embeddings API once for a batch of inputs
+async fn request_embeddings(
+    inputs: &[&String],
+    model: &str,
+    usage: UsageContext<'_>,
+    app_state: &AppState,
+) -> AppResult<Vec<Vec<f32>>> {
+    metered(
+        app_state,
+        usage,
+        LlmCallKind::Embedding,
+        model,
+        send_embeddings_request(inputs, model, app_state),
+    )
+    .await
+}
+
+fn invalid_response(api: &str) -> AppError {
+    AppError::UpstreamInvalidResponse(format!("Invalid {} response format", api))
+}
+
+async fn send_embeddings_request(
+    inputs: &[&String],
+    model: &str,
+    app_state: &AppState,
+) -> AppResult<(Vec<Vec<f32>>, TokenUsage)> {
     if app_state.llm_api_key.is_empty() {
         return Err(AppError::InternalError(
             "LLM_API_KEY not configured".to_string(),
         ));
     }
 
-    let client = &app_state.oidc_http_client;
     let request_body = json!({
-        "input": text,
-        "model": app_state.embedding_model
+        "input": inputs,
+        "model": model
     });
 
-    let response = client
-        .post(&format!("{}/embeddings", app_state.llm_base_url))
-        .header("Authorization", format!("Bearer {}", app_state.llm_api_key))
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| AppError::InternalError(format!("Failed to call embedding API: {}", e)))?;
+    // Embedding the same input again yields the same vector, so failures are retried
+    let body = app_state
+        .llm_client
+        .post_json(
+            "Embedding API",
+            &format!("{}/embeddings", app_state.llm_base_url),
+            &app_state.llm_api_key,
+            &request_body,
+            true,
+        )
+        .await?;
 
-    if !response.status().is_success() {
-        let text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(AppError::InternalError(format!(
-            "Embedding API error: {}",
-            text
-        )));
-    }
+    let data = body
+        .get("data")
+        .and_then(|data| data.as_array())
+        .ok_or_else(|| invalid_response("embedding"))?;
 
-    let body: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|e| AppError::InternalError(format!("Failed to parse embedding response: {}", e)))?;
+    // Items carry the index of their input and are not guaranteed to be in order
+    let mut embeddings: Vec<Option<Vec<f32>>> = vec![None; inputs.len()];
+    for (position, item) in data.iter().enumerate() {
+        let index = item
+            .get("index")
+            .and_then(|index| index.as_u64())
+            .map(|index| index as usize)
+            .unwrap_or(position);
+        let embedding = item
+            .get("embedding")
+            .and_then(|emb| emb.as_array())
+            .ok_or_else(|| invalid_response("embedding"))?;
 
-    let embedding = body
-        .get("data")
-        .and_then(|data| data.get(0))
-        .and_then(|item| item.get("embedding"))
-        .and_then(|emb| emb.as_array())
-        .ok_or_else(|| AppError::InternalError("Invalid embedding response format".to_string()))?;
+        let slot = embeddings.get_mut(index).ok_or_else(|| {
+            AppError::UpstreamInvalidResponse(format!(
+                "Embedding API returned unknown index {}",
+                index
+            ))
+        })?;
+        *slot = Some(
+            embedding
+                .iter()
+                .filter_map(|v| v.as_f64().map(|f| f as f32))
+                .collect(),
+        );
+    }
 
-    let embedding_vec: Vec<f32> = embedding
-        .iter()
-        .filter_map(|v| v.as_f64().map(|f| f as f32))
-        .collect();
+    let embeddings = embeddings
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| {
+            AppError::UpstreamInvalidResponse(format!(
+                "Embedding API returned {} vectors for {} inputs",
+                data.len(),
+                inputs.len()
+            ))
+        })?;
 
-    Ok(embedding_vec)
+    Ok((embeddings, TokenUsage::from_response(&body)))
 }
 
 /// Call LLM chat API to generate a response based on context
 async fn call_llm_chat(
     system_prompt: &str,
     user_message: &str,
-    app_state: &web::Data<AppState>,
+    temperature: f32,
+    max_tokens: u32,
+    usage: UsageContext<'_>,
+    app_state: &AppState,
+) -> AppResult<String> {
+    let messages = [ChatMessage {
+        role: "user".to_string(),
+        content: user_message.to_string(),
+    }];
+    call_llm_conversation(
+        system_prompt,
+        &messages,
+        temperature,
+        max_tokens,
+        usage,
+        app_state,
+    )
+    .await
+}
+
+/// Call LLM chat API with a system prompt followed by a conversation
+pub(crate) async fn call_llm_conversation(
+    system_prompt: &str,
+    messages: &[ChatMessage],
+    temperature: f32,
+    max_tokens: u32,
+    usage: UsageContext<'_>,
+    app_state: &AppState,
 ) -> AppResult<String> {
+    metered(
+        app_state,
+        usage,
+        LlmCallKind::Chat,
+        &app_state.chat_model,
+        send_chat_request(system_prompt, messages, temperature, max_tokens, app_state),
+    )
+    .await
+}
+
+/// System prompt followed by the conversation, in the chat API's message format
+fn chat_request_messages(system_prompt: &str, messages: &[ChatMessage]) -> Vec<serde_json::Value> {
+    let mut llm_messages = vec![json!({
+        "role": "system",
+        "content": system_prompt
+    })];
+    for msg in messages {
+        llm_messages.push(json!({
+            "role": msg.role,
+            "content": msg.content
+        }));
+    }
+    llm_messages
+}
+
+async fn send_chat_request(
+    system_prompt: &str,
+    messages: &[ChatMessage],
+    temperature: f32,
+    max_tokens: u32,
+    app_state: &AppState,
+) -> AppResult<(String, TokenUsage)> {
+    let request_body = json!({
+        "model": app_state.chat_model,
+        "messages": chat_request_messages(system_prompt, messages),
+        "temperature": temperature,
+        "max_tokens": max_tokens
+    });
+
+    let (message, tokens) = send_chat_completion(&request_body, app_state).await?;
+    let content = message
+        .get("content")
+        .and_then(|content| content.as_str())
+        .ok_or_else(|| invalid_response("LLM"))?;
+
+    Ok((content.to_string(), tokens))
+}
+
+/// Post a chat completion request and return the first choice's message
+async fn send_chat_completion(
+    request_body: &serde_json::Value,
+    app_state: &AppState,
+) -> AppResult<(serde_json::Value, TokenUsage)> {
     if app_state.llm_api_key.is_empty() {
         return Err(AppError::InternalError(
             "LLM_API_KEY not configured".to_string(),
         ));
     }
 
-    let client = &app_state.oidc_http_client;
-    let request_body = json!({
-        "model": app_state.chat_model,
-        "messages": [
-            {
-                "role": "system",
-                "content": system_prompt
+    // Each completion is billed, so a failed one is not sent again
+    let mut body = app_state
+        .llm_client
+        .post_json(
+            "LLM API",
+            &format!("{}/chat/completions", app_state.llm_base_url),
+            &app_state.llm_api_key,
+            request_body,
+            false,
+        )
+        .await?;
+
+    let tokens = TokenUsage::from_response(&body);
+    let message = body
+        .get_mut("choices")
+        .and_then(|choices| choices.get_mut(0))
+        .and_then(|choice| choice.get_mut("message"))
+        .map(serde_json::Value::take)
+        .ok_or_else(|| invalid_response("LLM"))?;
+
+    Ok((message, tokens))
+}
+
+/// Tool calls run per model turn; any beyond this are answered with an error
+const MAX_TOOL_CALLS_PER_TURN: usize = 5;
+
+/// Call the LLM chat API, letting the model look things up with read-only tools first
+/// The model gets `chat_tool_rounds` turns to call tools; the turn after that has the
+/// tools withheld so it has to answer
+async fn call_llm_with_tools(
+    system_prompt: &str,
+    messages: &[ChatMessage],
+    temperature: f32,
+    max_tokens: u32,
+    tools: ChatTools,
+    usage: UsageContext<'_>,
+    app_state: &AppState,
+) -> AppResult<(String, Vec<ToolCallRecord>)> {
+    let system_prompt = format!("{}{}", system_prompt, TOOL_INSTRUCTIONS);
+    let mut conversation = chat_request_messages(&system_prompt, messages);
+    let definitions = ChatTools::definitions();
+    let mut records = Vec::new();
+
+    for turn in 0..=app_state.chat_tool_rounds {
+        let tool_choice = if turn < app_state.chat_tool_rounds {
+            "auto"
+        } else {
+            "none"
+        };
+        let request_body = json!({
+            "model": app_state.chat_model,
+            "messages": conversation,
+            "tools": definitions,
+            "tool_choice": tool_choice,
+            "temperature": temperature,
+            "max_tokens": max_tokens
+        });
+        let message = metered(
+            app_state,
+            usage,
+            LlmCallKind::Chat,
+            &app_state.chat_model,
+            send_chat_completion(&request_body, app_state),
+        )
+        .await?;
+
+        let calls = message
+            .get("tool_calls")
+            .and_then(|calls| calls.as_array())
+            .filter(|calls| !calls.is_empty())
+            .cloned();
+        let Some(calls) = calls else {
+            let content = message
+                .get("content")
+                .and_then(|content| content.as_str())
+                .ok_or_else(|| invalid_response("LLM"))?;
+            return Ok((content.to_string(), records));
+        };
+
+        // The assistant turn with its calls must precede their results
+        conversation.push(message);
+        for (index, call) in calls.iter().enumerate() {
+            let call_id = call
+                .get("id")
+                .and_then(|id| id.as_str())
+                .unwrap_or_default();
+            let function = call.get("function");
+            let name = function
+                .and_then(|f| f.get("name"))
+                .and_then(|name| name.as_str())
+                .unwrap_or_default();
+            let arguments = function
+                .and_then(|f| f.get("arguments"))
+                .and_then(|arguments| arguments.as_str())
+                .unwrap_or_default();
+
+            let result = if index < MAX_TOOL_CALLS_PER_TURN {
+                tools.execute(app_state, name, arguments).await
+            } else {
+                Err(AppError::BadRequest(format!(
+                    "At most {} tool calls are run per turn",
+                    MAX_TOOL_CALLS_PER_TURN
+                )))
+            };
+            let (result, error) = match result {
+                Ok(value) => (Some(value), None),
+                // Mistakes the model can correct are passed back to it verbatim
+                Err(
+                    AppError::BadRequest(message)
+                    | AppError::NotFound(message)
+                    | AppError::ValidationError(message),
+                ) => (None, Some(message)),
+                Err(e) => {
+                    log::error!("Chat tool {} failed: {}", name, e);
+                    (None, Some("The tool failed unexpectedly".to_string()))
+                }
+            };
+
+            let content = match (&result, &error) {
+                (Some(value), _) => value.to_string(),
+                (None, error) => json!({ "error": error }).to_string(),
+            };
+            conversation.push(json!({
+                "role": "tool",
+                "tool_call_id": call_id,
+                "content": content
+            }));
+            records.push(ToolCallRecord {
+                name: name.to_string(),
+                arguments: serde_json::from_str(arguments)
+                    .unwrap_or_else(|_| serde_json::Value::String(arguments.to_string())),
+                result,
+                error,
+            });
+        }
+    }
+
+    Err(AppError::InternalError(
+        "Chat model kept calling tools after they were withdrawn".to_string(),
+    ))
+}
+
+/// Name of the model whose vectors currently serve queries
+pub(crate) async fn active_embedding_model(app_state: &AppState) -> AppResult<String> {
+    Ok(app_state
+        .db
+        .get_embedding_model_by_status(EmbeddingModelStatus::Active)
+        .await?
+        .map(|model| model.name)
+        .unwrap_or_else(|| app_state.embedding_model.clone()))
+}
+
+/// Models that new or changed scholars must be embedded with:
+/// the active one and, during a migration, its replacement
+async fn target_embedding_models(app_state: &AppState) -> AppResult<Vec<String>> {
+    let mut models = vec![active_embedding_model(app_state).await?];
+    if let Some(migrating) = app_state
+        .db
+        .get_embedding_model_by_status(EmbeddingModelStatus::Migrating)
+        .await?
+    {
+        models.push(migrating.name);
+    }
+    Ok(models)
+}
+
+/// A query ready for retrieval: its text for reranking and its vector from `model`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RetrievalQuery<'a> {
+    pub text: &'a str,
+    pub model: &'a str,
+    pub embedding: &'a [f32],
+}
+
+/// Two-stage retrieval: fetch a larger candidate set by vector similarity, then let
+/// the configured reranker pick the best `limit` scholars for the query
+pub(crate) async fn retrieve_scholars(
+    app_state: &AppState,
+    usage: UsageContext<'_>,
+    query: RetrievalQuery<'_>,
+    limit: i64,
+    threshold: f32,
+    filters: RAGFilters<'_>,
+) -> AppResult<Vec<RAGScholarResult>> {
+    let candidate_count = match app_state.reranker {
+        Reranker::None => limit,
+        _ => limit.max(app_state.rerank_candidates),
+    };
+
+    let candidates = app_state
+        .db
+        .search_scholars_by_embedding_filtered(
+            query.model,
+            query.embedding,
+            candidate_count,
+            threshold,
+            app_state.passages_per_scholar,
+            filters,
+        )
+        .await?;
+
+    Ok(rerank(
+        app_state,
+        usage,
+        query.text,
+        candidates,
+        limit.max(0) as usize,
+    )
+    .await)
+}
+
+/// News and tag documents for a query, under the same visibility and filters as scholars
+pub(crate) async fn retrieve_documents(
+    app_state: &AppState,
+    query: RetrievalQuery<'_>,
+    threshold: f32,
+    filters: RAGFilters<'_>,
+) -> AppResult<Vec<RAGSource>> {
+    if app_state.rag_document_limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    app_state
+        .db
+        .search_rag_documents(
+            query.model,
+            query.embedding,
+            app_state.rag_document_limit,
+            threshold,
+            filters,
+        )
+        .await
+}
+
+// Retrieved text is escaped so a crafted profile cannot close its context block
+fn scholar_context_header(scholar: &RAGScholarResult) -> String {
+    format!(
+        "<scholar>\n{} | Research: {}",
+        escape_context(&scholar.name),
+        escape_context(&scholar.field_of_research)
+    )
+}
+
+fn passage_context_line(passage: &RAGPassage) -> String {
+    format!(
+        "  [{}] {}",
+        passage.field.label(),
+        escape_context(&passage.content)
+    )
+}
+
+fn document_context_line(document: &RAGSource) -> String {
+    match document {
+        RAGSource::Scholar { name, .. } => format!("<scholar>{}</scholar>", escape_context(name)),
+        RAGSource::News {
+            title,
+            source,
+            url,
+            publish_date,
+            ..
+        } => format!(
+            "<news>{} ({}, {}) {}</news>",
+            escape_context(title),
+            escape_context(source),
+            publish_date.format("%Y-%m-%d"),
+            escape_context(url)
+        ),
+        RAGSource::Tag {
+            name, description, ..
+        } => format!(
+            "<tag>{}: {}</tag>",
+            escape_context(name),
+            escape_context(description.as_deref().unwrap_or_default())
+        ),
+    }
+}
+
+/// Format retrieved scholars and their most relevant passages for the system prompt,
+/// one delimited block per scholar
+fn build_scholar_context(scholars: &[RAGScholarResult]) -> String {
+    scholars
+        .iter()
+        .map(|s| {
+            std::iter::once(scholar_context_header(s))
+                .chain(s.passages.iter().map(passage_context_line))
+                .chain(std::iter::once("</scholar>".to_string()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Smallest useful share of a passage; below this a passage is dropped rather than cut
+const MIN_PASSAGE_TOKENS: usize = 20;
+
+/// Cut scholars down to the chat model's context budget, most relevant first
+/// Each scholar keeps as many of its best passages as fit, the last one possibly shortened;
+/// a scholar without room for a single passage is left out
+pub(crate) fn fit_context_budget(
+    scholars: Vec<RAGScholarResult>,
+    app_state: &AppState,
+) -> (Vec<RAGScholarResult>, ContextTruncation) {
+    let mut truncation = ContextTruncation {
+        budget_tokens: app_state.context_budget(),
+        ..Default::default()
+    };
+    let mut fitted = Vec::with_capacity(scholars.len());
+
+    for mut scholar in scholars {
+        // Every line costs about one more token for its newline
+        let header_tokens = estimate_tokens(&scholar_context_header(&scholar)) + 1;
+        let remaining = truncation
+            .budget_tokens
+            .saturating_sub(truncation.used_tokens);
+        if header_tokens + MIN_PASSAGE_TOKENS > remaining {
+            truncation.dropped_scholar_ids.push(scholar.id);
+            continue;
+        }
+
+        let mut used = header_tokens;
+        let mut kept = Vec::with_capacity(scholar.passages.len());
+        let mut truncated = false;
+        for mut passage in std::mem::take(&mut scholar.passages) {
+            let tokens = estimate_tokens(&passage_context_line(&passage)) + 1;
+            if used + tokens <= remaining {
+                used += tokens;
+                kept.push(passage);
+                continue;
+            }
+
+            truncated = true;
+            let room = remaining - used;
+            if room < MIN_PASSAGE_TOKENS {
+                break;
+            }
+            // Label and indent count against the room left for content
+            let overhead = tokens.saturating_sub(estimate_tokens(&passage.content));
+            let cut = truncate_to_tokens(&passage.content, room.saturating_sub(overhead + 1));
+            if cut.is_empty() {
+                break;
+            }
+            passage.content = format!("{}…", cut);
+            used += estimate_tokens(&passage_context_line(&passage)) + 1;
+            kept.push(passage);
+            break;
+        }
+
+        if kept.is_empty() {
+            truncation.dropped_scholar_ids.push(scholar.id);
+            continue;
+        }
+        if truncated {
+            truncation.truncated_scholar_ids.push(scholar.id.clone());
+        }
+        scholar.passages = kept;
+        truncation.used_tokens += used;
+        fitted.push(scholar);
+    }
+
+    if truncation.is_truncated() {
+        log::info!(
+            "Scholar context cut to {} of {} tokens: {} scholars truncated, {} dropped",
+            truncation.used_tokens,
+            truncation.budget_tokens,
+            truncation.truncated_scholar_ids.len(),
+            truncation.dropped_scholar_ids.len()
+        );
+    }
+
+    (fitted, truncation)
+}
+
+/// Add news and tag documents to the context in the budget the scholars left, most
+/// similar first; documents that do not fit are recorded as dropped
+pub(crate) fn fit_document_budget(
+    documents: Vec<RAGSource>,
+    truncation: &mut ContextTruncation,
+) -> Vec<RAGSource> {
+    let mut fitted = Vec::with_capacity(documents.len());
+    for document in documents {
+        let tokens = estimate_tokens(&document_context_line(&document)) + 1;
+        if truncation.used_tokens + tokens > truncation.budget_tokens {
+            truncation
+                .dropped_document_ids
+                .push(document.id().to_string());
+            continue;
+        }
+        truncation.used_tokens += tokens;
+        fitted.push(document);
+    }
+    fitted
+}
+
+/// Every scholar and document in the context as a typed source, most similar first
+pub(crate) fn context_sources(
+    scholars: &[RAGScholarResult],
+    documents: &[RAGSource],
+) -> Vec<RAGSource> {
+    let mut sources: Vec<RAGSource> = scholars
+        .iter()
+        .map(|scholar| RAGSource::Scholar {
+            id: scholar.id.clone(),
+            name: scholar.name.clone(),
+            similarity_score: scholar.similarity_score,
+        })
+        .chain(documents.iter().cloned())
+        .collect();
+    // Stable, so reranked scholars keep their order among equal scores
+    sources.sort_by(|a, b| b.similarity_score().total_cmp(&a.similarity_score()));
+    sources
+}
+
+/// Ids of everything in the context, for answer cache keys and invalidation
+fn context_ids(scholars: &[RAGScholarResult], documents: &[RAGSource]) -> Vec<String> {
+    scholars
+        .iter()
+        .map(|scholar| scholar.id.clone())
+        .chain(documents.iter().map(|document| document.id().to_string()))
+        .collect()
+}
+
+/// Render a prompt template's system prompt with the retrieved scholars, news and tags
+/// and the query
+/// Retrieved content goes inside one delimited context block preceded by a notice that
+/// it is data, not instructions
+pub(crate) fn render_system_prompt(
+    template: &PromptTemplate,
+    scholars: &[RAGScholarResult],
+    documents: &[RAGSource],
+    query: &str,
+) -> String {
+    let mut sections = Vec::new();
+    if !scholars.is_empty() {
+        sections.push(build_scholar_context(scholars));
+    }
+    if !documents.is_empty() {
+        let lines = documents
+            .iter()
+            .map(document_context_line)
+            .collect::<Vec<_>>()
+            .join("\n");
+        sections.push(format!("Related news and tags:\n{}", lines));
+    }
+
+    let context = if sections.is_empty() {
+        "No scholar information available for this query.".to_string()
+    } else {
+        format!(
+            "{}\n{}\n{}\n{}",
+            CONTEXT_NOTICE,
+            CONTEXT_OPEN,
+            sections.join("\n\n"),
+            CONTEXT_CLOSE
+        )
+    };
+    template.render(&context, &escape_context(query))
+}
+
+/// Contents of the user's messages, which can all reach the model
+fn user_messages(messages: &[ChatMessage]) -> impl Iterator<Item = &str> {
+    messages
+        .iter()
+        .filter(|message| message.role == "user")
+        .map(|message| message.content.as_str())
+}
+
+/// Prior messages considered when rewriting the latest one
+const CONDENSE_HISTORY_MESSAGES: usize = 6;
+
+/// Rewrite the latest user message into a standalone search query using the prior messages
+/// A first message is used as-is; if the rewrite fails, retrieval falls back to the raw message
+async fn condense_query(
+    messages: &[ChatMessage],
+    usage: UsageContext<'_>,
+    app_state: &AppState,
+) -> AppResult<String> {
+    let latest_index = messages
+        .iter()
+        .rposition(|msg| msg.role == "user")
+        .ok_or_else(|| AppError::BadRequest("No user message found".to_string()))?;
+    let latest = &messages[latest_index].content;
+
+    let history = &messages[latest_index.saturating_sub(CONDENSE_HISTORY_MESSAGES)..latest_index];
+    if history.is_empty() {
+        return Ok(latest.clone());
+    }
+
+    let transcript = history
+        .iter()
+        .map(|msg| {
+            let speaker = if msg.role == "assistant" {
+                "Assistant"
+            } else {
+                "User"
+            };
+            format!("{}: {}", speaker, msg.content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let system_prompt = "You rewrite the latest message of a conversation about scholars into a \
+        standalone search query. Resolve pronouns and references using the conversation and keep \
+        the names of the scholars they refer to. Reply with the query only, in the language of the \
+        latest message.";
+    let user_message = format!(
+        "Conversation:\n{}\n\nLatest message: {}",
+        transcript, latest
+    );
+    let messages = [ChatMessage {
+        role: "user".to_string(),
+        content: user_message,
+    }];
+
+    match call_llm_conversation(system_prompt, &messages, 0.0, 200, usage, app_state).await {
+        Ok(query) if !query.trim().is_empty() => Ok(query.trim().to_string()),
+        Ok(_) => Ok(latest.clone()),
+        Err(e) => {
+            log::warn!(
+                "Failed to condense chat query, using the latest message: {}",
+                e
+            );
+            Ok(latest.clone())
+        }
+    }
+}
+
+/// Most recent news items per pinned scholar put in context
+const PINNED_NEWS_PER_SCHOLAR: usize = 5;
+
+/// Pinned scholars as context entries, in request order, through the same lookup as
+/// the scholar pages, so a scholar the caller cannot see is reported as not found
+/// Their similarity to the query is kept for display; their news is returned when asked for
+async fn pinned_context(
+    app_state: &AppState,
+    scholar_ids: &[String],
+    include_hidden: bool,
+    include_news: bool,
+    query: RetrievalQuery<'_>,
+) -> AppResult<(Vec<RAGScholarResult>, Vec<RAGSource>)> {
+    if scholar_ids.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    if scholar_ids.len() as i64 > MAX_RAG_LIMIT {
+        return Err(AppError::ValidationError(format!(
+            "At most {} scholars can be pinned",
+            MAX_RAG_LIMIT
+        )));
+    }
+
+    let mut profiles = Vec::with_capacity(scholar_ids.len());
+    for id in scholar_ids {
+        if !profiles.iter().any(|p: &ScholarResponse| &p.id == id) {
+            profiles.push(app_state.db.get_scholar_visible(id, include_hidden).await?);
+        }
+    }
+
+    // Exact similarity over the pinned scholars only; unembedded ones score zero
+    let similarities: HashMap<String, f32> = app_state
+        .db
+        .search_scholars_by_embedding_filtered(
+            query.model,
+            query.embedding,
+            profiles.len() as i64,
+            -1.0,
+            1,
+            RAGFilters {
+                include_hidden,
+                identities: None,
+                tags: None,
+                scholar_ids: Some(scholar_ids),
             },
-            {
-                "role": "user",
-                "content": user_message
+        )
+        .await?
+        .into_iter()
+        .map(|scholar| (scholar.id, scholar.similarity_score))
+        .collect();
+
+    let mut scholars = Vec::with_capacity(profiles.len());
+    let mut news = Vec::new();
+    for profile in profiles {
+        let similarity_score = similarities.get(&profile.id).copied().unwrap_or(0.0);
+        if include_news {
+            news.extend(
+                profile
+                    .news
+                    .iter()
+                    .filter(|item| item.archived_at.is_none())
+                    .take(PINNED_NEWS_PER_SCHOLAR)
+                    .map(|item| RAGSource::News {
+                        id: item.id.clone(),
+                        title: item.title.clone(),
+                        source: item.source.clone(),
+                        url: item.url.clone(),
+                        publish_date: item.publish_date,
+                        similarity_score,
+                    }),
+            );
+        }
+
+        let passages = [
+            (PassageField::Introduction, &profile.introduction),
+            (PassageField::SocialInfluence, &profile.social_influence),
+        ]
+        .into_iter()
+        .filter(|(_, content)| !content.trim().is_empty())
+        .map(|(field, content)| RAGPassage {
+            field,
+            content: content.clone(),
+            similarity_score,
+        })
+        .collect();
+        scholars.push(RAGScholarResult {
+            id: profile.id,
+            name: profile.name,
+            field_of_research: profile.field_of_research,
+            introduction: profile.introduction,
+            social_influence: profile.social_influence,
+            similarity_score,
+            rerank_score: None,
+            passages,
+            pinned: true,
+        });
+    }
+
+    // News shared by two pinned scholars appears once
+    let mut seen = HashSet::new();
+    news.retain(|item| seen.insert(item.id().to_string()));
+
+    Ok((scholars, news))
+}
+
+/// RAG Chat endpoint
+/// Multi-turn conversation with scholar knowledge
+/// Requires editor or higher permission
+pub async fn rag_chat(
+    app_state: web::Data<AppState>,
+    req: web::Json<RAGChatRequest>,
+    _http_req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    // Require authentication and editor+ permission for chat
+    let claims = extract_claims(&_http_req)?;
+    crate::middleware::require_ai_access(&claims)?;
+
+    if req.messages.is_empty() {
+        return Err(AppError::BadRequest(
+            "At least one message is required".to_string(),
+        ));
+    }
+    check_quota(&app_state, &claims).await?;
+    let usage = UsageContext::user(&claims.user_id, "rag_chat");
+    guardrails::check_input(&app_state, usage, user_messages(&req.messages)).await?;
+
+    // Rewrite follow-ups like "what about her awards?" into a query that stands on its own
+    let standalone_query = condense_query(&req.messages, usage, &app_state).await?;
+
+    let model = active_embedding_model(&app_state).await?;
+    let query_embedding =
+        get_query_embedding(&standalone_query, &model, req.fresh, usage, &app_state).await?;
+
+    // Search for similar scholars with filters
+    let filters = RAGFilters {
+        include_hidden: req.include_hidden,
+        identities: req.identities.as_deref(),
+        tags: req.tags.as_deref(),
+        scholar_ids: None,
+    };
+
+    // Pinned scholars always come first; retrieval only fills the slots they leave
+    let (mut context_scholars, pinned_news) = pinned_context(
+        &app_state,
+        req.scholar_ids.as_deref().unwrap_or_default(),
+        req.include_hidden,
+        req.include_pinned_news,
+        RetrievalQuery {
+            text: &standalone_query,
+            model: &model,
+            embedding: &query_embedding,
+        },
+    )
+    .await?;
+
+    // Scholars from the previous turn come next, with their passages most relevant to the
+    // new query; the same filters apply so nothing hidden slips in
+    let open_slots = req.limit - context_scholars.len() as i64;
+    let sticky_ids: Vec<String> = req
+        .sticky_scholar_ids
+        .iter()
+        .flatten()
+        .filter(|id| !context_scholars.iter().any(|s| &s.id == *id))
+        .cloned()
+        .collect();
+    if open_slots > 0 && !sticky_ids.is_empty() {
+        let sticky_filters = RAGFilters {
+            scholar_ids: Some(&sticky_ids),
+            ..filters
+        };
+        let sticky = app_state
+            .db
+            .search_scholars_by_embedding_filtered(
+                &model,
+                &query_embedding,
+                open_slots.min(sticky_ids.len() as i64),
+                // Lowest possible cosine similarity: keep them whatever the query
+                -1.0,
+                app_state.passages_per_scholar,
+                sticky_filters,
+            )
+            .await?;
+        context_scholars.extend(sticky);
+    }
+
+    let retrieved = retrieve_scholars(
+        &app_state,
+        usage,
+        RetrievalQuery {
+            text: &standalone_query,
+            model: &model,
+            embedding: &query_embedding,
+        },
+        req.limit,
+        0.0,
+        filters,
+    )
+    .await?;
+    for scholar in retrieved {
+        if context_scholars.len() as i64 >= req.limit {
+            break;
+        }
+        if !context_scholars.iter().any(|s| s.id == scholar.id) {
+            context_scholars.push(scholar);
+        }
+    }
+
+    let retrieved_documents = retrieve_documents(
+        &app_state,
+        RetrievalQuery {
+            text: &standalone_query,
+            model: &model,
+            embedding: &query_embedding,
+        },
+        0.0,
+        filters,
+    )
+    .await?;
+    // Pinned news goes ahead of retrieved documents and is not repeated
+    let mut documents = pinned_news;
+    for document in retrieved_documents {
+        if !documents.iter().any(|d| d.id() == document.id()) {
+            documents.push(document);
+        }
+    }
+
+    // Build full system prompt with as much scholar context as the model's budget allows
+    let (context_scholars, mut context_truncation) =
+        fit_context_budget(context_scholars, &app_state);
+    let documents = fit_document_budget(documents, &mut context_truncation);
+    let template = app_state
+        .db
+        .get_active_prompt_template(PromptUseCase::Chat)
+        .await?;
+    let system_prompt =
+        render_system_prompt(&template, &context_scholars, &documents, &standalone_query);
+
+    // Call LLM with the conversation history; with tools enabled the model may first
+    // look up counts and listings under the same visibility as retrieval
+    let (message, tool_calls) = if app_state.chat_tools {
+        let tools = ChatTools {
+            include_hidden: req.include_hidden,
+        };
+        call_llm_with_tools(
+            &system_prompt,
+            &req.messages,
+            template.temperature,
+            template.max_tokens as u32,
+            tools,
+            usage,
+            &app_state,
+        )
+        .await?
+    } else {
+        let message = call_llm_conversation(
+            &system_prompt,
+            &req.messages,
+            template.temperature,
+            template.max_tokens as u32,
+            usage,
+            &app_state,
+        )
+        .await?;
+        (message, Vec::new())
+    };
+    let (message, _) =
+        guardrails::filter_output(&app_state, usage, req.include_hidden, message).await?;
+
+    let context_count = context_scholars.len() as i32;
+    let sources = context_sources(&context_scholars, &documents);
+
+    Ok(HttpResponse::Ok().json(RAGChatResponse {
+        message,
+        standalone_query,
+        context_scholars,
+        context_count,
+        prompt_template: template.version_ref(),
+        context_truncation,
+        sources,
+        tool_calls,
+    }))
+}
+
+/// Embed scholars' profile vectors and retrieval passages with the given model
+/// All inputs go through batched API calls and are stored together, so either
+/// every scholar is embedded or none is
+async fn embed_scholars(
+    app_state: &AppState,
+    usage: UsageContext<'_>,
+    model: &str,
+    scholar_ids: &[String],
+) -> AppResult<()> {
+    let inputs = app_state
+        .db
+        .build_scholar_embedding_inputs(
+            scholar_ids,
+            app_state.passage_max_chars,
+            app_state.passage_overlap_chars,
+        )
+        .await?;
+
+    let texts: Vec<String> = inputs
+        .iter()
+        .flat_map(|input| {
+            std::iter::once(input.embedding_text.clone())
+                .chain(input.passages.iter().map(|p| p.embedding_input.clone()))
+        })
+        .collect();
+    let mut embeddings = get_embeddings(&texts, model, usage, app_state)
+        .await?
+        .into_iter();
+
+    let embedded: Vec<EmbeddedScholar> = inputs
+        .into_iter()
+        .map(|input| {
+            let embedding = embeddings.next().unwrap_or_default();
+            let passages = input
+                .passages
+                .into_iter()
+                .map(|passage| (passage, embeddings.next().unwrap_or_default()))
+                .collect();
+            EmbeddedScholar {
+                scholar_id: input.scholar_id,
+                embedding_text: input.embedding_text,
+                embedding,
+                passages,
             }
-        ],
-        "temperature": 0.7,
-        "max_tokens": 1000
-    });
+        })
+        .collect();
 
-    let response = client
-        .post(&format!("{}/chat/completions", app_state.llm_base_url))
-        .header("Authorization", format!("Bearer {}", app_state.llm_api_key))
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| AppError::InternalError(format!("Failed to call LLM API: {}", e)))?;
+    app_state
+        .db
+        .store_scholar_embeddings(model, &embedded)
+        .await?;
+    // Answers may have quoted the passages just replaced
+    app_state.rag_cache.invalidate_scholars(scholar_ids);
+
+    Ok(())
+}
+
+/// Embed scholars in one batch, falling back to one scholar at a time when the
+/// batch fails for a reason other than the API being unavailable, so a single bad
+/// profile does not fail the others
+async fn embed_scholar_batch(
+    app_state: &AppState,
+    usage: UsageContext<'_>,
+    model: &str,
+    scholar_ids: &[String],
+) -> Vec<(String, AppResult<()>)> {
+    let error = match embed_scholars(app_state, usage, model, scholar_ids).await {
+        Ok(_) => return scholar_ids.iter().map(|id| (id.clone(), Ok(()))).collect(),
+        Err(e) => e,
+    };
+
+    // Retrying scholars one by one would only add load to an overloaded API
+    if is_retryable(&error) {
+        return scholar_ids
+            .iter()
+            .map(|id| (id.clone(), Err(copy_upstream_error(&error))))
+            .collect();
+    }
+
+    if let [scholar_id] = scholar_ids {
+        return vec![(scholar_id.clone(), Err(error))];
+    }
+
+    let mut results = Vec::with_capacity(scholar_ids.len());
+    for scholar_id in scholar_ids {
+        let result =
+            embed_scholars(app_state, usage, model, std::slice::from_ref(scholar_id)).await;
+        results.push((scholar_id.clone(), result));
+    }
+    results
+}
+
+/// Query parameters for batch embedding endpoint
+#[derive(Deserialize)]
+pub struct EmbedQuery {
+    /// "missing" - only embed scholars without embeddings (default)
+    /// "all" - reindex all visible scholars
+    #[serde(default = "default_embed_mode")]
+    pub mode: String,
+}
+
+fn default_embed_mode() -> String {
+    "missing".to_string()
+}
+
+/// Admin endpoint to queue a batch embedding job
+/// Two modes: missing (only scholars without embeddings) or all (reindex all)
+/// The job is processed in the background; poll its status with the returned id
+/// Requires admin permission
+pub async fn batch_embed_scholars(
+    app_state: web::Data<AppState>,
+    query: web::Query<EmbedQuery>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    crate::middleware::require_admin(&claims)?;
+
+    let model = active_embedding_model(&app_state).await?;
+
+    // Get scholars based on mode
+    let (mode, scholars) = match query.mode.as_str() {
+        "all" => ("all", app_state.db.get_all_visible_scholars().await?),
+        _ => (
+            "missing",
+            app_state.db.get_scholars_without_embeddings(&model).await?,
+        ),
+    };
+    let scholar_ids: Vec<String> = scholars.into_iter().map(|s| s.id).collect();
+
+    let job = app_state
+        .db
+        .create_embedding_job(mode, &model, &scholar_ids, &claims.user_id)
+        .await?;
+
+    log::info!(
+        "Queued embedding job {} (mode: {}, model: {}, scholars: {})",
+        job.id,
+        mode,
+        model,
+        job.total
+    );
+
+    Ok(HttpResponse::Accepted().json(job))
+}
+
+/// Admin endpoint listing recent embedding jobs
+pub async fn list_embedding_jobs(
+    app_state: web::Data<AppState>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    crate::middleware::require_admin(&claims)?;
+
+    let jobs = app_state.db.list_embedding_jobs(50).await?;
 
-    if !response.status().is_success() {
-        let text = response
-            .text()
+    Ok(HttpResponse::Ok().json(jobs))
+}
+
+/// Admin endpoint showing a job's progress and the scholars that failed
+pub async fn get_embedding_job(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    crate::middleware::require_admin(&claims)?;
+
+    let job_id = path.into_inner();
+    let job = app_state.db.get_embedding_job(&job_id).await?;
+    let failures = app_state.db.get_embedding_job_failures(&job_id).await?;
+
+    Ok(HttpResponse::Ok().json(EmbeddingJobDetail { job, failures }))
+}
+
+/// Admin endpoint to cancel a queued or running job
+pub async fn cancel_embedding_job(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    crate::middleware::require_admin(&claims)?;
+
+    let job = app_state
+        .db
+        .cancel_embedding_job(&path.into_inner())
+        .await?;
+    log::info!("Cancelled embedding job {}", job.id);
+
+    Ok(HttpResponse::Ok().json(job))
+}
+
+/// Scholars a worker claims and embeds together per round
+const EMBEDDING_JOB_BATCH_SIZE: i64 = 20;
+/// How long a claimed item stays hidden from other workers
+const EMBEDDING_JOB_LEASE_SECS: i64 = 300;
+/// Attempts before a retryable failure becomes permanent
+const EMBEDDING_JOB_MAX_ATTEMPTS: i32 = 6;
+
+/// Rate limits, server errors, timeouts, network failures and an open circuit are worth retrying
+fn is_retryable(error: &AppError) -> bool {
+    match error {
+        AppError::UpstreamError { status: None, .. }
+        | AppError::UpstreamTimeout(_)
+        | AppError::UpstreamRateLimited { .. }
+        | AppError::UpstreamUnavailable { .. } => true,
+        AppError::UpstreamError {
+            status: Some(status),
+            ..
+        } => *status == 429 || *status >= 500,
+        _ => false,
+    }
+}
+
+/// Copy of an upstream error, to report a failed batch for each of its scholars
+fn copy_upstream_error(error: &AppError) -> AppError {
+    match error {
+        AppError::UpstreamError { status, message } => AppError::UpstreamError {
+            status: *status,
+            message: message.clone(),
+        },
+        AppError::UpstreamTimeout(message) => AppError::UpstreamTimeout(message.clone()),
+        AppError::UpstreamRateLimited {
+            retry_after,
+            message,
+        } => AppError::UpstreamRateLimited {
+            retry_after: *retry_after,
+            message: message.clone(),
+        },
+        AppError::UpstreamUnavailable {
+            retry_after,
+            message,
+        } => AppError::UpstreamUnavailable {
+            retry_after: *retry_after,
+            message: message.clone(),
+        },
+        AppError::UpstreamInvalidResponse(message) => {
+            AppError::UpstreamInvalidResponse(message.clone())
+        }
+        other => AppError::InternalError(other.to_string()),
+    }
+}
+
+/// Exponential backoff starting at 10 seconds, capped at 15 minutes, but never
+/// sooner than the API asked to be called again
+fn retry_delay_secs(attempts: i32, error: &AppError) -> i64 {
+    let backoff = (10i64 << attempts.clamp(0, 10)).min(900);
+    match error {
+        AppError::UpstreamRateLimited {
+            retry_after: Some(secs),
+            ..
+        }
+        | AppError::UpstreamUnavailable {
+            retry_after: secs, ..
+        } => backoff.max(*secs as i64),
+        _ => backoff,
+    }
+}
+
+/// Background step that works through queued embedding jobs, oldest first
+/// Stops early when the API signals overload so the failed items can back off
+pub async fn process_embedding_jobs(app_state: &AppState) -> AppResult<()> {
+    if app_state.llm_api_key.is_empty() {
+        return Ok(());
+    }
+
+    while let Some(job) = app_state.db.get_next_embedding_job().await? {
+        // Vectors for a model that no longer serves queries would never be used
+        let models = target_embedding_models(app_state).await?;
+        if !models.contains(&job.model) {
+            app_state
+                .db
+                .fail_embedding_job(
+                    &job.id,
+                    &format!("Embedding model {} is no longer in use", job.model),
+                )
+                .await?;
+            log::warn!(
+                "Embedding job {} stopped: model {} retired",
+                job.id,
+                job.model
+            );
+            continue;
+        }
+
+        if job.status == EmbeddingJobStatus::Queued {
+            app_state.db.start_embedding_job(&job.id).await?;
+            log::info!("Started embedding job {} ({} scholars)", job.id, job.total);
+        }
+
+        let items = app_state
+            .db
+            .claim_embedding_job_items(&job.id, EMBEDDING_JOB_BATCH_SIZE, EMBEDDING_JOB_LEASE_SECS)
+            .await?;
+
+        if items.is_empty() {
+            continue;
+        }
+
+        // A cancellation takes effect between batches
+        if app_state.db.get_embedding_job_status(&job.id).await? != EmbeddingJobStatus::Running {
+            log::info!("Embedding job {} is no longer running", job.id);
+            continue;
+        }
+
+        let scholar_ids: Vec<String> = items.iter().map(|item| item.scholar_id.clone()).collect();
+        let usage = UsageContext {
+            user_id: job.created_by.as_deref(),
+            endpoint: "embedding_job",
+        };
+        let results = embed_scholar_batch(app_state, usage, &job.model, &scholar_ids).await;
+
+        let mut overloaded = false;
+        for (item, (scholar_id, result)) in items.iter().zip(results) {
+            let error = match result {
+                Ok(_) => {
+                    app_state
+                        .db
+                        .complete_embedding_job_item(&job.id, &scholar_id)
+                        .await?;
+                    continue;
+                }
+                Err(e) => e,
+            };
+
+            let attempts = item.attempts + 1;
+            if !is_retryable(&error) || attempts >= EMBEDDING_JOB_MAX_ATTEMPTS {
+                log::error!(
+                    "Failed to embed scholar {} in job {}: {}",
+                    scholar_id,
+                    job.id,
+                    error
+                );
+                app_state
+                    .db
+                    .fail_embedding_job_item(&job.id, &scholar_id, &error.to_string(), None)
+                    .await?;
+                continue;
+            }
+
+            let delay = retry_delay_secs(item.attempts, &error);
+            log::warn!(
+                "Embedding API unavailable for scholar {} in job {} (attempt {}), retrying in {}s: {}",
+                scholar_id,
+                job.id,
+                attempts,
+                delay,
+                error
+            );
+            app_state
+                .db
+                .fail_embedding_job_item(&job.id, &scholar_id, &error.to_string(), Some(delay))
+                .await?;
+            overloaded = true;
+        }
+
+        if overloaded {
+            return Ok(());
+        }
+
+        if app_state.db.finish_embedding_job_if_done(&job.id).await? {
+            let job = app_state.db.get_embedding_job(&job.id).await?;
+            log::info!(
+                "Embedding job {} completed. Embedded: {}, Failed: {}",
+                job.id,
+                job.succeeded,
+                job.failed
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Split scholars into those missing a vector from the model and those whose
+/// stored vector was produced from different text than their profile yields now
+async fn find_outdated_embeddings(
+    app_state: &AppState,
+    model: &str,
+    scholar_ids: &[String],
+) -> AppResult<(Vec<String>, Vec<String>)> {
+    let texts = app_state
+        .db
+        .build_scholar_embedding_texts(scholar_ids)
+        .await?;
+    let hashes = app_state
+        .db
+        .get_embedding_hashes(model, scholar_ids)
+        .await?;
+
+    let mut missing = Vec::new();
+    let mut stale = Vec::new();
+    for (scholar_id, text) in texts {
+        match hashes.get(&scholar_id) {
+            None => missing.push(scholar_id),
+            Some(Some(hash)) if *hash == hash_embedding_text(&text) => {}
+            Some(_) => stale.push(scholar_id),
+        }
+    }
+
+    Ok((missing, stale))
+}
+
+/// Queue scholars for re-embedding if their embedding text drifted from the stored vector
+/// Called after any change that feeds into the text (the scholar itself, tags, identities,
+/// news links); errors are logged so they never fail the triggering request
+pub async fn queue_embedding_refresh(app_state: &AppState, scholar_ids: &[String], reason: &str) {
+    app_state.rag_cache.invalidate_scholars(scholar_ids);
+    queue_outdated_embeddings(app_state, scholar_ids, reason, true).await;
+}
+
+/// Drop cached answers quoting changed news items or tags and have the next backlog
+/// pass re-embed the news and tag documents
+pub fn queue_rag_document_sync(app_state: &AppState, source_ids: &[String]) {
+    app_state.rag_cache.invalidate_documents(source_ids);
+    app_state.rag_documents_stale.store(true, Ordering::Relaxed);
+}
+
+async fn queue_outdated_embeddings(
+    app_state: &AppState,
+    scholar_ids: &[String],
+    reason: &str,
+    reset_existing: bool,
+) {
+    if scholar_ids.is_empty() {
+        return;
+    }
+
+    let result = async {
+        let model = active_embedding_model(app_state).await?;
+        let (missing, stale) = find_outdated_embeddings(app_state, &model, scholar_ids).await?;
+        let outdated = [missing, stale].concat();
+        app_state
+            .db
+            .queue_embedding_refresh(&outdated, reason, reset_existing)
             .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(AppError::InternalError(format!("LLM API error: {}", text)));
     }
+    .await;
 
-    let body: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|e| AppError::InternalError(format!("Failed to parse LLM response: {}", e)))?;
+    match result {
+        Ok(0) => {}
+        Ok(count) => log::info!("Queued {} scholars for re-embedding ({})", count, reason),
+        Err(e) => log::error!("Failed to queue embedding refresh ({}): {}", reason, e),
+    }
+}
 
-    let message = body
-        .get("choices")
-        .and_then(|choices| choices.get(0))
-        .and_then(|choice| choice.get("message"))
-        .and_then(|msg| msg.get("content"))
-        .and_then(|content| content.as_str())
-        .ok_or_else(|| AppError::InternalError("Invalid LLM response format".to_string()))?;
+/// Background step that re-embeds queued scholars
+/// During a model migration each scholar is embedded with both models; failures are retried with backoff
+pub async fn process_embedding_refresh_queue(app_state: &AppState) -> AppResult<()> {
+    if app_state.llm_api_key.is_empty() {
+        return Ok(());
+    }
+
+    let refreshes = app_state.db.get_due_embedding_refreshes(20).await?;
+    if refreshes.is_empty() {
+        return Ok(());
+    }
+
+    let models = target_embedding_models(app_state).await?;
+    let scholar_ids: Vec<String> = refreshes.iter().map(|r| r.scholar_id.clone()).collect();
+
+    // First error per scholar across the target models
+    let mut errors: HashMap<String, AppError> = HashMap::new();
+    for model in &models {
+        let usage = UsageContext::anonymous("embedding_refresh");
+        for (scholar_id, result) in embed_scholar_batch(app_state, usage, model, &scholar_ids).await
+        {
+            if let Err(e) = result {
+                errors.entry(scholar_id).or_insert(e);
+            }
+        }
+    }
 
-    Ok(message.to_string())
+    for refresh in refreshes {
+        match errors.remove(&refresh.scholar_id) {
+            None => {
+                log::info!(
+                    "Re-embedded scholar {} ({})",
+                    refresh.scholar_id,
+                    refresh.reason
+                );
+                app_state
+                    .db
+                    .complete_embedding_refresh(&refresh.scholar_id, refresh.queued_at)
+                    .await?;
+            }
+            Some(e) => {
+                log::warn!(
+                    "Failed to re-embed scholar {} (attempt {}): {}",
+                    refresh.scholar_id,
+                    refresh.attempts + 1,
+                    e
+                );
+                app_state
+                    .db
+                    .fail_embedding_refresh(&refresh.scholar_id, &e.to_string())
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Admin endpoint reporting how many scholars have fresh, stale or missing embeddings
+pub async fn get_embedding_status(
+    app_state: web::Data<AppState>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    crate::middleware::require_admin(&claims)?;
+
+    let model = active_embedding_model(&app_state).await?;
+    let scholar_ids = app_state.db.get_active_scholar_ids().await?;
+    let (missing, stale) = find_outdated_embeddings(&app_state, &model, &scholar_ids).await?;
+    let queued = app_state.db.count_queued_embedding_refreshes().await?;
+
+    let total = scholar_ids.len() as i64;
+    let missing = missing.len() as i64;
+    let stale = stale.len() as i64;
+
+    Ok(HttpResponse::Ok().json(EmbeddingStatusResponse {
+        model,
+        total,
+        fresh: total - missing - stale,
+        stale,
+        missing,
+        queued,
+    }))
 }
 
+/// Admin endpoint reporting hit and miss counts of the RAG caches
+pub async fn get_cache_stats(
+    app_state: web::Data<AppState>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    crate::middleware::require_admin(&claims)?;
 
+    Ok(HttpResponse::Ok().json(app_state.rag_cache.stats()))
+}
 
-/// RAG Chat endpoint
-/// Multi-turn conversation with scholar knowledge
-/// Requires editor or higher permission
-pub async fn rag_chat(
+/// Admin endpoint reporting LLM API usage per user and per day
+/// Defaults to the last 30 days; days are in UTC
+pub async fn get_llm_usage(
     app_state: web::Data<AppState>,
-    req: web::Json<RAGChatRequest>,
-    _http_req: HttpRequest,
+    query: web::Query<LlmUsageQuery>,
+    req: HttpRequest,
 ) -> AppResult<HttpResponse> {
-    // Require authentication and editor+ permission for chat
-    let claims = extract_claims(&_http_req)?;
-    crate::middleware::require_ai_access(&claims)?;
+    let claims = extract_claims(&req)?;
+    crate::middleware::require_admin(&claims)?;
 
-    if req.messages.is_empty() {
+    let to = query.to.unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let from = query.from.unwrap_or(to - chrono::Duration::days(29));
+    if from > to {
         return Err(AppError::BadRequest(
-            "At least one message is required".to_string(),
+            "from must not be after to".to_string(),
         ));
     }
 
-    // Get the last user message for context retrieval
-    let last_user_message = req
-        .messages
-        .iter()
-        .rev()
-        .find(|msg| msg.role == "user")
-        .ok_or_else(|| AppError::BadRequest("No user message found".to_string()))?;
+    let user_id = query.user_id.as_deref();
+    let users = app_state
+        .db
+        .get_llm_usage_by_user(from, to, user_id)
+        .await?;
+    let days = app_state.db.get_llm_usage_by_day(from, to, user_id).await?;
 
-    // Generate embedding for the last user message
-    let query_embedding = get_embedding(&last_user_message.content, &app_state).await?;
+    Ok(HttpResponse::Ok().json(LlmUsageResponse {
+        from,
+        to,
+        users,
+        days,
+    }))
+}
 
-    // Search for similar scholars with filters
-    let identities_ref = req.identities.as_deref();
-    let tags_ref = req.tags.as_deref();
-    
-    let context_scholars: Vec<RAGScholarResult> = app_state
+/// Admin endpoint listing the most recent guardrail triggers
+pub async fn list_guardrail_events(
+    app_state: web::Data<AppState>,
+    query: web::Query<GuardrailEventQuery>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    crate::middleware::require_admin(&claims)?;
+
+    let events = app_state
         .db
-        .search_scholars_by_embedding_filtered(
-            &query_embedding,
-            req.limit,
-            0.0,
-            req.include_hidden,
-            identities_ref,
-            tags_ref,
-        )
+        .list_guardrail_events(query.kind, query.limit())
         .await?;
 
-    // Build context
-    let context = context_scholars
-        .iter()
-        .map(|s| {
-            format!(
-                "- {}: {} | Research: {} | Influence: {}",
-                s.name, s.introduction, s.field_of_research, s.social_influence
-            )
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
+    Ok(HttpResponse::Ok().json(events))
+}
 
-    // Build full system prompt with scholar context
-    let system_prompt = format!(
-        "You are a knowledgeable assistant helping users learn about scholars. \
-        You have access to the following scholar information:\n\n{}\n\n\
-        Use this information to answer questions accurately. Be helpful and provide detailed information. \
-        IMPORTANT: Do not include scholar IDs in your responses.",
-        if context.is_empty() {
-            "No scholar information available for this query.".to_string()
-        } else {
-            context
-        }
-    );
+/// Reconcile the configured EMBEDDING_MODEL with the models recorded in the database
+/// A fresh database adopts the configured model; a migration starts only when
+/// EMBEDDING_MODEL itself changed since the last startup, so models an admin switched
+/// to through the migration endpoints stay in place across restarts. Before anything
+/// is recorded, vectors labelled with another model may predate model tracking, so
+/// the configured model is taken as the truth and migrated to
+pub async fn sync_embedding_model(app_state: &AppState) -> AppResult<()> {
+    let configured = &app_state.embedding_model;
+    let synced = app_state.db.get_synced_embedding_model().await?;
 
-    // Build messages for LLM
-    let mut llm_messages = vec![
-        json!({
-            "role": "system",
-            "content": system_prompt
-        }),
-    ];
+    let Some(active) = app_state
+        .db
+        .get_embedding_model_by_status(EmbeddingModelStatus::Active)
+        .await?
+    else {
+        app_state.db.activate_embedding_model(configured).await?;
+        app_state.db.set_synced_embedding_model(configured).await?;
+        log::info!("Registered embedding model {} as active", configured);
+        return Ok(());
+    };
 
-    // Add conversation history
-    for msg in &req.messages {
-        llm_messages.push(json!({
-            "role": msg.role,
-            "content": msg.content
-        }));
+    if synced.as_ref() == Some(configured) {
+        return Ok(());
     }
 
-    // Call LLM
-    if app_state.llm_api_key.is_empty() {
-        return Err(AppError::InternalError(
-            "LLM_API_KEY not configured".to_string(),
-        ));
+    let migrating = app_state
+        .db
+        .get_embedding_model_by_status(EmbeddingModelStatus::Migrating)
+        .await?;
+
+    if &active.name == configured || migrating.as_ref().is_some_and(|m| &m.name == configured) {
+        if migrating.is_some() {
+            log::info!("Resuming migration to embedding model {}", configured);
+        }
+        app_state.db.set_synced_embedding_model(configured).await?;
+        return Ok(());
     }
 
-    let client = &app_state.oidc_http_client;
-    let request_body = json!({
-        "model": app_state.chat_model,
-        "messages": llm_messages,
-        "temperature": 0.7,
-        "max_tokens": 1500
-    });
+    match migrating {
+        // Left unrecorded so the change is picked up at a later startup
+        Some(model) => {
+            log::warn!(
+                "EMBEDDING_MODEL is {} but a migration to {} is in progress; queries keep using {}",
+                configured,
+                model.name,
+                active.name
+            );
+        }
+        None => {
+            app_state
+                .db
+                .start_embedding_model_migration(configured)
+                .await?;
+            app_state.db.set_synced_embedding_model(configured).await?;
+            match synced {
+                Some(synced) => log::info!(
+                    "EMBEDDING_MODEL changed from {} to {}; re-embedding scholars in the background",
+                    synced,
+                    configured
+                ),
+                None => log::info!(
+                    "Vectors labelled {} may not come from EMBEDDING_MODEL {}; re-embedding scholars in the background",
+                    active.name,
+                    configured
+                ),
+            }
+        }
+    }
 
-    let response = client
-        .post(&format!("{}/chat/completions", app_state.llm_base_url))
-        .header("Authorization", format!("Bearer {}", app_state.llm_api_key))
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| AppError::InternalError(format!("Failed to call LLM API: {}", e)))?;
+    Ok(())
+}
 
-    if !response.status().is_success() {
-        let text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        return Err(AppError::InternalError(format!("LLM API error: {}", text)));
+/// Background step that keeps embeddings complete
+/// With `full_scan`, which rebuilds every scholar's and document's text and so runs
+/// rarely, queues every scholar whose vector is missing or stale for the active model
+/// and re-embeds changed documents as a safety net for missed change hooks; documents
+/// are also synced when a news item or tag changed. Then advances a running model
+/// migration and switches over once every scholar has been re-embedded
+pub async fn process_embedding_backlog(app_state: &AppState, full_scan: bool) -> AppResult<()> {
+    if app_state.llm_api_key.is_empty() {
+        return Ok(());
     }
 
-    let body: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|e| AppError::InternalError(format!("Failed to parse LLM response: {}", e)))?;
+    if full_scan {
+        // Leave already queued scholars to their backoff
+        let scholar_ids = app_state.db.get_active_scholar_ids().await?;
+        queue_outdated_embeddings(app_state, &scholar_ids, "drift_scan", false).await;
+    }
 
-    let message = body
-        .get("choices")
-        .and_then(|choices| choices.get(0))
-        .and_then(|choice| choice.get("message"))
-        .and_then(|msg| msg.get("content"))
-        .and_then(|content| content.as_str())
-        .ok_or_else(|| AppError::InternalError("Invalid LLM response format".to_string()))?
-        .to_string();
+    let usage = UsageContext::anonymous("embedding_documents");
+    let active = active_embedding_model(app_state).await?;
+    if (app_state.rag_documents_stale.swap(false, Ordering::Relaxed) || full_scan)
+        && let Err(e) = sync_rag_documents(app_state, usage, &active).await
+    {
+        // Retried on the next pass
+        app_state.rag_documents_stale.store(true, Ordering::Relaxed);
+        log::error!(
+            "Failed to sync news and tag documents for {}: {}",
+            active,
+            e
+        );
+    }
 
-    let context_count = context_scholars.len() as i32;
+    let Some(target) = app_state
+        .db
+        .get_embedding_model_by_status(EmbeddingModelStatus::Migrating)
+        .await?
+    else {
+        return Ok(());
+    };
 
-    Ok(HttpResponse::Ok().json(RAGChatResponse {
-        message,
-        context_scholars,
-        context_count,
-    }))
-}
+    // Documents are few; a failure leaves the switch-over to the next pass
+    sync_rag_documents(app_state, usage, &target.name).await?;
 
-/// Embed scholars - helper function to process embeddings
-async fn embed_scholars(
-    app_state: &web::Data<AppState>,
-    scholars: Vec<crate::models::Scholar>,
-) -> (i32, i32) {
-    let mut embedded_count = 0;
-    let mut failed_count = 0;
-
-    for scholar in scholars {
-        match app_state
-            .db
-            .build_scholar_embedding_text(&scholar.id)
-            .await
-        {
-            Ok(embedding_text) => {
-                match get_embedding(&embedding_text, app_state).await {
-                    Ok(embedding) => {
-                        match app_state
-                            .db
-                            .store_scholar_embedding(&scholar.id, &embedding_text, &embedding)
-                            .await
-                        {
-                            Ok(_) => {
-                                embedded_count += 1;
-                                log::info!("Embedded scholar: {} ({})", scholar.name, scholar.id);
-                            }
-                            Err(e) => {
-                                failed_count += 1;
-                                log::error!(
-                                    "Failed to store embedding for {}: {}",
-                                    scholar.id, e
-                                );
-                            }
-                        }
-                    }
+    let pending = app_state
+        .db
+        .get_scholars_pending_embedding(&target.name)
+        .await?;
+
+    if !pending.is_empty() {
+        log::info!(
+            "Embedding {} scholars with migration target {}",
+            pending.len(),
+            target.name
+        );
+        let scholar_ids: Vec<String> = pending.into_iter().map(|s| s.id).collect();
+        let mut embedded_count = 0;
+        let mut failed_count = 0;
+        let usage = UsageContext::anonymous("embedding_migration");
+        for chunk in scholar_ids.chunks(EMBEDDING_JOB_BATCH_SIZE as usize) {
+            for (scholar_id, result) in
+                embed_scholar_batch(app_state, usage, &target.name, chunk).await
+            {
+                match result {
+                    Ok(_) => embedded_count += 1,
                     Err(e) => {
                         failed_count += 1;
-                        log::error!("Failed to generate embedding for {}: {}", scholar.id, e);
+                        log::error!("Failed to embed scholar {}: {}", scholar_id, e);
                     }
                 }
             }
-            Err(e) => {
-                failed_count += 1;
-                log::error!("Failed to build embedding text for {}: {}", scholar.id, e);
-            }
         }
+        log::info!(
+            "Migration pass for {} finished. Embedded: {}, Failed: {}",
+            target.name,
+            embedded_count,
+            failed_count
+        );
+        if failed_count > 0 {
+            // Retry the failures on the next pass before switching over
+            return Ok(());
+        }
+    }
 
-        // Small delay to avoid rate limiting
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    // Scholars created during the pass were embedded with both models already
+    let (embedded, total) = app_state.db.get_embedding_progress(&target.name).await?;
+    if embedded < total {
+        return Ok(());
     }
 
-    (embedded_count, failed_count)
-}
+    app_state
+        .db
+        .complete_embedding_model_migration(&target.name)
+        .await?;
+    log::info!("Switched active embedding model to {}", target.name);
 
-/// Query parameters for batch embedding endpoint
-#[derive(Deserialize)]
-pub struct EmbedQuery {
-    /// "missing" - only embed scholars without embeddings (default)
-    /// "all" - reindex all visible scholars
-    #[serde(default = "default_embed_mode")]
-    pub mode: String,
+    Ok(())
 }
 
-fn default_embed_mode() -> String {
-    "missing".to_string()
+/// Embed news items and tag descriptions that are new or changed since they were last
+/// embedded with `model`, and drop the documents of deleted or archived sources
+async fn sync_rag_documents(
+    app_state: &AppState,
+    usage: UsageContext<'_>,
+    model: &str,
+) -> AppResult<()> {
+    if app_state.rag_document_limit == 0 {
+        return Ok(());
+    }
+
+    let documents = app_state.db.build_rag_document_inputs().await?;
+    let hashes = app_state.db.get_rag_document_hashes(model).await?;
+    let outdated: Vec<&RAGDocumentInput> = documents
+        .iter()
+        .filter(|document| {
+            hashes.get(&(document.doc_type, document.source_id.clone()))
+                != Some(&hash_embedding_text(&document.content))
+        })
+        .collect();
+
+    for chunk in outdated.chunks(EMBEDDING_JOB_BATCH_SIZE as usize) {
+        let texts: Vec<String> = chunk
+            .iter()
+            .map(|document| document.content.clone())
+            .collect();
+        let embeddings = get_embeddings(&texts, model, usage, app_state).await?;
+        let embedded: Vec<(&RAGDocumentInput, Vec<f32>)> =
+            chunk.iter().copied().zip(embeddings).collect();
+        app_state.db.store_rag_documents(model, &embedded).await?;
+    }
+
+    let removed = app_state
+        .db
+        .delete_orphaned_rag_documents(model, &documents)
+        .await?;
+    if !outdated.is_empty() || !removed.is_empty() {
+        log::info!(
+            "Synced news and tag documents for {}: {} embedded, {} removed",
+            model,
+            outdated.len(),
+            removed.len()
+        );
+    }
+
+    // Answers quoting the old text must not outlive it
+    let changed: Vec<String> = outdated
+        .iter()
+        .map(|document| document.source_id.clone())
+        .chain(removed)
+        .collect();
+    app_state.rag_cache.invalidate_documents(&changed);
+
+    Ok(())
 }
 
-/// Admin endpoint to trigger batch embedding for scholars
-/// Two modes: missing (only scholars without embeddings) or all (reindex all)
-/// Requires admin permission
-pub async fn batch_embed_scholars(
+/// Admin endpoint listing embedding models and the progress of a running migration
+pub async fn list_embedding_models(
     app_state: web::Data<AppState>,
-    query: web::Query<EmbedQuery>,
-    _req: HttpRequest,
+    req: HttpRequest,
 ) -> AppResult<HttpResponse> {
-    // Require admin authentication
-    let claims = extract_claims(&_req)?;
+    let claims = extract_claims(&req)?;
     crate::middleware::require_admin(&claims)?;
 
-    log::info!("Starting batch embedding (mode: {})...", query.mode);
+    let models = app_state.db.list_embedding_models().await?;
 
-    // Get scholars based on mode
-    let scholars = match query.mode.as_str() {
-        "all" => {
-            // Reindex all visible scholars
-            app_state.db.get_all_visible_scholars().await?
-        }
-        "missing" | _ => {
-            // Get scholars without embeddings (default)
-            app_state.db.get_scholars_without_embeddings().await?
+    let migration = match models
+        .iter()
+        .find(|model| model.status == EmbeddingModelStatus::Migrating)
+    {
+        Some(model) => {
+            let (embedded, total) = app_state.db.get_embedding_progress(&model.name).await?;
+            Some(EmbeddingMigrationProgress {
+                model: model.name.clone(),
+                embedded,
+                total,
+            })
         }
+        None => None,
     };
 
-    if scholars.is_empty() {
-        return Ok(HttpResponse::Ok().json(serde_json::json!({
-            "message": match query.mode.as_str() {
-                "all" => "No visible scholars found",
-                _ => "All scholars are already embedded"
-            },
-            "embedded_count": 0,
-            "failed_count": 0,
-            "mode": query.mode
-        })));
-    }
+    Ok(HttpResponse::Ok().json(EmbeddingModelsResponse {
+        configured: app_state.embedding_model.clone(),
+        models,
+        migration,
+    }))
+}
+
+/// Admin endpoint to start re-embedding all scholars with another model
+/// Queries keep using the active model until the background migration completes
+pub async fn start_embedding_migration(
+    app_state: web::Data<AppState>,
+    body: web::Json<EmbeddingMigrationRequest>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    crate::middleware::require_admin(&claims)?;
 
-    let (embedded_count, failed_count) = embed_scholars(&app_state, scholars).await;
+    let model_name = body.model.trim();
+    if model_name.is_empty() {
+        return Err(AppError::BadRequest("Model name is required".to_string()));
+    }
 
+    let model = app_state
+        .db
+        .start_embedding_model_migration(model_name)
+        .await?;
     log::info!(
-        "Batch embedding completed (mode: {}). Embedded: {}, Failed: {}",
-        query.mode, embedded_count, failed_count
+        "Embedding model migration to {} started by {}",
+        model.name,
+        claims.user_id
     );
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "message": "Batch embedding completed",
-        "embedded_count": embedded_count,
-        "failed_count": failed_count,
-        "mode": query.mode
-    })))
+    Ok(HttpResponse::Accepted().json(model))
 }
 
-/// Embed a single scholar (used internally for auto-embedding on create/update)
-pub async fn embed_single_scholar(
-    app_state: &web::Data<AppState>,
-    scholar_id: &str,
-) -> AppResult<()> {
-    match app_state
-        .db
-        .build_scholar_embedding_text(scholar_id)
-        .await
-    {
-        Ok(embedding_text) => {
-            match get_embedding(&embedding_text, app_state).await {
-                Ok(embedding) => {
-                    app_state
-                        .db
-                        .store_scholar_embedding(scholar_id, &embedding_text, &embedding)
-                        .await?;
-                    log::info!("Auto-embedded scholar: {}", scholar_id);
-                    Ok(())
-                }
-                Err(e) => {
-                    log::warn!("Failed to auto-embed scholar {}: {}", scholar_id, e);
-                    Ok(()) // Don't fail the scholar creation
-                }
-            }
-        }
-        Err(e) => {
-            log::warn!("Failed to build embedding text for scholar {}: {}", scholar_id, e);
-            Ok(()) // Don't fail the scholar creation
-        }
-    }
+/// Admin endpoint to abandon a running model migration
+pub async fn cancel_embedding_migration(
+    app_state: web::Data<AppState>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    crate::middleware::require_admin(&claims)?;
+
+    app_state.db.cancel_embedding_model_migration().await?;
+
+    Ok(HttpResponse::NoContent().finish())
 }
 
 /// Authenticated RAG Search endpoint
@@ -434,65 +2015,321 @@ pub async fn rag_search_authenticated(
     crate::middleware::require_ai_access(&claims)?;
     
     log::info!("RAG search requested for query: {}", body.query);
+    check_quota(&app_state, &claims).await?;
+    let usage = UsageContext::user(&claims.user_id, "rag_search");
+    guardrails::check_input(&app_state, usage, [body.query.as_str()]).await?;
 
     // Generate embedding for the query
-    let query_embedding = get_embedding(&body.query, &app_state).await?;
+    let model = active_embedding_model(&app_state).await?;
+    let query_embedding =
+        get_query_embedding(&body.query, &model, body.fresh, usage, &app_state).await?;
 
     // Search for similar scholars in the database with filters
-    let identities_ref = body.identities.as_deref();
-    let tags_ref = body.tags.as_deref();
-    
-    let scholars: Vec<RAGScholarResult> = app_state
-        .db
-        .search_scholars_by_embedding_filtered(
-            &query_embedding,
-            body.limit,
-            body.threshold,
-            body.include_hidden,
-            identities_ref,
-            tags_ref,
-        )
-        .await?;
+    let filters = RAGFilters {
+        include_hidden: body.include_hidden,
+        identities: body.identities.as_deref(),
+        tags: body.tags.as_deref(),
+        scholar_ids: None,
+    };
+
+    let scholars: Vec<RAGScholarResult> = retrieve_scholars(
+        &app_state,
+        usage,
+        RetrievalQuery {
+            text: &body.query,
+            model: &model,
+            embedding: &query_embedding,
+        },
+        body.limit,
+        body.threshold,
+        filters,
+    )
+    .await?;
+    let documents = retrieve_documents(
+        &app_state,
+        RetrievalQuery {
+            text: &body.query,
+            model: &model,
+            embedding: &query_embedding,
+        },
+        body.threshold,
+        filters,
+    )
+    .await?;
 
-    if scholars.is_empty() {
+    if scholars.is_empty() && documents.is_empty() {
         return Ok(HttpResponse::Ok().json(RAGSearchResponse {
             query: body.query.clone(),
             retrieved_scholars: vec![],
             response: "No relevant scholars found for your query.".to_string(),
             context_count: 0,
+            cached: false,
+            prompt_template: None,
+            context_truncation: None,
+            sources: vec![],
         }));
     }
 
-    // Build context from retrieved scholars
-    let context = scholars
-        .iter()
-        .map(|s| {
-            format!(
-                "- {}: {} | Research: {} | Influence: {}",
-                s.name, s.introduction, s.field_of_research, s.social_influence
-            )
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    let system_prompt = format!(
-        "You are a knowledgeable assistant helping users find information about scholars. \
-        You have access to the following scholar information:\n\n{}\n\n\
-        Use this information to answer the user's query accurately and helpfully. \
-        If the information doesn't directly answer the query, explain what you know about related topics. \
-        IMPORTANT: Do not include scholar IDs in your responses.",
-        context
+    let (scholars, mut context_truncation) = fit_context_budget(scholars, &app_state);
+    let documents = fit_document_budget(documents, &mut context_truncation);
+    let template = app_state
+        .db
+        .get_active_prompt_template(PromptUseCase::Search)
+        .await?;
+    let source_ids = context_ids(&scholars, &documents);
+    let answer_key = RAGCache::answer_key(
+        &app_state.chat_model,
+        &template.id,
+        &body.query,
+        &filters,
+        &source_ids,
     );
+    let context_count = scholars.len() as i32;
+    let sources = context_sources(&scholars, &documents);
 
-    // Call LLM to generate response
-    let llm_response = call_llm_chat(&system_prompt, &body.query, &app_state).await?;
+    if !body.fresh
+        && let Some(response) = app_state.rag_cache.get_answer(&answer_key).await
+    {
+        return Ok(HttpResponse::Ok().json(RAGSearchResponse {
+            query: body.query.clone(),
+            retrieved_scholars: scholars,
+            response,
+            context_count,
+            cached: true,
+            prompt_template: Some(template.version_ref()),
+            context_truncation: Some(context_truncation),
+            sources,
+        }));
+    }
 
-    let context_count = scholars.len() as i32;
+    // Build system prompt with context from retrieved scholars, news and tags
+    let system_prompt = render_system_prompt(&template, &scholars, &documents, &body.query);
+
+    // Call LLM to generate response
+    let llm_response = call_llm_chat(
+        &system_prompt,
+        &body.query,
+        template.temperature,
+        template.max_tokens as u32,
+        usage,
+        &app_state,
+    )
+    .await?;
+    let (llm_response, allowed) =
+        guardrails::filter_output(&app_state, usage, body.include_hidden, llm_response).await?;
+    if allowed {
+        app_state
+            .rag_cache
+            .insert_answer(answer_key, &llm_response, &source_ids)
+            .await;
+    }
 
     Ok(HttpResponse::Ok().json(RAGSearchResponse {
         query: body.query.clone(),
         retrieved_scholars: scholars,
         response: llm_response,
         context_count,
+        cached: false,
+        prompt_template: Some(template.version_ref()),
+        context_truncation: Some(context_truncation),
+        sources,
+    }))
+}
+
+/// Scholars retrieved for a public question
+const ASK_SCHOLAR_LIMIT: i64 = 5;
+
+/// Client IP for rate limiting: the connection's peer address, unless the peer is a
+/// trusted proxy, in which case X-Forwarded-For is followed from the right past every
+/// trusted hop; entries further left are client-supplied and never consulted
+fn client_ip(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> String {
+    let Some(mut client) = req.peer_addr().map(|peer| peer.ip()) else {
+        return "unknown".to_string();
+    };
+
+    let hops: Vec<IpAddr> = req
+        .headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map_while(|hop| hop.trim().parse().ok())
+        .collect();
+    for hop in hops.into_iter().rev() {
+        if !trusted_proxies.contains(&client) {
+            break;
+        }
+        client = hop;
+    }
+
+    client.to_string()
+}
+
+/// Public endpoint answering visitors' questions about scholars
+/// Only visible scholars are ever retrieved; conversations are size-capped and each
+/// client IP gets a daily request budget
+pub async fn ask(
+    app_state: web::Data<AppState>,
+    body: web::Json<AskRequest>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let messages = &body.messages;
+    if messages.is_empty() {
+        return Err(AppError::BadRequest(
+            "At least one message is required".to_string(),
+        ));
+    }
+    if messages.len() > app_state.ask_max_turns {
+        return Err(AppError::ValidationError(format!(
+            "Conversations are limited to {} messages",
+            app_state.ask_max_turns
+        )));
+    }
+    for message in messages {
+        if message.role != "user" && message.role != "assistant" {
+            return Err(AppError::ValidationError(format!(
+                "Invalid message role: {}",
+                message.role
+            )));
+        }
+        if message.content.chars().count() > app_state.ask_max_message_chars {
+            return Err(AppError::ValidationError(format!(
+                "Messages are limited to {} characters",
+                app_state.ask_max_message_chars
+            )));
+        }
+    }
+    let question = match messages.last() {
+        Some(message) if message.role == "user" && !message.content.trim().is_empty() => {
+            message.content.clone()
+        }
+        _ => {
+            return Err(AppError::BadRequest(
+                "The last message must be a non-empty question".to_string(),
+            ));
+        }
+    };
+
+    let client_ip = client_ip(&req, &app_state.trusted_proxies);
+    let requests = app_state.db.increment_ask_requests(&client_ip).await?;
+    if requests > app_state.ask_daily_limit {
+        return Err(AppError::TooManyRequests(
+            "Daily question limit reached, please try again tomorrow".to_string(),
+        ));
+    }
+
+    let usage = UsageContext::anonymous("ask");
+    guardrails::check_input(&app_state, usage, user_messages(messages)).await?;
+    let model = active_embedding_model(&app_state).await?;
+    let query_embedding = get_query_embedding(&question, &model, false, usage, &app_state).await?;
+
+    // Hidden scholars are never exposed publicly, whatever the request says
+    let filters = RAGFilters {
+        include_hidden: false,
+        identities: None,
+        tags: None,
+        scholar_ids: None,
+    };
+
+    let scholars = retrieve_scholars(
+        &app_state,
+        usage,
+        RetrievalQuery {
+            text: &question,
+            model: &model,
+            embedding: &query_embedding,
+        },
+        ASK_SCHOLAR_LIMIT,
+        0.0,
+        filters,
+    )
+    .await?;
+    let documents = retrieve_documents(
+        &app_state,
+        RetrievalQuery {
+            text: &question,
+            model: &model,
+            embedding: &query_embedding,
+        },
+        0.0,
+        filters,
+    )
+    .await?;
+
+    if scholars.is_empty() && documents.is_empty() {
+        return Ok(HttpResponse::Ok().json(RAGSearchResponse {
+            query: question,
+            retrieved_scholars: vec![],
+            response: "No relevant scholars found for your question.".to_string(),
+            context_count: 0,
+            cached: false,
+            prompt_template: None,
+            context_truncation: None,
+            sources: vec![],
+        }));
+    }
+
+    let (scholars, mut context_truncation) = fit_context_budget(scholars, &app_state);
+    let documents = fit_document_budget(documents, &mut context_truncation);
+    let template = app_state
+        .db
+        .get_active_prompt_template(PromptUseCase::Ask)
+        .await?;
+    let source_ids = context_ids(&scholars, &documents);
+    let context_count = scholars.len() as i32;
+    let sources = context_sources(&scholars, &documents);
+
+    // Only single questions are cached; follow-ups depend on the conversation
+    let answer_key = (messages.len() == 1).then(|| {
+        RAGCache::answer_key(
+            &app_state.chat_model,
+            &template.id,
+            &question,
+            &filters,
+            &source_ids,
+        )
+    });
+    if let Some(key) = &answer_key
+        && let Some(response) = app_state.rag_cache.get_answer(key).await
+    {
+        return Ok(HttpResponse::Ok().json(RAGSearchResponse {
+            query: question,
+            retrieved_scholars: scholars,
+            response,
+            context_count,
+            cached: true,
+            prompt_template: Some(template.version_ref()),
+            context_truncation: Some(context_truncation),
+            sources,
+        }));
+    }
+
+    let system_prompt = render_system_prompt(&template, &scholars, &documents, &question);
+
+    let response = call_llm_conversation(
+        &system_prompt,
+        messages,
+        template.temperature,
+        template.max_tokens as u32,
+        usage,
+        &app_state,
+    )
+    .await?;
+    let (response, allowed) = guardrails::filter_output(&app_state, usage, false, response).await?;
+    if let Some(key) = answer_key.filter(|_| allowed) {
+        app_state
+            .rag_cache
+            .insert_answer(key, &response, &source_ids)
+            .await;
+    }
+
+    Ok(HttpResponse::Ok().json(RAGSearchResponse {
+        query: question,
+        retrieved_scholars: scholars,
+        response,
+        context_count,
+        cached: false,
+        prompt_template: Some(template.version_ref()),
+        context_truncation: Some(context_truncation),
+        sources,
     }))
 }
@@ -0,0 +1,143 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use std::collections::HashMap;
+
+use crate::db::rag::hash_embedding_text;
+use crate::handlers::rag::{active_embedding_model, get_query_embedding};
+use crate::middleware::{extract_claims, require_admin};
+use crate::models::*;
+use crate::usage::check_quota;
+use crate::utils::{AppError, AppResult, AppState};
+
+/// The requested embedding model, which must be registered, or the active one
+async fn inspected_model(app_state: &AppState, requested: Option<&str>) -> AppResult<String> {
+    let Some(name) = requested else {
+        return active_embedding_model(app_state).await;
+    };
+
+    let models = app_state.db.list_embedding_models().await?;
+    if !models.iter().any(|model| model.name == name) {
+        return Err(AppError::BadRequest(format!(
+            "Embedding model {} is not registered",
+            name
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// Show what is stored for a scholar's embedding, whether it is stale and which
+/// scholars are nearest to it, hidden ones included
+/// Requires admin permission
+pub async fn inspect_scholar_embedding(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<EmbeddingInspectionQuery>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let scholar_id = path.into_inner();
+    let scholar = app_state.db.get_scholar(&scholar_id).await?;
+    let model = inspected_model(&app_state, query.model.as_deref()).await?;
+
+    let embedding = app_state
+        .db
+        .get_stored_embedding(&scholar_id, &model)
+        .await?;
+    let current_text = app_state
+        .db
+        .build_scholar_embedding_texts(std::slice::from_ref(&scholar_id))
+        .await?
+        .remove(&scholar_id)
+        .unwrap_or_default();
+    let stale = embedding.as_ref().is_some_and(|stored| {
+        stored.content_hash.as_deref() != Some(hash_embedding_text(&current_text).as_str())
+    });
+
+    let neighbors = if embedding.is_some() {
+        app_state
+            .db
+            .get_exact_scholar_neighbors(&scholar_id, &model, query.neighbors())
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(HttpResponse::Ok().json(EmbeddingInspectionResponse {
+        queued: app_state
+            .db
+            .is_embedding_refresh_queued(&scholar_id)
+            .await?,
+        passages: app_state
+            .db
+            .get_stored_passages(&scholar_id, &model)
+            .await?,
+        scholar_id,
+        name: scholar.name,
+        visible: scholar.visible,
+        model,
+        embedding,
+        current_text,
+        stale,
+        neighbors,
+    }))
+}
+
+/// Score every scholar against arbitrary query text, exactly and before any threshold,
+/// and show where the index-backed search ranks each of them
+/// Requires admin permission
+pub async fn query_similarity(
+    app_state: web::Data<AppState>,
+    body: web::Json<QuerySimilarityRequest>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_admin(&claims)?;
+
+    let query = body.query.trim();
+    if query.is_empty() {
+        return Err(AppError::BadRequest("Query must not be empty".to_string()));
+    }
+    check_quota(&app_state, &claims).await?;
+    let usage = UsageContext::user(&claims.user_id, "rag_similarity");
+
+    let model = inspected_model(&app_state, body.model.as_deref()).await?;
+    let embedding = get_query_embedding(query, &model, body.fresh, usage, &app_state).await?;
+    let limit = body.limit();
+
+    let mut scholars = app_state
+        .db
+        .get_raw_query_similarities(&model, &embedding, limit)
+        .await?;
+
+    let index_ranks: HashMap<String, usize> = app_state
+        .db
+        .search_scholars_by_embedding_filtered(
+            &model,
+            &embedding,
+            limit,
+            // Lowest possible cosine similarity: nothing is cut by the threshold
+            -1.0,
+            1,
+            RAGFilters {
+                include_hidden: true,
+                identities: None,
+                tags: None,
+                scholar_ids: None,
+            },
+        )
+        .await?
+        .into_iter()
+        .enumerate()
+        .map(|(rank, scholar)| (scholar.id, rank + 1))
+        .collect();
+    for scholar in &mut scholars {
+        scholar.index_rank = index_ranks.get(&scholar.id).copied();
+    }
+
+    Ok(HttpResponse::Ok().json(QuerySimilarityResponse {
+        query: query.to_string(),
+        model,
+        scholars,
+    }))
+}
@@ -0,0 +1,165 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use std::collections::HashMap;
+
+use crate::handlers::rag::active_embedding_model;
+use crate::middleware::{extract_claims, require_ai_access};
+use crate::models::*;
+use crate::utils::{AppResult, AppState};
+
+/// Suggestions backed by less of the neighborhood than this are left out
+const MIN_SUGGESTION_CONFIDENCE: f32 = 0.2;
+const MAX_TAG_SUGGESTIONS: usize = 8;
+/// Neighbor names quoted in an explanation
+const EXPLANATION_NAMES: usize = 3;
+
+/// Neighbors sharing one tag or identity, in neighbor order
+#[derive(Default)]
+struct Votes<'a> {
+    weight: f32,
+    scholars: Vec<&'a ScholarNeighbor>,
+}
+
+impl Votes<'_> {
+    fn explanation(&self, neighbor_count: usize, what: &str) -> String {
+        let names = self
+            .scholars
+            .iter()
+            .take(EXPLANATION_NAMES)
+            .map(|scholar| format!("{} ({:.2})", scholar.name, scholar.similarity))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "{} of the {} most similar scholars {}, including {}",
+            self.scholars.len(),
+            neighbor_count,
+            what,
+            names
+        )
+    }
+
+    fn scholar_ids(&self) -> Vec<String> {
+        self.scholars
+            .iter()
+            .map(|scholar| scholar.id.clone())
+            .collect()
+    }
+}
+
+/// Vectors pointing away from the scholar carry no evidence
+fn vote_weight(neighbor: &ScholarNeighbor) -> f32 {
+    neighbor.similarity.max(0.0)
+}
+
+/// Suggest tags and an identity for a scholar from its nearest neighbors in
+/// embedding space, weighting each neighbor's values by its similarity
+/// No chat model is involved; the scholar's stored profile vector is reused
+/// Requires editor or higher permission
+pub async fn get_scholar_suggestions(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<ScholarSuggestionQuery>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    require_ai_access(&claims)?;
+
+    let scholar_id = path.into_inner();
+    let scholar = app_state.db.get_scholar(&scholar_id).await?;
+    let model = active_embedding_model(&app_state).await?;
+
+    let Some(neighbors) = app_state
+        .db
+        .get_scholar_neighbors(&scholar_id, &model, query.neighbors())
+        .await?
+    else {
+        return Ok(HttpResponse::Ok().json(ScholarSuggestionsResponse {
+            scholar_id,
+            embedding_model: model,
+            embedded: false,
+            neighbors: Vec::new(),
+            tags: Vec::new(),
+            identities: Vec::new(),
+        }));
+    };
+
+    let neighbor_ids: Vec<String> = neighbors.iter().map(|n| n.id.clone()).collect();
+    let neighbor_tags = app_state
+        .db
+        .get_active_tags_by_scholar(&neighbor_ids)
+        .await?;
+    let total_weight: f32 = neighbors.iter().map(vote_weight).sum();
+
+    let mut tag_votes: HashMap<&str, (&TagListItem, Votes)> = HashMap::new();
+    let mut identity_votes: HashMap<&str, Votes> = HashMap::new();
+    for neighbor in &neighbors {
+        for tag in neighbor_tags.get(&neighbor.id).into_iter().flatten() {
+            let (_, votes) = tag_votes
+                .entry(tag.id.as_str())
+                .or_insert_with(|| (tag, Votes::default()));
+            votes.weight += vote_weight(neighbor);
+            votes.scholars.push(neighbor);
+        }
+        if !neighbor.identity_archived {
+            let votes = identity_votes
+                .entry(neighbor.identity_id.as_str())
+                .or_default();
+            votes.weight += vote_weight(neighbor);
+            votes.scholars.push(neighbor);
+        }
+    }
+
+    let confidence = |votes: &Votes| {
+        if total_weight > 0.0 {
+            votes.weight / total_weight
+        } else {
+            0.0
+        }
+    };
+
+    let mut tags: Vec<TagSuggestion> = tag_votes
+        .into_values()
+        .filter(|(tag, _)| !scholar.tags.iter().any(|current| current.id == tag.id))
+        .map(|(tag, votes)| TagSuggestion {
+            tag_id: tag.id.clone(),
+            name: tag.name.clone(),
+            color: tag.color.clone(),
+            confidence: confidence(&votes),
+            explanation: votes.explanation(neighbors.len(), "have this tag"),
+            supporting_scholar_ids: votes.scholar_ids(),
+        })
+        .filter(|suggestion| suggestion.confidence >= MIN_SUGGESTION_CONFIDENCE)
+        .collect();
+    tags.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    tags.truncate(MAX_TAG_SUGGESTIONS);
+
+    let mut identities: Vec<IdentitySuggestion> = identity_votes
+        .into_iter()
+        .map(|(identity_id, votes)| IdentitySuggestion {
+            identity_id: identity_id.to_string(),
+            name: votes.scholars[0].identity_name.clone(),
+            confidence: confidence(&votes),
+            explanation: votes.explanation(neighbors.len(), "have this identity"),
+            is_current: identity_id == scholar.identity.id,
+            supporting_scholar_ids: votes.scholar_ids(),
+        })
+        .filter(|suggestion| suggestion.confidence >= MIN_SUGGESTION_CONFIDENCE)
+        .collect();
+    identities.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    Ok(HttpResponse::Ok().json(ScholarSuggestionsResponse {
+        scholar_id,
+        embedding_model: model,
+        embedded: true,
+        neighbors,
+        tags,
+        identities,
+    }))
+}
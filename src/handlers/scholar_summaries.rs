@@ -0,0 +1,160 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+
+use crate::db::rag::hash_embedding_text;
+use crate::handlers::rag::call_llm_conversation;
+use crate::middleware::{extract_claims, validate_input};
+use crate::models::*;
+use crate::utils::{AppError, AppResult, AppState};
+
+/// Scholars summarised per background pass
+const SUMMARY_BATCH_SIZE: i64 = 10;
+
+const SUMMARY_PROMPT: &str = "You write the one or two sentence summary shown on a scholar's card \
+in a scholar database. Summarise who the scholar is and what they are known for, using only facts \
+stated in the introduction, in the language of the introduction. Reply with the summary only, \
+without a heading or quotation marks.";
+
+pub async fn get_scholar_summary(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let _claims = extract_claims(&req)?;
+
+    let summary = app_state.db.get_scholar_summary(&path.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+/// Rewrite a scholar's summary and/or lock it against regeneration
+/// An unlocked rewrite is replaced by a generated summary once the introduction changes
+pub async fn update_scholar_summary(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    input: web::Json<ScholarSummaryRequest>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let claims = extract_claims(&req)?;
+    validate_input(&*input)?;
+    if input.summary.is_none() && input.locked.is_none() {
+        return Err(AppError::BadRequest(
+            "Nothing to update: give a summary, locked, or both".to_string(),
+        ));
+    }
+
+    let summary = app_state
+        .db
+        .update_scholar_summary(&path.into_inner(), &input, &claims.user_id)
+        .await?;
+    log::info!(
+        "Summary of scholar {} updated by {} (locked: {})",
+        summary.scholar_id,
+        claims.user_id,
+        summary.locked
+    );
+
+    app_state.cache.invalidate_pattern("/api/scholars").await;
+
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+/// Discard a scholar's summary; a new one is generated in the background
+pub async fn delete_scholar_summary(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    req: HttpRequest,
+) -> AppResult<HttpResponse> {
+    let _claims = extract_claims(&req)?;
+
+    app_state
+        .db
+        .delete_scholar_summary(&path.into_inner())
+        .await?;
+
+    app_state.cache.invalidate_pattern("/api/scholars").await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn generate_summary(
+    app_state: &AppState,
+    scholar: &PendingScholarSummary,
+) -> AppResult<String> {
+    let messages = [ChatMessage {
+        role: "user".to_string(),
+        content: format!(
+            "{}\n\nIntroduction:\n{}",
+            scholar.name, scholar.introduction
+        ),
+    }];
+    let usage = UsageContext::anonymous("scholar_summary");
+    let reply =
+        call_llm_conversation(SUMMARY_PROMPT, &messages, 0.3, 200, usage, app_state).await?;
+
+    let summary = reply
+        .trim()
+        .trim_matches(|c| c == '"' || c == '“' || c == '”')
+        .trim();
+    if summary.is_empty() {
+        return Err(AppError::UpstreamInvalidResponse(
+            "LLM API sent an empty summary".to_string(),
+        ));
+    }
+    Ok(summary.to_string())
+}
+
+/// Background step that summarises scholars whose summary is missing or was written
+/// from an older introduction; locked summaries are left alone
+pub async fn process_scholar_summaries(app_state: &AppState) -> AppResult<()> {
+    if !app_state.scholar_summaries || app_state.llm_api_key.is_empty() {
+        return Ok(());
+    }
+
+    let pending = app_state
+        .db
+        .get_pending_scholar_summaries(SUMMARY_BATCH_SIZE)
+        .await?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let mut saved = 0;
+    for scholar in &pending {
+        let content_hash = hash_embedding_text(&scholar.introduction);
+        match generate_summary(app_state, scholar).await {
+            Ok(summary) => {
+                let stored = app_state
+                    .db
+                    .save_generated_summary(
+                        &scholar.id,
+                        &summary,
+                        &content_hash,
+                        &app_state.chat_model,
+                    )
+                    .await?;
+                app_state
+                    .db
+                    .clear_scholar_summary_failures(&scholar.id)
+                    .await?;
+                if stored {
+                    saved += 1;
+                }
+            }
+            // Retried once its backoff has passed
+            Err(e) => {
+                log::warn!("Failed to summarise scholar {}: {}", scholar.id, e);
+                app_state
+                    .db
+                    .fail_scholar_summary(&scholar.id, &content_hash, &e.to_string())
+                    .await?;
+            }
+        }
+    }
+
+    if saved > 0 {
+        log::info!("Generated {} scholar summaries", saved);
+        app_state.cache.invalidate_pattern("/api/scholars").await;
+    }
+
+    Ok(())
+}
@@ -2,6 +2,7 @@ use actix_web::{HttpRequest, HttpResponse, web};
 
 use crate::middleware::{extract_claims, require_admin, validate_input};
 use crate::models::*;
+use crate::scholar_map::build_scholar_map;
 use crate::utils::{AppError, AppResult, AppState};
 
 pub async fn list_scholars(
@@ -29,6 +30,13 @@ pub async fn get_scholar(
     Ok(HttpResponse::Ok().json(scholar_response))
 }
 
+/// Visible scholars placed on a 2D map by the similarity of their profiles
+pub async fn get_scholar_map(app_state: web::Data<AppState>) -> AppResult<HttpResponse> {
+    let map = build_scholar_map(&app_state).await?;
+
+    Ok(HttpResponse::Ok().json(map))
+}
+
 pub async fn get_scholar_admin(
     app_state: web::Data<AppState>,
     path: web::Path<String>,
@@ -69,13 +77,9 @@ pub async fn create_scholar(
     let scholar = app_state.db.create_scholar(&input, &claims.user_id).await?;
     let scholar_response = app_state.db.get_scholar(&scholar.id).await?;
 
-    // Auto-embed the scholar (non-blocking, errors are logged)
-    let app_state_clone = app_state.clone();
-    tokio::spawn(async move {
-        if let Err(e) = crate::handlers::rag::embed_single_scholar(&app_state_clone, &scholar.id).await {
-            log::error!("Failed to auto-embed scholar {}: {}", scholar.id, e);
-        }
-    });
+    // Queue the scholar for embedding (processed in the background)
+    crate::handlers::rag::queue_embedding_refresh(&app_state, &[scholar.id], "scholar_created")
+        .await;
 
     app_state.cache.invalidate_pattern("/api/scholars").await;
     app_state.cache.invalidate_pattern("/api/tags").await;
@@ -99,14 +103,9 @@ pub async fn update_scholar(
         .await?;
     let scholar_response = app_state.db.get_scholar(&scholar.id).await?;
 
-    // Auto-reembed the scholar (non-blocking, errors are logged)
-    let app_state_clone = app_state.clone();
-    let scholar_id_clone = scholar.id.clone();
-    tokio::spawn(async move {
-        if let Err(e) = crate::handlers::rag::embed_single_scholar(&app_state_clone, &scholar_id_clone).await {
-            log::error!("Failed to auto-embed scholar {}: {}", scholar_id_clone, e);
-        }
-    });
+    // Queue the scholar for re-embedding if its profile text changed
+    crate::handlers::rag::queue_embedding_refresh(&app_state, &[scholar.id], "scholar_updated")
+        .await;
 
     app_state.cache.invalidate_pattern("/api/scholars").await;
     app_state.cache.invalidate_pattern("/api/tags").await;
@@ -148,6 +147,7 @@ pub async fn delete_scholar(
 
     let scholar_id = path.into_inner();
     app_state.db.delete_scholar(&scholar_id).await?;
+    app_state.rag_cache.invalidate_scholars(&[scholar_id]);
 
     app_state.cache.invalidate_pattern("/api/scholars").await;
     app_state.cache.invalidate_pattern("/api/tags").await;
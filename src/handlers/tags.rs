@@ -88,6 +88,7 @@ pub async fn create_tag(
 
     app_state.cache.invalidate_pattern("/api/tags").await;
     app_state.cache.invalidate_pattern("/api/scholars").await;
+    crate::handlers::rag::queue_rag_document_sync(&app_state, std::slice::from_ref(&tag.id));
 
     Ok(HttpResponse::Created().json(TagResponse { tag, scholars }))
 }
@@ -114,6 +115,7 @@ pub async fn update_tag(
         .update_tag(&tag_id, &tag_request, &claims.user_id)
         .await?;
     let (scholar_ids, _) = app_state.db.get_tag_scholars(&tag.id, None, None).await?;
+    crate::handlers::rag::queue_embedding_refresh(&app_state, &scholar_ids, "tag_updated").await;
     let scholars_map = app_state.db.get_scholars_info(&scholar_ids).await?;
 
     let scholars: Vec<ScholarInfo> = scholar_ids
@@ -123,6 +125,7 @@ pub async fn update_tag(
 
     app_state.cache.invalidate_pattern("/api/tags").await;
     app_state.cache.invalidate_pattern("/api/scholars").await;
+    crate::handlers::rag::queue_rag_document_sync(&app_state, &[tag_id]);
 
     Ok(HttpResponse::Ok().json(TagResponse { tag, scholars }))
 }
@@ -136,10 +139,13 @@ pub async fn delete_tag(
     require_admin(&claims)?;
 
     let tag_id = path.into_inner();
+    let (scholar_ids, _) = app_state.db.get_tag_scholars(&tag_id, None, None).await?;
     app_state.db.delete_tag(&tag_id).await?;
+    crate::handlers::rag::queue_embedding_refresh(&app_state, &scholar_ids, "tag_deleted").await;
 
     app_state.cache.invalidate_pattern("/api/tags").await;
     app_state.cache.invalidate_pattern("/api/scholars").await;
+    crate::handlers::rag::queue_rag_document_sync(&app_state, &[tag_id]);
 
     Ok(HttpResponse::NoContent().finish())
 }
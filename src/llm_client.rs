@@ -0,0 +1,273 @@
+use reqwest::{StatusCode, header::RETRY_AFTER};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::utils::{AppError, AppResult};
+
+/// Longest Retry-After a retried call waits out; longer ones fail the call instead
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+/// Upstream error bodies are logged up to this many characters
+const MAX_ERROR_BODY_CHARS: usize = 500;
+
+pub struct LlmClientSettings {
+    pub connect_timeout: Duration,
+    /// Longest wait for the next bytes of a response, including the first ones
+    pub read_timeout: Duration,
+    /// Extra attempts for idempotent calls
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled for each one after
+    pub retry_delay: Duration,
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+    /// How long an open circuit fails calls before letting a trial call through
+    pub open_duration: Duration,
+}
+
+impl LlmClientSettings {
+    pub fn from_env() -> Self {
+        let setting = |key: &str, default: u64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            connect_timeout: Duration::from_secs(setting("LLM_CONNECT_TIMEOUT_SECS", 10).max(1)),
+            read_timeout: Duration::from_secs(setting("LLM_READ_TIMEOUT_SECS", 120).max(1)),
+            max_retries: setting("LLM_MAX_RETRIES", 3).min(10) as u32,
+            retry_delay: Duration::from_millis(setting("LLM_RETRY_DELAY_MS", 500)),
+            failure_threshold: setting("LLM_CIRCUIT_FAILURES", 5).max(1) as u32,
+            open_duration: Duration::from_secs(setting("LLM_CIRCUIT_OPEN_SECS", 30).max(1)),
+        }
+    }
+}
+
+/// HTTP client for the LLM, embedding and rerank APIs
+/// Calls time out instead of holding a worker, idempotent calls are retried, and after
+/// repeated failures a circuit breaker fails calls immediately until the API recovers;
+/// each API has its own breaker, so an outage of one leaves the others usable
+#[derive(Clone)]
+pub struct LlmClient {
+    http: reqwest::Client,
+    settings: Arc<LlmClientSettings>,
+    /// Keyed by the API name passed to `post_json`
+    breakers: Arc<Mutex<HashMap<String, Breaker>>>,
+}
+
+#[derive(Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    /// Set while the circuit is open
+    open_until: Option<Instant>,
+}
+
+impl LlmClient {
+    pub fn new(settings: LlmClientSettings) -> Self {
+        let http = reqwest::Client::builder()
+            .connect_timeout(settings.connect_timeout)
+            .read_timeout(settings.read_timeout)
+            .build()
+            .expect("Failed to build LLM HTTP client");
+
+        Self {
+            http,
+            settings: Arc::new(settings),
+            breakers: Arc::default(),
+        }
+    }
+
+    /// POST a JSON request and return the JSON response
+    /// `api` names the API in errors and logs; with `retry`, meant for idempotent calls,
+    /// timeouts, network failures, server errors and rate limits are retried
+    pub async fn post_json(
+        &self,
+        api: &str,
+        url: &str,
+        api_key: &str,
+        body: &Value,
+        retry: bool,
+    ) -> AppResult<Value> {
+        let attempts = if retry {
+            self.settings.max_retries + 1
+        } else {
+            1
+        };
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            self.permit(api)?;
+            let result = self.send(api, url, api_key, body).await;
+            self.record(api, &result);
+
+            let error = match result {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            let delay = match self.retry_delay(&error, attempt) {
+                Some(delay) if attempt < attempts => delay,
+                _ => return Err(error),
+            };
+            log::warn!(
+                "{} failed (attempt {} of {}), retrying in {:?}: {}",
+                api,
+                attempt,
+                attempts,
+                delay,
+                error
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn send(&self, api: &str, url: &str, api_key: &str, body: &Value) -> AppResult<Value> {
+        let response = self
+            .http
+            .post(url)
+            .bearer_auth(api_key)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| transport_error(api, e))?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after);
+            return Err(AppError::UpstreamRateLimited {
+                retry_after,
+                message: format!("{} rate limit reached", api),
+            });
+        }
+        if !status.is_success() {
+            // The body may echo the request; keep it in the log, out of the error
+            let text = response.text().await.unwrap_or_default();
+            log::warn!(
+                "{} error ({}): {}",
+                api,
+                status,
+                text.chars().take(MAX_ERROR_BODY_CHARS).collect::<String>()
+            );
+            return Err(AppError::UpstreamError {
+                status: Some(status.as_u16()),
+                message: format!("{} returned {}", api, status),
+            });
+        }
+
+        response.json().await.map_err(|e| {
+            if e.is_timeout() {
+                transport_error(api, e)
+            } else {
+                AppError::UpstreamInvalidResponse(format!(
+                    "{} sent an unreadable response: {}",
+                    api, e
+                ))
+            }
+        })
+    }
+
+    /// None when the error is not worth retrying
+    fn retry_delay(&self, error: &AppError, attempt: u32) -> Option<Duration> {
+        let backoff = self.settings.retry_delay * 2u32.saturating_pow(attempt - 1);
+        match error {
+            AppError::UpstreamRateLimited {
+                retry_after: Some(secs),
+                ..
+            } => {
+                let wait = Duration::from_secs(*secs);
+                (wait <= MAX_RETRY_AFTER).then_some(wait)
+            }
+            AppError::UpstreamRateLimited { .. }
+            | AppError::UpstreamTimeout(_)
+            | AppError::UpstreamError { status: None, .. } => Some(backoff),
+            AppError::UpstreamError {
+                status: Some(status),
+                ..
+            } if *status >= 500 => Some(backoff),
+            _ => None,
+        }
+    }
+
+    /// Fail fast while the circuit is open; once it has been open long enough, let one
+    /// trial call through and keep the rest waiting until the trial can have timed out
+    fn permit(&self, api: &str) -> AppResult<()> {
+        let mut breakers = self.breakers.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(breaker) = breakers.get_mut(api) else {
+            return Ok(());
+        };
+        let Some(open_until) = breaker.open_until else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        if now < open_until {
+            return Err(AppError::UpstreamUnavailable {
+                retry_after: (open_until - now).as_secs() + 1,
+                message: format!("{} circuit is open after repeated failures", api),
+            });
+        }
+        breaker.open_until = Some(now + self.settings.connect_timeout + self.settings.read_timeout);
+        Ok(())
+    }
+
+    /// Outages count towards opening the circuit; any other response shows the API is up
+    fn record(&self, api: &str, result: &AppResult<Value>) {
+        let failed = match result {
+            Err(AppError::UpstreamTimeout(_))
+            | Err(AppError::UpstreamError { status: None, .. }) => true,
+            Err(AppError::UpstreamError {
+                status: Some(status),
+                ..
+            }) => *status >= 500,
+            // Rate limits say nothing about whether the API is healthy
+            Err(AppError::UpstreamRateLimited { .. }) => return,
+            _ => false,
+        };
+
+        let mut breakers = self.breakers.lock().unwrap_or_else(PoisonError::into_inner);
+        let breaker = breakers.entry(api.to_string()).or_default();
+        if !failed {
+            if breaker.open_until.take().is_some() {
+                log::info!("{} recovered, closing the circuit", api);
+            }
+            breaker.consecutive_failures = 0;
+            return;
+        }
+
+        breaker.consecutive_failures += 1;
+        if breaker.consecutive_failures >= self.settings.failure_threshold {
+            log::warn!(
+                "{} failed {} times in a row, opening the circuit for {:?}",
+                api,
+                breaker.consecutive_failures,
+                self.settings.open_duration
+            );
+            breaker.open_until = Some(Instant::now() + self.settings.open_duration);
+        }
+    }
+}
+
+fn transport_error(api: &str, error: reqwest::Error) -> AppError {
+    if error.is_timeout() {
+        AppError::UpstreamTimeout(format!("{} did not respond in time", api))
+    } else {
+        AppError::UpstreamError {
+            status: None,
+            message: format!("Failed to call {}: {}", api, error),
+        }
+    }
+}
+
+/// Seconds to wait, given as a number of seconds or as an HTTP date
+fn parse_retry_after(value: &str) -> Option<u64> {
+    if let Ok(secs) = value.trim().parse() {
+        return Some(secs);
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    Some((date.timestamp() - chrono::Utc::now().timestamp()).max(0) as u64)
+}
@@ -1,8 +1,17 @@
+mod chat_tools;
+mod clustering;
 mod constants;
 mod db;
+mod evaluation;
+mod guardrails;
 mod handlers;
+mod llm_client;
 mod middleware;
 mod models;
+mod rag_cache;
+mod rerank;
+mod scholar_map;
+mod usage;
 mod utils;
 
 use actix_cors::Cors;
@@ -11,7 +20,7 @@ use actix_web::http::header;
 use actix_web::{App, HttpResponse, HttpServer, web};
 use dotenvy::dotenv;
 use std::env;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -28,7 +37,7 @@ async fn main() -> std::io::Result<()> {
     let oidc_client_id = env::var("OIDC_CLIENT_ID").expect("OIDC_CLIENT_ID must be set");
     let oidc_client_secret =
         env::var("OIDC_CLIENT_SECRET").expect("OIDC_CLIENT_SECRET must be set");
-    let db = db::Database::new(&database_url)
+    let db = db::Database::new(&database_url, db::VectorIndexSettings::from_env())
         .await
         .expect("Failed to initialize database");
 
@@ -45,6 +54,11 @@ async fn main() -> std::io::Result<()> {
     .await;
     log::info!("Application initialized");
 
+    // `sfl-database eval [--k N] [--threshold T]` scores retrieval and exits
+    if env::args().nth(1).as_deref() == Some("eval") {
+        return run_eval_command(&app_state, env::args().skip(2).collect()).await;
+    }
+
     let cleanup_db = app_state.db.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(3600));
@@ -59,10 +73,79 @@ async fn main() -> std::io::Result<()> {
                     log::error!("Failed to cleanup expired tokens: {}", e);
                 }
             }
+            if let Err(e) = cleanup_db.cleanup_ask_rate_limits().await {
+                log::error!("Failed to cleanup ask rate limits: {}", e);
+            }
         }
     });
     log::info!("Started background cleanup task for expired tokens");
 
+    if let Err(e) = handlers::rag::sync_embedding_model(&app_state).await {
+        log::error!("Failed to sync embedding model: {}", e);
+    }
+    if let Err(e) = app_state.db.ensure_vector_indexes().await {
+        log::error!("Failed to build vector indexes: {}", e);
+    }
+
+    let embedding_state = app_state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        // The drift scan rebuilds every embedding text, so it runs at startup and hourly
+        let mut last_full_scan: Option<Instant> = None;
+        loop {
+            interval.tick().await;
+            let full_scan =
+                last_full_scan.is_none_or(|at| at.elapsed() >= Duration::from_secs(3600));
+            if full_scan {
+                last_full_scan = Some(Instant::now());
+            }
+            if let Err(e) =
+                handlers::rag::process_embedding_backlog(&embedding_state, full_scan).await
+            {
+                log::error!("Failed to process embedding backlog: {}", e);
+            }
+        }
+    });
+    log::info!("Started background task for embedding backfill and model migrations");
+
+    let refresh_state = app_state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(10));
+        loop {
+            interval.tick().await;
+            if let Err(e) = handlers::rag::process_embedding_refresh_queue(&refresh_state).await {
+                log::error!("Failed to process embedding refresh queue: {}", e);
+            }
+        }
+    });
+    log::info!("Started background task for embedding refreshes");
+
+    let jobs_state = app_state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            if let Err(e) = handlers::rag::process_embedding_jobs(&jobs_state).await {
+                log::error!("Failed to process embedding jobs: {}", e);
+            }
+        }
+    });
+    log::info!("Started background worker for embedding jobs");
+
+    let summaries_state = app_state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            if let Err(e) =
+                handlers::scholar_summaries::process_scholar_summaries(&summaries_state).await
+            {
+                log::error!("Failed to process scholar summaries: {}", e);
+            }
+        }
+    });
+    log::info!("Started background task for scholar summaries");
+
     let bind_address = format!("{}:{}", host, port);
     log::info!("Starting server at http://{}", bind_address);
 
@@ -116,6 +199,87 @@ fn configure_routes(cfg: &mut web::ServiceConfig, cache: middleware::CacheMiddle
         );
 }
 
+/// Run the retrieval evaluation from the command line and print the report
+async fn run_eval_command(app_state: &utils::AppState, args: Vec<String>) -> std::io::Result<()> {
+    let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+
+    let mut k = 5;
+    let mut threshold = 0.0;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| invalid(format!("Missing value for {}", arg)))?;
+        match arg.as_str() {
+            "--k" => {
+                k = value
+                    .parse()
+                    .map_err(|_| invalid(format!("Invalid k: {}", value)))?
+            }
+            "--threshold" => {
+                threshold = value
+                    .parse()
+                    .map_err(|_| invalid(format!("Invalid threshold: {}", value)))?
+            }
+            other => return Err(invalid(format!("Unknown argument: {}", other))),
+        }
+    }
+    let k = k.clamp(1, models::MAX_RAG_LIMIT);
+
+    let usage = models::UsageContext::anonymous("retrieval_eval");
+    let run = evaluation::run_retrieval_evaluation(app_state, usage, k, threshold)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    let summary = &run.summary;
+
+    println!(
+        "Run {}: {} questions, model {}, reranker {}, threshold {}",
+        summary.id,
+        summary.question_count,
+        summary.embedding_model,
+        summary.reranker,
+        summary.threshold
+    );
+    println!("recall@{}: {:.3}", summary.k, summary.recall_at_k);
+    println!("MRR:       {:.3}", summary.mrr);
+
+    let previous = app_state
+        .db
+        .list_eval_runs(2)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?
+        .into_iter()
+        .find(|previous| previous.id != summary.id);
+    if let Some(previous) = previous {
+        println!(
+            "Compared to run {} ({}, model {}, reranker {}, k {}): recall {:+.3}, MRR {:+.3}",
+            previous.id,
+            previous.created_at.format("%Y-%m-%d %H:%M"),
+            previous.embedding_model,
+            previous.reranker,
+            previous.k,
+            summary.recall_at_k - previous.recall_at_k,
+            summary.mrr - previous.mrr
+        );
+    }
+
+    for result in run
+        .results
+        .iter()
+        .filter(|r| !r.missed_scholar_ids.is_empty())
+    {
+        println!(
+            "MISS {:.2} recall, RR {:.2}: {}\n     missed: {}",
+            result.recall,
+            result.reciprocal_rank,
+            result.question,
+            result.missed_scholar_ids.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
 async fn health_check(app_state: web::Data<utils::AppState>) -> HttpResponse {
     match sqlx::query("SELECT 1").fetch_one(&app_state.db.pool).await {
         Ok(_) => HttpResponse::Ok().body("OK"),
@@ -148,6 +312,7 @@ fn configure_api_routes(cfg: &mut web::ServiceConfig, cache: middleware::CacheMi
         web::scope("/scholars")
             .wrap(cache.clone())
             .route("", web::get().to(handlers::scholars::list_scholars))
+            .route("/map", web::get().to(handlers::scholars::get_scholar_map))
             .route("/{id}", web::get().to(handlers::scholars::get_scholar)),
     )
     .service(
@@ -167,6 +332,7 @@ fn configure_api_routes(cfg: &mut web::ServiceConfig, cache: middleware::CacheMi
             .wrap(cache.clone())
             .route("", web::get().to(handlers::identities::list_identities)),
     )
+    .route("/ask", web::post().to(handlers::rag::ask))
     .service(
         web::scope("/admin")
             .wrap(middleware::AuthMiddleware)
@@ -174,12 +340,32 @@ fn configure_api_routes(cfg: &mut web::ServiceConfig, cache: middleware::CacheMi
                 web::scope("/scholars")
                     .route("", web::get().to(handlers::scholars::list_all_scholars))
                     .route("", web::post().to(handlers::scholars::create_scholar))
+                    .route(
+                        "/draft",
+                        web::post().to(handlers::profile_drafts::draft_scholar_profile),
+                    )
                     .route("/{id}", web::get().to(handlers::scholars::get_scholar_admin))
                     .route("/{id}", web::put().to(handlers::scholars::update_scholar))
                     .route("/{id}", web::delete().to(handlers::scholars::delete_scholar))
                     .route(
                         "/{id}/history",
                         web::get().to(handlers::scholars::get_scholar_history),
+                    )
+                    .route(
+                        "/{id}/suggestions",
+                        web::get().to(handlers::scholar_suggestions::get_scholar_suggestions),
+                    )
+                    .route(
+                        "/{id}/summary",
+                        web::get().to(handlers::scholar_summaries::get_scholar_summary),
+                    )
+                    .route(
+                        "/{id}/summary",
+                        web::put().to(handlers::scholar_summaries::update_scholar_summary),
+                    )
+                    .route(
+                        "/{id}/summary",
+                        web::delete().to(handlers::scholar_summaries::delete_scholar_summary),
                     ),
             )
             .service(
@@ -226,8 +412,117 @@ fn configure_api_routes(cfg: &mut web::ServiceConfig, cache: middleware::CacheMi
             .service(
                 web::scope("/rag")
                     .route("/embed", web::post().to(handlers::rag::batch_embed_scholars))
+                    .route("/jobs", web::get().to(handlers::rag::list_embedding_jobs))
+                    .route(
+                        "/jobs/{id}",
+                        web::get().to(handlers::rag::get_embedding_job),
+                    )
+                    .route(
+                        "/jobs/{id}/cancel",
+                        web::post().to(handlers::rag::cancel_embedding_job),
+                    )
+                    .route(
+                        "/status",
+                        web::get().to(handlers::rag::get_embedding_status),
+                    )
+                    .route("/cache", web::get().to(handlers::rag::get_cache_stats))
+                    .route("/usage", web::get().to(handlers::rag::get_llm_usage))
+                    .route(
+                        "/guardrails",
+                        web::get().to(handlers::rag::list_guardrail_events),
+                    )
+                    .route(
+                        "/scholars/{id}",
+                        web::get().to(handlers::rag_inspection::inspect_scholar_embedding),
+                    )
+                    .route(
+                        "/similarity",
+                        web::post().to(handlers::rag_inspection::query_similarity),
+                    )
+                    .route(
+                        "/clusters",
+                        web::get().to(handlers::clustering::list_cluster_runs),
+                    )
+                    .route(
+                        "/clusters",
+                        web::post().to(handlers::clustering::run_clustering),
+                    )
+                    .route(
+                        "/clusters/{id}",
+                        web::get().to(handlers::clustering::get_cluster_run),
+                    )
+                    .service(
+                        web::scope("/eval")
+                            .route(
+                                "/questions",
+                                web::get().to(handlers::evaluation::list_eval_questions),
+                            )
+                            .route(
+                                "/questions",
+                                web::post().to(handlers::evaluation::create_eval_question),
+                            )
+                            .route(
+                                "/questions/{id}",
+                                web::get().to(handlers::evaluation::get_eval_question),
+                            )
+                            .route(
+                                "/questions/{id}",
+                                web::put().to(handlers::evaluation::update_eval_question),
+                            )
+                            .route(
+                                "/questions/{id}",
+                                web::delete().to(handlers::evaluation::delete_eval_question),
+                            )
+                            .route("/runs", web::get().to(handlers::evaluation::list_eval_runs))
+                            .route("/runs", web::post().to(handlers::evaluation::run_eval))
+                            .route(
+                                "/runs/{id}",
+                                web::get().to(handlers::evaluation::get_eval_run),
+                            ),
+                    )
+                    .route(
+                        "/models",
+                        web::get().to(handlers::rag::list_embedding_models),
+                    )
+                    .route(
+                        "/models/migration",
+                        web::post().to(handlers::rag::start_embedding_migration),
+                    )
+                    .route(
+                        "/models/migration",
+                        web::delete().to(handlers::rag::cancel_embedding_migration),
+                    )
                     .route("/search", web::post().to(handlers::rag::rag_search_authenticated))
-                    .route("/chat", web::post().to(handlers::rag::rag_chat)),
+                    .route("/chat", web::post().to(handlers::rag::rag_chat))
+                    .service(
+                        web::scope("/prompts")
+                            .route(
+                                "",
+                                web::get().to(handlers::prompt_templates::list_prompt_templates),
+                            )
+                            .route(
+                                "",
+                                web::post().to(handlers::prompt_templates::create_prompt_template),
+                            )
+                            .route(
+                                "/{id}",
+                                web::get().to(handlers::prompt_templates::get_prompt_template),
+                            )
+                            .route(
+                                "/{id}",
+                                web::delete()
+                                    .to(handlers::prompt_templates::delete_prompt_template),
+                            )
+                            .route(
+                                "/{id}/activate",
+                                web::post()
+                                    .to(handlers::prompt_templates::activate_prompt_template),
+                            )
+                            .route(
+                                "/{id}/preview",
+                                web::post().to(handlers::prompt_templates::preview_prompt_template),
+                            ),
+                    ),
             ),
     );
 }
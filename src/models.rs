@@ -1,19 +1,41 @@
+pub mod cluster;
 pub mod common;
+pub mod embedding_job;
+pub mod evaluation;
+pub mod guardrail;
 pub mod history;
 pub mod identity;
 pub mod image;
+pub mod llm_usage;
 pub mod news;
+pub mod profile_draft;
+pub mod prompt_template;
 pub mod rag;
+pub mod rag_inspection;
 pub mod scholar;
+pub mod scholar_map;
+pub mod scholar_suggestion;
+pub mod scholar_summary;
 pub mod tag;
 pub mod user;
 
+pub use cluster::*;
 pub use common::*;
+pub use embedding_job::*;
+pub use evaluation::*;
+pub use guardrail::*;
 pub use history::*;
 pub use identity::*;
 pub use image::*;
+pub use llm_usage::*;
 pub use news::*;
+pub use profile_draft::*;
+pub use prompt_template::*;
 pub use rag::*;
+pub use rag_inspection::*;
 pub use scholar::*;
+pub use scholar_map::*;
+pub use scholar_suggestion::*;
+pub use scholar_summary::*;
 pub use tag::*;
 pub use user::*;
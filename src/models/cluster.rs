@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+
+/// Most clusters a run may ask for
+pub const MAX_CLUSTERS: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct ClusterRunRequest {
+    /// Optional: number of clusters (default: about the square root of half the
+    /// embedded scholars, clamped to 2-50)
+    pub k: Option<usize>,
+    /// Optional: seed of the centroid initialisation (default: random)
+    pub seed: Option<u64>,
+}
+
+/// A scholar in a cluster with its cosine distance to the centroid
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterMember {
+    pub id: String,
+    pub name: String,
+    pub distance: f32,
+}
+
+/// How many of a cluster's members carry a tag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterTagCount {
+    #[serde(rename = "tagId")]
+    pub tag_id: String,
+    pub name: String,
+    pub count: i32,
+    /// Share of the cluster's members with the tag
+    pub share: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScholarCluster {
+    pub index: i32,
+    pub size: i32,
+    /// Members closest to the centroid, the cluster's most typical scholars
+    #[serde(rename = "centroidNearest")]
+    pub centroid_nearest: Vec<ClusterMember>,
+    /// Most common unarchived tags, most common first
+    #[serde(rename = "topTags")]
+    pub top_tags: Vec<ClusterTagCount>,
+    /// The top tag, when enough of the members carry it
+    #[serde(rename = "dominantTag")]
+    pub dominant_tag: Option<ClusterTagCount>,
+    /// No tag dominates: the cluster may be a topic the taxonomy lacks
+    #[serde(rename = "tagCandidate")]
+    pub tag_candidate: bool,
+    /// Every member, closest to the centroid first
+    pub members: Vec<ClusterMember>,
+}
+
+/// One clustering run, without its clusters
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ClusterRunSummary {
+    pub id: String,
+    #[serde(rename = "embeddingModel")]
+    pub embedding_model: String,
+    pub k: i32,
+    pub seed: i64,
+    #[serde(rename = "scholarCount")]
+    pub scholar_count: i32,
+    pub iterations: i32,
+    #[serde(rename = "meanDistance")]
+    pub mean_distance: f32,
+    #[serde(rename = "candidateCount")]
+    pub candidate_count: i32,
+    #[serde(rename = "createdBy")]
+    pub created_by: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ClusterRun {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub summary: ClusterRunSummary,
+    pub clusters: Json<Vec<ScholarCluster>>,
+}
+
+/// A non-deleted scholar's profile vector
+#[derive(Debug, Clone)]
+pub struct ScholarVector {
+    pub id: String,
+    pub name: String,
+    pub embedding: Vec<f32>,
+}
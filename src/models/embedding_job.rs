@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "embedding_job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingJobStatus {
+    Queued,
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "embedding_job_item_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingJobItemStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+/// A batch embedding job with progress counted from its items
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EmbeddingJob {
+    pub id: String,
+    pub mode: String,
+    pub model: String,
+    pub status: EmbeddingJobStatus,
+    pub error: Option<String>,
+    #[serde(rename = "createdBy")]
+    pub created_by: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "startedAt")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(rename = "finishedAt")]
+    pub finished_at: Option<DateTime<Utc>>,
+    pub total: i64,
+    pub pending: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+}
+
+/// A scholar within a job that failed at least once
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EmbeddingJobItemFailure {
+    #[serde(rename = "scholarId")]
+    pub scholar_id: String,
+    #[serde(rename = "scholarName")]
+    pub scholar_name: String,
+    /// Pending items are still being retried
+    pub status: EmbeddingJobItemStatus,
+    pub attempts: i32,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    #[serde(rename = "nextAttemptAt")]
+    pub next_attempt_at: DateTime<Utc>,
+}
+
+/// An item claimed by the worker
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct EmbeddingJobItem {
+    pub scholar_id: String,
+    pub attempts: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingJobDetail {
+    #[serde(flatten)]
+    pub job: EmbeddingJob,
+    pub failures: Vec<EmbeddingJobItemFailure>,
+}
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use validator::Validate;
+
+/// A question and the scholars retrieval should find for it
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EvalQuestion {
+    pub id: String,
+    pub question: String,
+    #[serde(rename = "expectedScholarIds")]
+    pub expected_scholar_ids: Vec<String>,
+    #[serde(rename = "includeHidden")]
+    pub include_hidden: bool,
+    pub identities: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
+    #[serde(rename = "createdBy")]
+    pub created_by: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct EvalQuestionRequest {
+    #[validate(length(min = 1, max = 2000))]
+    pub question: String,
+    #[serde(rename = "expectedScholarIds")]
+    #[validate(length(min = 1, max = 50))]
+    pub expected_scholar_ids: Vec<String>,
+    #[serde(default, rename = "includeHidden")]
+    pub include_hidden: bool,
+    pub identities: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EvalRunRequest {
+    /// Optional: scholars retrieved per question (default: 5, clamped to 1-20)
+    #[serde(
+        default = "super::rag::default_limit",
+        deserialize_with = "super::rag::clamp_limit"
+    )]
+    pub k: i64,
+    /// Optional: similarity threshold for vector search (default: 0.0)
+    #[serde(default)]
+    pub threshold: f32,
+}
+
+/// How retrieval did on one question
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalQuestionResult {
+    #[serde(rename = "questionId")]
+    pub question_id: String,
+    pub question: String,
+    #[serde(rename = "expectedScholarIds")]
+    pub expected_scholar_ids: Vec<String>,
+    /// Retrieved scholars, best first
+    #[serde(rename = "retrievedScholarIds")]
+    pub retrieved_scholar_ids: Vec<String>,
+    /// Expected scholars that were not retrieved
+    #[serde(rename = "missedScholarIds")]
+    pub missed_scholar_ids: Vec<String>,
+    /// Share of expected scholars found in the top k
+    pub recall: f32,
+    /// 1 / rank of the first expected scholar, 0 when none was retrieved
+    #[serde(rename = "reciprocalRank")]
+    pub reciprocal_rank: f32,
+}
+
+/// Scores of one evaluation run, without per-question results
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EvalRunSummary {
+    pub id: String,
+    #[serde(rename = "embeddingModel")]
+    pub embedding_model: String,
+    pub reranker: String,
+    pub k: i32,
+    pub threshold: f32,
+    #[serde(rename = "questionCount")]
+    pub question_count: i32,
+    #[serde(rename = "recallAtK")]
+    pub recall_at_k: f32,
+    pub mrr: f32,
+    #[serde(rename = "createdBy")]
+    pub created_by: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EvalRun {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub summary: EvalRunSummary,
+    pub results: Json<Vec<EvalQuestionResult>>,
+}
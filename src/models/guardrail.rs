@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::UsageContext;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "guardrail_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GuardrailKind {
+    /// A user message looked like an attempt to override the system prompt
+    PromptInjection,
+    /// An answer mentioned a scholar id
+    ScholarIdLeak,
+    /// An answer mentioned a hidden scholar where hidden scholars are excluded
+    HiddenScholarLeak,
+}
+
+/// One guardrail trigger, as recorded
+#[derive(Debug, Clone)]
+pub struct GuardrailEventRecord<'a> {
+    pub context: UsageContext<'a>,
+    pub kind: GuardrailKind,
+    pub detail: &'a str,
+    pub excerpt: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GuardrailEventQuery {
+    pub kind: Option<GuardrailKind>,
+    /// Most recent events returned (default: 100)
+    pub limit: Option<i64>,
+}
+
+impl GuardrailEventQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(100).clamp(1, 500)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct GuardrailEvent {
+    pub id: i64,
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
+    #[serde(rename = "userName")]
+    pub user_name: Option<String>,
+    pub endpoint: String,
+    pub kind: GuardrailKind,
+    pub detail: String,
+    pub excerpt: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,114 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "llm_call_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum LlmCallKind {
+    Embedding,
+    Chat,
+    Rerank,
+}
+
+/// Who an LLM API call is made for and why, recorded in the usage log
+#[derive(Debug, Clone, Copy)]
+pub struct UsageContext<'a> {
+    /// None for public endpoints and background work without an initiating user
+    pub user_id: Option<&'a str>,
+    pub endpoint: &'a str,
+}
+
+impl<'a> UsageContext<'a> {
+    pub fn user(user_id: &'a str, endpoint: &'a str) -> Self {
+        Self {
+            user_id: Some(user_id),
+            endpoint,
+        }
+    }
+
+    pub fn anonymous(endpoint: &'a str) -> Self {
+        Self {
+            user_id: None,
+            endpoint,
+        }
+    }
+}
+
+/// Token counts from an API response's `usage` field
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenUsage {
+    pub prompt_tokens: Option<i32>,
+    pub completion_tokens: Option<i32>,
+}
+
+impl TokenUsage {
+    pub fn from_response(body: &serde_json::Value) -> Self {
+        let usage = body.get("usage");
+        let count = |field: &str| {
+            usage
+                .and_then(|usage| usage.get(field))
+                .and_then(|tokens| tokens.as_i64())
+                .map(|tokens| tokens as i32)
+        };
+        Self {
+            // Rerank APIs commonly report only a total
+            prompt_tokens: count("prompt_tokens").or_else(|| count("total_tokens")),
+            completion_tokens: count("completion_tokens"),
+        }
+    }
+}
+
+/// One entry of the usage log
+#[derive(Debug, Clone)]
+pub struct LlmUsageRecord<'a> {
+    pub context: UsageContext<'a>,
+    pub kind: LlmCallKind,
+    pub model: &'a str,
+    pub tokens: TokenUsage,
+    pub latency_ms: i32,
+    /// None when the call succeeded
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LlmUsageQuery {
+    /// First day included (default: 30 days ago)
+    pub from: Option<NaiveDate>,
+    /// Last day included (default: today)
+    pub to: Option<NaiveDate>,
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
+}
+
+/// Calls and tokens for one user, or anonymous calls, over a period
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LlmUsageSummary {
+    /// None for the per-user totals
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day: Option<NaiveDate>,
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
+    #[serde(rename = "userName")]
+    pub user_name: Option<String>,
+    #[serde(rename = "userEmail")]
+    pub user_email: Option<String>,
+    pub calls: i64,
+    #[serde(rename = "failedCalls")]
+    pub failed_calls: i64,
+    #[serde(rename = "promptTokens")]
+    pub prompt_tokens: i64,
+    #[serde(rename = "completionTokens")]
+    pub completion_tokens: i64,
+    #[serde(rename = "averageLatencyMs")]
+    pub average_latency_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LlmUsageResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Totals per user over the period, heaviest first
+    pub users: Vec<LlmUsageSummary>,
+    /// Usage per user per day, most recent first
+    pub days: Vec<LlmUsageSummary>,
+}
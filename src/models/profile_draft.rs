@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Source material to draft a scholar profile from
+#[derive(Debug, Deserialize, Validate)]
+pub struct ProfileDraftRequest {
+    /// Pasted text such as a press release or CV excerpt
+    #[serde(rename = "sourceText")]
+    #[validate(length(min = 20, max = 20000))]
+    pub source_text: String,
+    /// Optional: scholar whose current profile the draft should build on
+    #[serde(rename = "scholarId")]
+    pub scholar_id: Option<String>,
+}
+
+/// A suggested field value and the sentence of the source that supports it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftSuggestion<T> {
+    pub value: T,
+    #[serde(rename = "sourceSentence", alias = "source_sentence")]
+    pub source_sentence: String,
+}
+
+/// Draft profile text and field suggestions, shaped to pre-fill a scholar form
+/// Nothing is saved
+#[derive(Debug, Serialize)]
+pub struct ProfileDraftResponse {
+    pub introduction: String,
+    #[serde(rename = "socialInfluence")]
+    pub social_influence: String,
+    /// None when the source does not state it
+    #[serde(rename = "fieldOfResearch")]
+    pub field_of_research: Option<DraftSuggestion<String>>,
+    #[serde(rename = "yearOfBirth")]
+    pub year_of_birth: Option<DraftSuggestion<i32>>,
+}
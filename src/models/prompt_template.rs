@@ -0,0 +1,135 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::{ContextTruncation, RAGScholarResult, RAGSource};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "prompt_use_case", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PromptUseCase {
+    /// Single-shot answers from the authenticated search endpoint
+    Search,
+    /// Multi-turn conversations from the authenticated chat endpoint
+    Chat,
+    /// Public questions from site visitors
+    Ask,
+}
+
+/// One version of the system prompt and generation settings for a use case
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PromptTemplate {
+    pub id: String,
+    #[serde(rename = "useCase")]
+    pub use_case: PromptUseCase,
+    pub version: i32,
+    pub name: String,
+    #[serde(rename = "systemPrompt")]
+    pub system_prompt: String,
+    pub temperature: f32,
+    #[serde(rename = "maxTokens")]
+    pub max_tokens: i32,
+    #[serde(rename = "isActive")]
+    pub is_active: bool,
+    #[serde(rename = "createdBy")]
+    pub created_by: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "activatedAt")]
+    pub activated_at: Option<DateTime<Utc>>,
+}
+
+/// Variables a system prompt may reference as `{name}`
+pub const PROMPT_VARIABLES: &[&str] = &["context", "query"];
+
+impl PromptTemplate {
+    /// Substitute the retrieved scholar context and the user's query in one pass, so
+    /// placeholders inside the inserted text are left as they are
+    pub fn render(&self, context: &str, query: &str) -> String {
+        let mut rendered = String::with_capacity(self.system_prompt.len() + context.len());
+        let mut rest = self.system_prompt.as_str();
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(after) = rest.strip_prefix("{context}") {
+                rendered.push_str(context);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("{query}") {
+                rendered.push_str(query);
+                rest = after;
+            } else {
+                rendered.push('{');
+                rest = &rest[1..];
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+
+    pub fn version_ref(&self) -> PromptTemplateRef {
+        PromptTemplateRef {
+            id: self.id.clone(),
+            version: self.version,
+        }
+    }
+}
+
+/// The template version that produced a RAG response
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptTemplateRef {
+    pub id: String,
+    pub version: i32,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PromptTemplateRequest {
+    #[serde(rename = "useCase")]
+    pub use_case: PromptUseCase,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[serde(rename = "systemPrompt")]
+    #[validate(length(min = 1, max = 20000))]
+    pub system_prompt: String,
+    #[validate(range(min = 0.0, max = 2.0))]
+    pub temperature: f32,
+    #[serde(rename = "maxTokens")]
+    #[validate(range(min = 1, max = 16000))]
+    pub max_tokens: i32,
+    /// Make the new version the active one for its use case
+    #[serde(default)]
+    pub activate: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromptTemplateQuery {
+    #[serde(rename = "useCase")]
+    pub use_case: Option<PromptUseCase>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromptPreviewRequest {
+    /// Sample query to retrieve scholars for
+    pub query: String,
+    /// Optional: maximum number of scholars to retrieve (default: 5, clamped to 1-20)
+    #[serde(
+        default = "super::rag::default_limit",
+        deserialize_with = "super::rag::clamp_limit"
+    )]
+    pub limit: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PromptPreviewResponse {
+    pub template: PromptTemplateRef,
+    /// System prompt as it would be sent to the chat model
+    #[serde(rename = "systemPrompt")]
+    pub system_prompt: String,
+    pub temperature: f32,
+    #[serde(rename = "maxTokens")]
+    pub max_tokens: i32,
+    /// Scholars that fit in the context
+    pub retrieved_scholars: Vec<RAGScholarResult>,
+    /// News and tag documents that fit in the context
+    pub retrieved_documents: Vec<RAGSource>,
+    pub context_truncation: ContextTruncation,
+}
@@ -1,11 +1,12 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Deserialize)]
 pub struct RAGSearchRequest {
     /// The user's query or message
     pub query: String,
-    /// Optional: maximum number of scholars to retrieve (default: 5)
-    #[serde(default = "default_limit")]
+    /// Optional: maximum number of scholars to retrieve (default: 5, clamped to 1-20)
+    #[serde(default = "default_limit", deserialize_with = "clamp_limit")]
     pub limit: i64,
     /// Optional: similarity threshold for vector search (0.0-1.0)
     #[serde(default = "default_threshold")]
@@ -17,12 +18,23 @@ pub struct RAGSearchRequest {
     pub identities: Option<Vec<String>>,
     /// Optional: filter by tag names
     pub tags: Option<Vec<String>>,
+    /// Optional: bypass the query embedding and answer caches (default: false)
+    #[serde(default)]
+    pub fresh: bool,
 }
 
-fn default_limit() -> i64 {
+/// Most scholars a RAG request may retrieve
+pub const MAX_RAG_LIMIT: i64 = 20;
+
+pub(crate) fn default_limit() -> i64 {
     5
 }
 
+/// Keep requested scholar counts within 1..=MAX_RAG_LIMIT instead of rejecting them
+pub(crate) fn clamp_limit<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    Ok(i64::deserialize(deserializer)?.clamp(1, MAX_RAG_LIMIT))
+}
+
 fn default_threshold() -> f32 {
     0.0
 }
@@ -37,6 +49,50 @@ pub struct RAGSearchResponse {
     pub response: String,
     /// Number of scholars used in the context
     pub context_count: i32,
+    /// Whether the response was served from the answer cache
+    pub cached: bool,
+    /// Prompt template version that produced the response; None when the LLM was not called
+    pub prompt_template: Option<super::PromptTemplateRef>,
+    /// How the scholar context was cut to fit the chat model's budget
+    pub context_truncation: Option<ContextTruncation>,
+    /// Scholars, news and tags in the context, most similar first
+    pub sources: Vec<RAGSource>,
+}
+
+/// What was left out of the scholar context to stay within its token budget
+/// Scholars are kept most relevant first; less relevant ones lose passages, then their place
+#[derive(Debug, Default, Serialize)]
+pub struct ContextTruncation {
+    /// Estimated tokens available for scholar context
+    pub budget_tokens: usize,
+    /// Estimated tokens of the context actually sent
+    pub used_tokens: usize,
+    /// Scholars included with some passages dropped or shortened
+    pub truncated_scholar_ids: Vec<String>,
+    /// Scholars retrieved but left out of the context entirely
+    pub dropped_scholar_ids: Vec<String>,
+    /// News and tag documents retrieved but left out of the context
+    pub dropped_document_ids: Vec<String>,
+}
+
+impl ContextTruncation {
+    pub fn is_truncated(&self) -> bool {
+        !self.truncated_scholar_ids.is_empty()
+            || !self.dropped_scholar_ids.is_empty()
+            || !self.dropped_document_ids.is_empty()
+    }
+}
+
+/// Visibility and metadata filters applied to vector searches
+#[derive(Debug, Clone, Copy)]
+pub struct RAGFilters<'a> {
+    pub include_hidden: bool,
+    /// Identity names
+    pub identities: Option<&'a [String]>,
+    /// Tag names
+    pub tags: Option<&'a [String]>,
+    /// Restrict the search to these scholars
+    pub scholar_ids: Option<&'a [String]>,
 }
 
 #[derive(Debug, Serialize)]
@@ -46,16 +102,146 @@ pub struct RAGScholarResult {
     pub field_of_research: String,
     pub introduction: String,
     pub social_influence: String,
+    /// Vector similarity score (0.0-1.0) of the best matching passage
+    pub similarity_score: f32,
+    /// Relevance score from the reranker, when one ran
+    pub rerank_score: Option<f32>,
+    /// Most relevant passages, best first
+    pub passages: Vec<RAGPassage>,
+    /// In context because the request pinned it, whatever its similarity
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "passage_field", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PassageField {
+    /// Name, field of research, identity, tags and news titles
+    Profile,
+    Introduction,
+    SocialInfluence,
+}
+
+impl PassageField {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PassageField::Profile => "Profile",
+            PassageField::Introduction => "Introduction",
+            PassageField::SocialInfluence => "Social Influence",
+        }
+    }
+}
+
+/// A chunk of a scholar's profile, ready to be embedded
+#[derive(Debug, Clone)]
+pub struct ScholarPassage {
+    pub field: PassageField,
+    /// Position of the passage within its field
+    pub passage_index: i32,
+    pub content: String,
+    /// Content with enough context to stand on its own for the embedding model
+    pub embedding_input: String,
+}
+
+/// Everything embedded for one scholar
+#[derive(Debug, Clone)]
+pub struct ScholarEmbeddingInput {
+    pub scholar_id: String,
+    /// Whole-profile text behind the scholar's single vector
+    pub embedding_text: String,
+    pub passages: Vec<ScholarPassage>,
+}
+
+/// A scholar's profile vector and passage vectors, ready to be stored
+#[derive(Debug, Clone)]
+pub struct EmbeddedScholar {
+    pub scholar_id: String,
+    pub embedding_text: String,
+    pub embedding: Vec<f32>,
+    pub passages: Vec<(ScholarPassage, Vec<f32>)>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RAGPassage {
+    pub field: PassageField,
+    pub content: String,
     /// Similarity score (0.0-1.0) from vector search
     pub similarity_score: f32,
 }
 
+/// Kinds of retrieval documents besides scholar passages
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, Hash)]
+#[sqlx(type_name = "rag_document_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RAGDocumentType {
+    News,
+    Tag,
+}
+
+/// A news item or tag description, ready to be embedded
+#[derive(Debug, Clone)]
+pub struct RAGDocumentInput {
+    pub doc_type: RAGDocumentType,
+    /// Id of the news item or tag
+    pub source_id: String,
+    pub content: String,
+}
+
+/// Something retrieved for a query, typed so clients can link to it
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RAGSource {
+    Scholar {
+        id: String,
+        name: String,
+        similarity_score: f32,
+    },
+    News {
+        id: String,
+        title: String,
+        source: String,
+        url: String,
+        publish_date: DateTime<Utc>,
+        similarity_score: f32,
+    },
+    Tag {
+        id: String,
+        name: String,
+        description: Option<String>,
+        similarity_score: f32,
+    },
+}
+
+impl RAGSource {
+    pub fn id(&self) -> &str {
+        match self {
+            RAGSource::Scholar { id, .. }
+            | RAGSource::News { id, .. }
+            | RAGSource::Tag { id, .. } => id,
+        }
+    }
+
+    pub fn similarity_score(&self) -> f32 {
+        match self {
+            RAGSource::Scholar {
+                similarity_score, ..
+            }
+            | RAGSource::News {
+                similarity_score, ..
+            }
+            | RAGSource::Tag {
+                similarity_score, ..
+            } => *similarity_score,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct RAGChatRequest {
     /// Conversation messages
     pub messages: Vec<ChatMessage>,
-    /// Optional: maximum number of scholars to retrieve for context
-    #[serde(default = "default_limit")]
+    /// Optional: maximum number of scholars to retrieve for context (default: 5, clamped to 1-20)
+    #[serde(default = "default_limit", deserialize_with = "clamp_limit")]
     pub limit: i64,
     /// Optional: include hidden scholars (default: false)
     #[serde(default)]
@@ -64,6 +250,26 @@ pub struct RAGChatRequest {
     pub identities: Option<Vec<String>>,
     /// Optional: filter by tag names
     pub tags: Option<Vec<String>>,
+    /// Optional: bypass the query embedding cache (default: false)
+    #[serde(default)]
+    pub fresh: bool,
+    /// Optional: scholars from the previous turn to keep in context
+    pub sticky_scholar_ids: Option<Vec<String>>,
+    /// Optional: scholars always in context ahead of retrieved ones, with their full
+    /// introduction and social influence; only visibility applies to them, not the
+    /// identity and tag filters
+    #[serde(alias = "scholarIds")]
+    pub scholar_ids: Option<Vec<String>>,
+    /// Optional: also put the pinned scholars' news in context (default: false)
+    #[serde(default)]
+    pub include_pinned_news: bool,
+}
+
+/// Public question about scholars from a site visitor
+#[derive(Debug, Deserialize)]
+pub struct AskRequest {
+    /// Conversation messages, ending with the visitor's question
+    pub messages: Vec<ChatMessage>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -77,8 +283,121 @@ pub struct ChatMessage {
 pub struct RAGChatResponse {
     /// The assistant's response message
     pub message: String,
+    /// Standalone query rewritten from the latest message and used for retrieval
+    pub standalone_query: String,
     /// Retrieved scholars used in the context
     pub context_scholars: Vec<RAGScholarResult>,
     /// Number of scholars used
     pub context_count: i32,
+    /// Prompt template version that produced the response
+    pub prompt_template: super::PromptTemplateRef,
+    /// How the scholar context was cut to fit the chat model's budget
+    pub context_truncation: ContextTruncation,
+    /// Scholars, news and tags in the context, most similar first
+    pub sources: Vec<RAGSource>,
+    /// Database tools the model called before answering, in call order
+    pub tool_calls: Vec<ToolCallRecord>,
+}
+
+/// One tool call made by the chat model and what it got back
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolCallRecord {
+    pub name: String,
+    /// Arguments as the model sent them
+    pub arguments: serde_json::Value,
+    /// None when the call failed
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "embedding_model_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingModelStatus {
+    Active,
+    Migrating,
+    Retired,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EmbeddingModel {
+    pub name: String,
+    /// Vector length, known once the model has returned its first embedding
+    pub dimensions: Option<i32>,
+    pub status: EmbeddingModelStatus,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "activatedAt")]
+    pub activated_at: Option<DateTime<Utc>>,
+    #[serde(rename = "retiredAt")]
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingMigrationProgress {
+    /// Model being migrated to
+    pub model: String,
+    /// Scholars that already have a vector from the new model
+    pub embedded: i64,
+    /// Scholars that need one before the switch-over
+    pub total: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingModelsResponse {
+    /// Model configured through EMBEDDING_MODEL
+    pub configured: String,
+    pub models: Vec<EmbeddingModel>,
+    pub migration: Option<EmbeddingMigrationProgress>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmbeddingMigrationRequest {
+    /// Name of the model to re-embed all scholars with
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct EmbeddingRefresh {
+    #[serde(rename = "scholarId")]
+    pub scholar_id: String,
+    pub reason: String,
+    #[serde(rename = "queuedAt")]
+    pub queued_at: DateTime<Utc>,
+    pub attempts: i32,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    #[serde(rename = "nextAttemptAt")]
+    pub next_attempt_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingStatusResponse {
+    /// Model whose vectors serve queries
+    pub model: String,
+    /// Non-deleted scholars
+    pub total: i64,
+    /// Scholars whose vector matches their current profile
+    pub fresh: i64,
+    /// Scholars whose profile changed since they were embedded
+    pub stale: i64,
+    /// Scholars without a vector from the active model
+    pub missing: i64,
+    /// Scholars waiting in the refresh queue
+    pub queued: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RAGCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Approximate number of cached entries
+    pub entries: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RAGCacheStatsResponse {
+    pub query_embeddings: RAGCacheStats,
+    /// None when answer caching is disabled
+    pub answers: Option<RAGCacheStats>,
 }
@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::PassageField;
+
+/// Most neighbors or scored scholars an inspection may list
+pub const MAX_INSPECTION_RESULTS: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct EmbeddingInspectionQuery {
+    /// Optional: nearest scholars to list (default: 10, clamped to 1-100)
+    pub neighbors: Option<i64>,
+    /// Optional: embedding model to inspect (default: the active model)
+    pub model: Option<String>,
+}
+
+impl EmbeddingInspectionQuery {
+    pub fn neighbors(&self) -> i64 {
+        self.neighbors
+            .unwrap_or(10)
+            .clamp(1, MAX_INSPECTION_RESULTS)
+    }
+}
+
+/// A scholar's profile vector as stored, without the vector itself
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StoredEmbedding {
+    pub model: String,
+    pub dimensions: i32,
+    pub embedding_text: String,
+    /// None for vectors stored before content hashes were kept
+    pub content_hash: Option<String>,
+    pub embedded_at: DateTime<Utc>,
+}
+
+/// A retrieval passage as stored for one model
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StoredPassage {
+    pub field: PassageField,
+    pub passage_index: i32,
+    pub content: String,
+}
+
+/// A scholar near another one, by exact cosine distance of the profile vectors
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct EmbeddingNeighbor {
+    pub id: String,
+    pub name: String,
+    pub visible: bool,
+    pub distance: f64,
+    pub similarity: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmbeddingInspectionResponse {
+    pub scholar_id: String,
+    pub name: String,
+    pub visible: bool,
+    pub model: String,
+    /// None when the scholar has no vector from the model
+    pub embedding: Option<StoredEmbedding>,
+    /// The text the scholar would be embedded with now
+    pub current_text: String,
+    /// Whether the stored vector was made from a different text than the current one
+    pub stale: bool,
+    /// Whether the scholar is waiting in the refresh queue
+    pub queued: bool,
+    pub passages: Vec<StoredPassage>,
+    pub neighbors: Vec<EmbeddingNeighbor>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuerySimilarityRequest {
+    pub query: String,
+    /// Optional: scholars to list (default: 20, clamped to 1-100)
+    pub limit: Option<i64>,
+    /// Optional: embedding model to score with (default: the active model)
+    pub model: Option<String>,
+    /// Optional: bypass the query embedding cache (default: false)
+    #[serde(default)]
+    pub fresh: bool,
+}
+
+impl QuerySimilarityRequest {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(20).clamp(1, MAX_INSPECTION_RESULTS)
+    }
+}
+
+/// A scholar's best passage for a query, scored exactly and before any threshold
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RawScholarSimilarity {
+    pub id: String,
+    pub name: String,
+    pub visible: bool,
+    pub similarity: f64,
+    pub best_field: PassageField,
+    pub best_passage: String,
+    /// Position in what the index-backed search returns for the same query with hidden
+    /// scholars included, None when it misses the scholar
+    #[sqlx(skip)]
+    pub index_rank: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuerySimilarityResponse {
+    pub query: String,
+    pub model: String,
+    pub scholars: Vec<RawScholarSimilarity>,
+}
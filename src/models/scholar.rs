@@ -112,6 +112,9 @@ pub struct ScholarResponse {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Earliest year of birth a scholar can have
+pub const MIN_YEAR_OF_BIRTH: i32 = 1900;
+
 #[derive(Debug, Deserialize, Validate)]
 pub struct ScholarRequest {
     #[validate(length(min = 1, max = 255))]
@@ -121,7 +124,7 @@ pub struct ScholarRequest {
     #[validate(length(min = 1, max = 500))]
     pub field_of_research: String,
     #[serde(rename = "yearOfBirth")]
-    #[validate(range(min = 1900))]
+    #[validate(range(min = MIN_YEAR_OF_BIRTH))]
     pub year_of_birth: i32,
     pub image: Option<String>,
     #[validate(length(min = 1))]
@@ -199,6 +202,10 @@ pub struct ScholarListItemExt {
     pub tags: Vec<TagListItem>,
     #[serde(rename = "imageFilename", skip_serializing_if = "Option::is_none")]
     pub image_filename: Option<String>,
+    /// One or two sentences for the scholar's card, generated from the introduction
+    /// or written by an editor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
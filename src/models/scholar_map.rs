@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::TagListItem;
+
+/// A visible scholar placed on the 2D map of profile vectors
+#[derive(Debug, Clone, Serialize)]
+pub struct ScholarMapPoint {
+    pub id: String,
+    pub name: String,
+    pub identity: String,
+    pub tags: Vec<TagListItem>,
+    #[serde(rename = "imageFilename", skip_serializing_if = "Option::is_none")]
+    pub image_filename: Option<String>,
+    /// Coordinates within [-1, 1]; distances are comparable across both axes
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScholarMapResponse {
+    #[serde(rename = "embeddingModel")]
+    pub embedding_model: String,
+    /// Share of the vectors' variance each axis captures
+    #[serde(rename = "explainedVariance")]
+    pub explained_variance: [f32; 2],
+    /// When the projection was last computed
+    #[serde(rename = "computedAt")]
+    pub computed_at: DateTime<Utc>,
+    pub points: Vec<ScholarMapPoint>,
+}
+
+/// Who a map point is, without its coordinates
+#[derive(Debug, sqlx::FromRow)]
+pub struct ScholarMapDetails {
+    pub id: String,
+    pub name: String,
+    pub identity: String,
+    pub image_filename: Option<String>,
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+/// Most neighbors a suggestion request may look at
+pub const MAX_SUGGESTION_NEIGHBORS: i64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct ScholarSuggestionQuery {
+    /// Optional: nearest scholars to aggregate over (default: 10, clamped to 1-50)
+    pub neighbors: Option<i64>,
+}
+
+impl ScholarSuggestionQuery {
+    pub fn neighbors(&self) -> i64 {
+        self.neighbors
+            .unwrap_or(10)
+            .clamp(1, MAX_SUGGESTION_NEIGHBORS)
+    }
+}
+
+/// A scholar close to another one in embedding space
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ScholarNeighbor {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "identityId")]
+    pub identity_id: String,
+    #[serde(rename = "identityName")]
+    pub identity_name: String,
+    /// Whether the identity is still offered to editors
+    #[serde(skip)]
+    pub identity_archived: bool,
+    /// Cosine similarity of the two profile vectors
+    pub similarity: f32,
+}
+
+/// A tag worn by neighboring scholars
+#[derive(Debug, Clone, Serialize)]
+pub struct TagSuggestion {
+    #[serde(rename = "tagId")]
+    pub tag_id: String,
+    pub name: String,
+    pub color: Option<String>,
+    /// Similarity-weighted share of the neighbors that have the tag, 0-1
+    pub confidence: f32,
+    pub explanation: String,
+    #[serde(rename = "supportingScholarIds")]
+    pub supporting_scholar_ids: Vec<String>,
+}
+
+/// An identity held by neighboring scholars
+#[derive(Debug, Clone, Serialize)]
+pub struct IdentitySuggestion {
+    #[serde(rename = "identityId")]
+    pub identity_id: String,
+    pub name: String,
+    /// Similarity-weighted share of the neighbors with the identity, 0-1
+    pub confidence: f32,
+    pub explanation: String,
+    /// The scholar already has this identity
+    #[serde(rename = "isCurrent")]
+    pub is_current: bool,
+    #[serde(rename = "supportingScholarIds")]
+    pub supporting_scholar_ids: Vec<String>,
+}
+
+/// Tag and identity suggestions for one scholar, best first
+/// Tags the scholar already has are never suggested
+#[derive(Debug, Serialize)]
+pub struct ScholarSuggestionsResponse {
+    #[serde(rename = "scholarId")]
+    pub scholar_id: String,
+    #[serde(rename = "embeddingModel")]
+    pub embedding_model: String,
+    /// False until the scholar has a profile vector from the active model;
+    /// there is nothing to suggest from before that
+    pub embedded: bool,
+    pub neighbors: Vec<ScholarNeighbor>,
+    pub tags: Vec<TagSuggestion>,
+    pub identities: Vec<IdentitySuggestion>,
+}
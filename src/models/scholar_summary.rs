@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// A scholar's card summary with how it was written
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ScholarSummary {
+    #[serde(rename = "scholarId")]
+    pub scholar_id: String,
+    pub summary: String,
+    /// Generation leaves locked summaries alone, even when the profile changes
+    pub locked: bool,
+    /// Chat model that wrote the summary, None when written by an editor
+    pub model: Option<String>,
+    #[serde(rename = "editedBy")]
+    pub edited_by: Option<String>,
+    /// The introduction changed since the summary was written
+    pub stale: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Rewrite a summary, lock or unlock it, or both
+#[derive(Debug, Deserialize, Validate)]
+pub struct ScholarSummaryRequest {
+    #[validate(length(min = 1, max = 500))]
+    pub summary: Option<String>,
+    pub locked: Option<bool>,
+}
+
+/// A scholar whose summary is missing or was written from an older introduction
+#[derive(Debug, sqlx::FromRow)]
+pub struct PendingScholarSummary {
+    pub id: String,
+    pub name: String,
+    pub introduction: String,
+}
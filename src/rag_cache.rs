@@ -0,0 +1,175 @@
+use moka::future::Cache;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::models::{RAGCacheStats, RAGCacheStatsResponse, RAGFilters};
+
+/// In-memory caches for RAG: query embeddings and, optionally, generated answers
+#[derive(Clone)]
+pub struct RAGCache {
+    embeddings: Cache<String, Arc<Vec<f32>>>,
+    embedding_counters: Arc<Counters>,
+    /// None when answer caching is disabled
+    answers: Option<Cache<String, CachedAnswer>>,
+    answer_counters: Arc<Counters>,
+}
+
+#[derive(Clone)]
+struct CachedAnswer {
+    answer: Arc<String>,
+    /// Scholars, news items and tags the answer was generated from, for invalidation
+    source_ids: Arc<Vec<String>>,
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Counters {
+    fn record(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self, entries: u64) -> RAGCacheStats {
+        RAGCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries,
+        }
+    }
+}
+
+impl RAGCache {
+    pub fn new(embedding_ttl: Duration, answer_ttl: Option<Duration>) -> Self {
+        let embeddings = Cache::builder()
+            .max_capacity(10_000)
+            .time_to_live(embedding_ttl)
+            .build();
+
+        let answers = answer_ttl.map(|ttl| {
+            Cache::builder()
+                .max_capacity(1_000)
+                .time_to_live(ttl)
+                .support_invalidation_closures()
+                .build()
+        });
+
+        Self {
+            embeddings,
+            embedding_counters: Arc::default(),
+            answers,
+            answer_counters: Arc::default(),
+        }
+    }
+
+    pub async fn get_embedding(&self, model: &str, text: &str) -> Option<Vec<f32>> {
+        let embedding = self.embeddings.get(&hash_key(&[model, text])).await;
+        self.embedding_counters.record(embedding.is_some());
+        embedding.map(|embedding| embedding.as_ref().clone())
+    }
+
+    pub async fn insert_embedding(&self, model: &str, text: &str, embedding: Vec<f32>) {
+        self.embeddings
+            .insert(hash_key(&[model, text]), Arc::new(embedding))
+            .await;
+    }
+
+    /// Key for an answer to `query` generated by `chat_model` with a prompt template version
+    /// from exactly these scholars
+    pub fn answer_key(
+        chat_model: &str,
+        prompt_template_id: &str,
+        query: &str,
+        filters: &RAGFilters<'_>,
+        scholar_ids: &[String],
+    ) -> String {
+        let sorted = |values: Option<&[String]>| {
+            let mut values = values.map(|v| v.to_vec()).unwrap_or_default();
+            values.sort();
+            values.join("\u{1f}")
+        };
+
+        hash_key(&[
+            chat_model,
+            prompt_template_id,
+            query,
+            if filters.include_hidden {
+                "hidden"
+            } else {
+                "visible"
+            },
+            &sorted(filters.identities),
+            &sorted(filters.tags),
+            &sorted(filters.scholar_ids),
+            &scholar_ids.join("\u{1f}"),
+        ])
+    }
+
+    pub async fn get_answer(&self, key: &str) -> Option<String> {
+        let answers = self.answers.as_ref()?;
+        let answer = answers.get(key).await;
+        self.answer_counters.record(answer.is_some());
+        answer.map(|cached| cached.answer.as_ref().clone())
+    }
+
+    pub async fn insert_answer(&self, key: String, answer: &str, source_ids: &[String]) {
+        if let Some(answers) = &self.answers {
+            let cached = CachedAnswer {
+                answer: Arc::new(answer.to_string()),
+                source_ids: Arc::new(source_ids.to_vec()),
+            };
+            answers.insert(key, cached).await;
+        }
+    }
+
+    /// Drop cached answers generated from any of these scholars
+    pub fn invalidate_scholars(&self, scholar_ids: &[String]) {
+        self.invalidate_sources(scholar_ids);
+    }
+
+    /// Drop cached answers that quote any of these news items or tags
+    pub fn invalidate_documents(&self, source_ids: &[String]) {
+        self.invalidate_sources(source_ids);
+    }
+
+    fn invalidate_sources(&self, source_ids: &[String]) {
+        let Some(answers) = &self.answers else {
+            return;
+        };
+        if source_ids.is_empty() {
+            return;
+        }
+
+        let source_ids = source_ids.to_vec();
+        answers
+            .invalidate_entries_if(move |_, cached| {
+                cached.source_ids.iter().any(|id| source_ids.contains(id))
+            })
+            .expect("Failed to invalidate cache");
+    }
+
+    pub fn stats(&self) -> RAGCacheStatsResponse {
+        RAGCacheStatsResponse {
+            query_embeddings: self.embedding_counters.stats(self.embeddings.entry_count()),
+            answers: self
+                .answers
+                .as_ref()
+                .map(|answers| self.answer_counters.stats(answers.entry_count())),
+        }
+    }
+}
+
+/// Hash the parts with separators so that different splits never collide
+fn hash_key(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.len().to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
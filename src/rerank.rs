@@ -0,0 +1,270 @@
+use serde_json::json;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::models::{ChatMessage, LlmCallKind, RAGScholarResult, TokenUsage, UsageContext};
+use crate::usage::metered;
+use crate::utils::{AppError, AppResult, AppState};
+
+/// Second retrieval stage that reorders vector search candidates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reranker {
+    /// Keep the vector search order
+    None,
+    /// OpenAI-style `/rerank` endpoint (Cohere, Jina, TEI and compatible servers)
+    Api,
+    /// Ask the chat model to score each candidate
+    Llm,
+    /// Share of query terms found in the candidate's text
+    Lexical,
+}
+
+impl Reranker {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Reranker::None => "none",
+            Reranker::Api => "api",
+            Reranker::Llm => "llm",
+            Reranker::Lexical => "lexical",
+        }
+    }
+}
+
+impl FromStr for Reranker {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "none" | "" => Ok(Reranker::None),
+            "api" => Ok(Reranker::Api),
+            "llm" => Ok(Reranker::Llm),
+            "lexical" => Ok(Reranker::Lexical),
+            other => Err(format!("Unknown reranker: {}", other)),
+        }
+    }
+}
+
+/// Rerank candidates for `query` and keep the best `limit`
+/// Scores are recorded in `rerank_score`; when the API or LLM reranker fails,
+/// the lexical reranker is used instead so the request still succeeds
+pub async fn rerank(
+    app_state: &AppState,
+    usage: UsageContext<'_>,
+    query: &str,
+    mut candidates: Vec<RAGScholarResult>,
+    limit: usize,
+) -> Vec<RAGScholarResult> {
+    let documents: Vec<String> = candidates.iter().map(rerank_document).collect();
+
+    let scores = match app_state.reranker {
+        Reranker::None => {
+            candidates.truncate(limit);
+            return candidates;
+        }
+        Reranker::Lexical => Ok(lexical_scores(query, &documents)),
+        Reranker::Api => {
+            metered(
+                app_state,
+                usage,
+                LlmCallKind::Rerank,
+                &app_state.rerank_model,
+                api_scores(app_state, query, &documents),
+            )
+            .await
+        }
+        Reranker::Llm => llm_scores(app_state, usage, query, &documents).await,
+    };
+
+    let scores = scores.unwrap_or_else(|e| {
+        log::warn!(
+            "{:?} reranker failed, falling back to lexical overlap: {}",
+            app_state.reranker,
+            e
+        );
+        lexical_scores(query, &documents)
+    });
+
+    for (candidate, score) in candidates.iter_mut().zip(scores) {
+        candidate.rerank_score = Some(score);
+    }
+
+    // Ties keep the vector order
+    candidates.sort_by(|a, b| {
+        b.rerank_score
+            .partial_cmp(&a.rerank_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    candidates.truncate(limit);
+    candidates
+}
+
+/// Text a reranker judges a candidate by: its name, field and retrieved passages
+fn rerank_document(scholar: &RAGScholarResult) -> String {
+    let passages = scholar
+        .passages
+        .iter()
+        .map(|p| p.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "{} | {}\n{}",
+        scholar.name, scholar.field_of_research, passages
+    )
+}
+
+/// Lowercased words for Latin text and character bigrams for CJK text,
+/// which has no spaces between words
+fn terms(text: &str) -> HashSet<String> {
+    let mut terms = HashSet::new();
+    let mut word = String::new();
+    let mut previous_cjk: Option<char> = None;
+
+    for c in text.chars() {
+        if c.is_alphanumeric() && c.is_ascii() {
+            word.extend(c.to_lowercase());
+            previous_cjk = None;
+            continue;
+        }
+        if !word.is_empty() {
+            terms.insert(std::mem::take(&mut word));
+        }
+        if c.is_alphanumeric() {
+            match previous_cjk {
+                Some(previous) => terms.insert(format!("{}{}", previous, c)),
+                None => terms.insert(c.to_string()),
+            };
+            previous_cjk = Some(c);
+        } else {
+            previous_cjk = None;
+        }
+    }
+    if !word.is_empty() {
+        terms.insert(word);
+    }
+
+    terms
+}
+
+fn lexical_scores(query: &str, documents: &[String]) -> Vec<f32> {
+    let query_terms = terms(query);
+    if query_terms.is_empty() {
+        return vec![0.0; documents.len()];
+    }
+
+    documents
+        .iter()
+        .map(|document| {
+            let document_terms = terms(document);
+            let matched = query_terms.intersection(&document_terms).count();
+            matched as f32 / query_terms.len() as f32
+        })
+        .collect()
+}
+
+async fn api_scores(
+    app_state: &AppState,
+    query: &str,
+    documents: &[String],
+) -> AppResult<(Vec<f32>, TokenUsage)> {
+    let request_body = json!({
+        "model": app_state.rerank_model,
+        "query": query,
+        "documents": documents,
+        "top_n": documents.len()
+    });
+
+    // Not retried: a failed rerank falls back to lexical scoring straight away
+    let body = app_state
+        .llm_client
+        .post_json(
+            "Rerank API",
+            &format!("{}/rerank", app_state.rerank_base_url),
+            &app_state.llm_api_key,
+            &request_body,
+            false,
+        )
+        .await?;
+
+    let results = body
+        .get("results")
+        .and_then(|results| results.as_array())
+        .ok_or_else(|| {
+            AppError::UpstreamInvalidResponse("Invalid rerank response format".to_string())
+        })?;
+
+    // Results are sorted by score and refer back to documents by index
+    let mut scores = vec![0.0; documents.len()];
+    for result in results {
+        let index = result
+            .get("index")
+            .and_then(|i| i.as_u64())
+            .map(|i| i as usize);
+        let score = result.get("relevance_score").and_then(|s| s.as_f64());
+        match (index, score) {
+            (Some(index), Some(score)) if index < scores.len() => scores[index] = score as f32,
+            _ => {
+                return Err(AppError::UpstreamInvalidResponse(
+                    "Invalid rerank response format".to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok((scores, TokenUsage::from_response(&body)))
+}
+
+async fn llm_scores(
+    app_state: &AppState,
+    usage: UsageContext<'_>,
+    query: &str,
+    documents: &[String],
+) -> AppResult<Vec<f32>> {
+    let system_prompt = "You judge how relevant scholar profiles are to a search query. \
+        Score each numbered profile from 0 (irrelevant) to 10 (exactly what is asked for). \
+        Reply with a JSON array of numbers only, one score per profile, in order.";
+
+    let profiles = documents
+        .iter()
+        .enumerate()
+        .map(|(i, document)| format!("[{}] {}", i + 1, document))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let messages = [ChatMessage {
+        role: "user".to_string(),
+        content: format!("Query: {}\n\nProfiles:\n{}", query, profiles),
+    }];
+
+    let reply = crate::handlers::rag::call_llm_conversation(
+        system_prompt,
+        &messages,
+        0.0,
+        (documents.len() * 8 + 50) as u32,
+        usage,
+        app_state,
+    )
+    .await?;
+
+    // Tolerate prose or code fences around the array
+    let scores: Vec<f32> = reply
+        .find('[')
+        .zip(reply.rfind(']'))
+        .and_then(|(start, end)| serde_json::from_str(reply.get(start..=end)?).ok())
+        .ok_or_else(|| {
+            // The reply may quote the profiles; keep it in the log, out of the error
+            log::warn!("Invalid LLM rerank reply: {}", reply);
+            AppError::UpstreamInvalidResponse("LLM API sent an unreadable rerank reply".to_string())
+        })?;
+
+    if scores.len() != documents.len() {
+        return Err(AppError::UpstreamInvalidResponse(format!(
+            "LLM returned {} scores for {} profiles",
+            scores.len(),
+            documents.len()
+        )));
+    }
+
+    Ok(scores
+        .into_iter()
+        .map(|score| score.clamp(0.0, 10.0) / 10.0)
+        .collect())
+}
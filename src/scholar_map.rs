@@ -0,0 +1,226 @@
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::clustering::{dot, normalize};
+use crate::handlers::rag::active_embedding_model;
+use crate::models::{ScholarMapPoint, ScholarMapResponse};
+use crate::utils::{AppError, AppResult, AppState};
+
+/// Power iterations per principal component before settling for the estimate
+const POWER_ITERATIONS: usize = 200;
+/// Change in direction below which a component counts as converged
+const CONVERGENCE: f32 = 1e-5;
+
+/// The last 2D projection of the visible scholars' profile vectors, kept until a
+/// vector is stored or a scholar is shown, hidden or deleted
+#[derive(Clone, Default)]
+pub struct ScholarMapCache {
+    /// Locked while a projection is computed, so concurrent requests wait for it
+    projection: Arc<Mutex<Option<Arc<Projection>>>>,
+}
+
+struct Projection {
+    model: String,
+    fingerprint: String,
+    computed_at: DateTime<Utc>,
+    explained_variance: [f32; 2],
+    /// Scholar id with its coordinates
+    coordinates: Vec<(String, [f32; 2])>,
+}
+
+impl ScholarMapCache {
+    async fn projection(&self, app_state: &AppState) -> AppResult<Arc<Projection>> {
+        let model = active_embedding_model(app_state).await?;
+        let fingerprint = app_state.db.get_scholar_map_fingerprint(&model).await?;
+
+        let mut cached = self.projection.lock().await;
+        if let Some(projection) = cached
+            .as_ref()
+            .filter(|projection| projection.model == model && projection.fingerprint == fingerprint)
+        {
+            return Ok(projection.clone());
+        }
+
+        let scholars = app_state.db.get_scholar_vectors(&model, false).await?;
+        let (ids, vectors): (Vec<String>, Vec<Vec<f32>>) = scholars
+            .into_iter()
+            .map(|scholar| (scholar.id, normalize(scholar.embedding)))
+            .unzip();
+        let (coordinates, explained_variance) =
+            tokio::task::spawn_blocking(move || project_2d(&vectors))
+                .await
+                .map_err(|e| AppError::InternalError(format!("Projection failed: {}", e)))?;
+        log::info!(
+            "Projected {} scholars with {} onto the map ({:.1}% of variance)",
+            ids.len(),
+            model,
+            (explained_variance[0] + explained_variance[1]) * 100.0
+        );
+
+        let projection = Arc::new(Projection {
+            model,
+            fingerprint,
+            computed_at: Utc::now(),
+            explained_variance,
+            coordinates: ids.into_iter().zip(coordinates).collect(),
+        });
+        *cached = Some(projection.clone());
+        Ok(projection)
+    }
+}
+
+/// Every visible embedded scholar on a 2D map, similar profiles close together
+pub async fn build_scholar_map(app_state: &AppState) -> AppResult<ScholarMapResponse> {
+    let projection = app_state.scholar_map.projection(app_state).await?;
+
+    let ids: Vec<String> = projection
+        .coordinates
+        .iter()
+        .map(|(id, _)| id.clone())
+        .collect();
+    let mut tags = app_state.db.get_active_tags_by_scholar(&ids).await?;
+    let coordinates: std::collections::HashMap<&str, [f32; 2]> = projection
+        .coordinates
+        .iter()
+        .map(|(id, point)| (id.as_str(), *point))
+        .collect();
+
+    // Scholars hidden since the projection was computed drop out here
+    let points = app_state
+        .db
+        .get_scholar_map_details(&ids)
+        .await?
+        .into_iter()
+        .filter_map(|details| {
+            let [x, y] = *coordinates.get(details.id.as_str())?;
+            Some(ScholarMapPoint {
+                tags: tags.remove(&details.id).unwrap_or_default(),
+                id: details.id,
+                name: details.name,
+                identity: details.identity,
+                image_filename: details.image_filename,
+                x,
+                y,
+            })
+        })
+        .collect();
+
+    Ok(ScholarMapResponse {
+        embedding_model: projection.model.clone(),
+        explained_variance: projection.explained_variance,
+        computed_at: projection.computed_at,
+        points,
+    })
+}
+
+/// Project vectors onto their first two principal components, scaled into [-1, 1]
+/// with both axes sharing one scale; also returns each axis' share of the variance
+fn project_2d(vectors: &[Vec<f32>]) -> (Vec<[f32; 2]>, [f32; 2]) {
+    let Some(dimensions) = vectors.first().map(Vec::len) else {
+        return (Vec::new(), [0.0; 2]);
+    };
+
+    let mut mean = vec![0.0f32; dimensions];
+    for vector in vectors {
+        for (sum, value) in mean.iter_mut().zip(vector) {
+            *sum += value;
+        }
+    }
+    mean.iter_mut().for_each(|sum| *sum /= vectors.len() as f32);
+    let centered: Vec<Vec<f32>> = vectors
+        .iter()
+        .map(|vector| {
+            vector
+                .iter()
+                .zip(&mean)
+                .map(|(value, mean)| value - mean)
+                .collect()
+        })
+        .collect();
+
+    let first = principal_component(&centered, &[]);
+    let second = principal_component(&centered, std::slice::from_ref(&first));
+    let mut coordinates: Vec<[f32; 2]> = centered
+        .iter()
+        .map(|row| [dot(row, &first), dot(row, &second)])
+        .collect();
+
+    let total_variance: f32 = centered.iter().map(|row| dot(row, row)).sum();
+    let mut explained_variance = [0.0; 2];
+    if total_variance > 0.0 {
+        for (axis, explained) in explained_variance.iter_mut().enumerate() {
+            *explained = coordinates
+                .iter()
+                .map(|point| point[axis].powi(2))
+                .sum::<f32>()
+                / total_variance;
+        }
+    }
+
+    let extent = coordinates
+        .iter()
+        .flatten()
+        .fold(0.0f32, |extent, value| extent.max(value.abs()));
+    if extent > 0.0 {
+        coordinates
+            .iter_mut()
+            .flatten()
+            .for_each(|value| *value /= extent);
+    }
+
+    (coordinates, explained_variance)
+}
+
+/// Leading eigenvector of the rows' covariance, orthogonal to the earlier components,
+/// by power iteration; all zeros when nothing is left to explain
+fn principal_component(rows: &[Vec<f32>], previous: &[Vec<f32>]) -> Vec<f32> {
+    // The row that stands out most is a far better start than an arbitrary direction
+    let Some(start) = rows
+        .iter()
+        .map(|row| orthogonalize(row.clone(), previous))
+        .max_by(|a, b| dot(a, a).total_cmp(&dot(b, b)))
+    else {
+        return Vec::new();
+    };
+
+    let mut component = normalize(start);
+    for _ in 0..POWER_ITERATIONS {
+        // Covariance times the component, without forming the covariance matrix
+        let mut next = vec![0.0f32; component.len()];
+        for row in rows {
+            let weight = dot(row, &component);
+            for (sum, value) in next.iter_mut().zip(row) {
+                *sum += weight * value;
+            }
+        }
+        let next = normalize(orthogonalize(next, previous));
+        let converged = dot(&next, &component).abs() > 1.0 - CONVERGENCE;
+        component = next;
+        if converged {
+            break;
+        }
+    }
+
+    // A component's sign is arbitrary; fixing it keeps the map from mirroring
+    // between recomputations
+    let largest = component
+        .iter()
+        .copied()
+        .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+        .unwrap_or_default();
+    if largest < 0.0 {
+        component.iter_mut().for_each(|value| *value = -*value);
+    }
+    component
+}
+
+fn orthogonalize(mut vector: Vec<f32>, basis: &[Vec<f32>]) -> Vec<f32> {
+    for direction in basis {
+        let projection = dot(&vector, direction);
+        for (value, component) in vector.iter_mut().zip(direction) {
+            *value -= projection * component;
+        }
+    }
+    vector
+}
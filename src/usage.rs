@@ -0,0 +1,87 @@
+use std::future::Future;
+use std::time::Instant;
+
+use crate::models::{Claims, LlmCallKind, LlmUsageRecord, TokenUsage, UsageContext, UserRole};
+use crate::utils::{AppError, AppResult, AppState};
+
+/// Daily token quotas per role; None means unlimited
+#[derive(Debug, Clone, Copy)]
+pub struct TokenQuotas {
+    pub admin: Option<i64>,
+    pub moderator: Option<i64>,
+    pub editor: Option<i64>,
+}
+
+impl TokenQuotas {
+    /// Read LLM_DAILY_TOKENS_<ROLE>; 0 or a missing value means unlimited
+    pub fn from_env() -> Self {
+        let quota = |key: &str, default: i64| {
+            let tokens = std::env::var(key)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default);
+            (tokens > 0).then_some(tokens)
+        };
+
+        Self {
+            admin: quota("LLM_DAILY_TOKENS_ADMIN", 0),
+            moderator: quota("LLM_DAILY_TOKENS_MODERATOR", 500_000),
+            editor: quota("LLM_DAILY_TOKENS_EDITOR", 200_000),
+        }
+    }
+
+    pub fn for_role(&self, role: &UserRole) -> Option<i64> {
+        match role {
+            UserRole::Admin => self.admin,
+            UserRole::Moderator => self.moderator,
+            UserRole::Editor => self.editor,
+        }
+    }
+}
+
+/// Refuse further LLM calls once a user has spent their role's tokens for the day
+pub async fn check_quota(app_state: &AppState, claims: &Claims) -> AppResult<()> {
+    let Some(quota) = app_state.token_quotas.for_role(&claims.role) else {
+        return Ok(());
+    };
+
+    let used = app_state.db.get_user_tokens_today(&claims.user_id).await?;
+    if used >= quota {
+        return Err(AppError::TooManyRequests(format!(
+            "Daily AI usage limit of {} tokens reached, please try again tomorrow",
+            quota
+        )));
+    }
+    Ok(())
+}
+
+/// Run an LLM API call and record it in the usage log, whatever its outcome
+/// Failing to record is logged and never fails the call itself
+pub async fn metered<T>(
+    app_state: &AppState,
+    context: UsageContext<'_>,
+    kind: LlmCallKind,
+    model: &str,
+    call: impl Future<Output = AppResult<(T, TokenUsage)>>,
+) -> AppResult<T> {
+    let started = Instant::now();
+    let result = call.await;
+
+    let (tokens, error) = match &result {
+        Ok((_, tokens)) => (*tokens, None),
+        Err(e) => (TokenUsage::default(), Some(e.to_string())),
+    };
+    let record = LlmUsageRecord {
+        context,
+        kind,
+        model,
+        tokens,
+        latency_ms: started.elapsed().as_millis().min(i32::MAX as u128) as i32,
+        error,
+    };
+    if let Err(e) = app_state.db.record_llm_usage(&record).await {
+        log::error!("Failed to record LLM usage for {}: {}", context.endpoint, e);
+    }
+
+    result.map(|(value, _)| value)
+}
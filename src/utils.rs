@@ -1,8 +1,18 @@
 use crate::db::Database;
+use crate::llm_client::{LlmClient, LlmClientSettings};
 use crate::middleware::CacheMiddleware;
-use actix_web::{HttpResponse, error::ResponseError, http::StatusCode};
+use crate::models::MAX_RAG_LIMIT;
+use crate::rag_cache::RAGCache;
+use crate::rerank::Reranker;
+use crate::scholar_map::ScholarMapCache;
+use crate::usage::TokenQuotas;
+use actix_web::{HttpResponse, error::ResponseError, http::StatusCode, http::header::RETRY_AFTER};
 use openidconnect::{ClientId, ClientSecret, IssuerUrl, reqwest};
 use serde::Serialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, atomic::AtomicBool};
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Clone)]
@@ -14,11 +24,47 @@ pub struct AppState {
     pub oidc_client_id: ClientId,
     pub oidc_client_secret: ClientSecret,
     pub cache: CacheMiddleware,
+    pub rag_cache: RAGCache,
+    pub scholar_map: ScholarMapCache,
     // LLM Configuration
+    /// Client for LLM, embedding and rerank API calls, with timeouts and a circuit breaker
+    pub llm_client: LlmClient,
     pub llm_api_key: String,
     pub llm_base_url: String,
     pub embedding_model: String,
     pub chat_model: String,
+    /// Most inputs and estimated tokens sent in one embeddings request
+    pub embedding_batch_size: usize,
+    pub embedding_batch_tokens: usize,
+    // Passage chunking and retrieval
+    pub passage_max_chars: usize,
+    pub passage_overlap_chars: usize,
+    pub passages_per_scholar: i64,
+    /// Estimated tokens of scholar context per prompt, by chat model, with a default
+    pub context_token_budget: usize,
+    pub context_token_budgets: HashMap<String, usize>,
+    // Reranking of vector search candidates
+    pub reranker: Reranker,
+    pub rerank_candidates: i64,
+    pub rerank_model: String,
+    pub rerank_base_url: String,
+    /// Whether RAG chat may call read-only database tools, and for how many turns
+    pub chat_tools: bool,
+    pub chat_tool_rounds: usize,
+    /// News and tag documents retrieved alongside scholars; 0 disables them
+    pub rag_document_limit: i64,
+    /// Set when a news item or tag changes, so the next backlog pass re-embeds documents
+    pub rag_documents_stale: Arc<AtomicBool>,
+    /// Whether card summaries are generated for scholars in the background
+    pub scholar_summaries: bool,
+    // Public ask endpoint limits
+    pub ask_daily_limit: i32,
+    pub ask_max_message_chars: usize,
+    pub ask_max_turns: usize,
+    /// Reverse proxies whose X-Forwarded-For is trusted for the client IP
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Daily LLM token quotas per role
+    pub token_quotas: TokenQuotas,
 }
 
 impl AppState {
@@ -39,6 +85,7 @@ impl AppState {
         let oidc_client_secret = ClientSecret::new(oidc_client_secret);
 
         // Load LLM configuration from environment
+        let llm_client = LlmClient::new(LlmClientSettings::from_env());
         let llm_api_key = std::env::var("LLM_API_KEY").unwrap_or_default();
         let llm_base_url = std::env::var("LLM_BASE_URL")
             .unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
@@ -47,6 +94,52 @@ impl AppState {
         let chat_model = std::env::var("CHAT_MODEL")
             .unwrap_or_else(|_| "gpt-4-turbo".to_string());
 
+        let embedding_batch_size = env_or("EMBEDDING_BATCH_SIZE", 100).max(1);
+        let embedding_batch_tokens = env_or("EMBEDDING_BATCH_TOKENS", 50_000).max(1);
+
+        // Answers are cached for an hour by default; 0 disables answer caching
+        let embedding_cache_ttl = env_or("RAG_EMBEDDING_CACHE_TTL_SECS", 86_400);
+        let answer_cache_ttl = env_or("RAG_ANSWER_CACHE_TTL_SECS", 3_600);
+        let rag_cache = RAGCache::new(
+            Duration::from_secs(embedding_cache_ttl),
+            (answer_cache_ttl > 0).then(|| Duration::from_secs(answer_cache_ttl)),
+        );
+
+        let passage_max_chars = env_or("PASSAGE_MAX_CHARS", 500).max(50);
+        // Overlap must leave room for new text in every passage
+        let passage_overlap_chars = env_or("PASSAGE_OVERLAP_CHARS", 100).min(passage_max_chars / 2);
+        let passages_per_scholar = env_or("RAG_PASSAGES_PER_SCHOLAR", 3).max(1);
+
+        let context_token_budget = env_or("RAG_CONTEXT_TOKENS", 6_000).max(100);
+        let context_token_budgets =
+            parse_model_budgets(&std::env::var("RAG_CONTEXT_TOKENS_BY_MODEL").unwrap_or_default());
+
+        let reranker = std::env::var("RERANKER")
+            .unwrap_or_default()
+            .parse()
+            .unwrap_or_else(|e| {
+                log::warn!("{}, reranking disabled", e);
+                Reranker::None
+            });
+        let rerank_candidates = env_or("RERANK_CANDIDATES", 20).max(1);
+        let rerank_model =
+            std::env::var("RERANK_MODEL").unwrap_or_else(|_| "rerank-v1".to_string());
+        let rerank_base_url =
+            std::env::var("RERANK_BASE_URL").unwrap_or_else(|_| llm_base_url.clone());
+
+        let chat_tools = env_or("RAG_CHAT_TOOLS", true);
+        let chat_tool_rounds = env_or("RAG_CHAT_TOOL_ROUNDS", 3);
+        let rag_document_limit = env_or("RAG_DOCUMENT_LIMIT", 3).clamp(0, MAX_RAG_LIMIT);
+        let scholar_summaries = env_or("SCHOLAR_SUMMARIES", true);
+
+        let ask_daily_limit = env_or("ASK_DAILY_LIMIT", 20);
+        let ask_max_message_chars = env_or("ASK_MAX_MESSAGE_CHARS", 1000).max(1);
+        let ask_max_turns = env_or("ASK_MAX_TURNS", 10).max(1);
+        let trusted_proxies =
+            parse_trusted_proxies(&std::env::var("TRUSTED_PROXIES").unwrap_or_default());
+
+        let token_quotas = TokenQuotas::from_env();
+
         Self {
             db,
             jwt_secret,
@@ -55,14 +148,92 @@ impl AppState {
             oidc_client_id,
             oidc_client_secret,
             cache,
+            rag_cache,
+            scholar_map: ScholarMapCache::default(),
+            llm_client,
             llm_api_key,
             llm_base_url,
             embedding_model,
             chat_model,
+            embedding_batch_size,
+            embedding_batch_tokens,
+            passage_max_chars,
+            passage_overlap_chars,
+            passages_per_scholar,
+            context_token_budget,
+            context_token_budgets,
+            reranker,
+            rerank_candidates,
+            rerank_model,
+            rerank_base_url,
+            chat_tools,
+            chat_tool_rounds,
+            rag_document_limit,
+            rag_documents_stale: Arc::default(),
+            scholar_summaries,
+            ask_daily_limit,
+            ask_max_message_chars,
+            ask_max_turns,
+            trusted_proxies,
+            token_quotas,
         }
     }
 }
 
+impl AppState {
+    /// Token budget for the scholar context sent to the configured chat model
+    pub fn context_budget(&self) -> usize {
+        self.context_token_budgets
+            .get(&self.chat_model)
+            .copied()
+            .unwrap_or(self.context_token_budget)
+    }
+}
+
+/// Parse `model=tokens` pairs separated by commas, skipping malformed entries
+fn parse_model_budgets(value: &str) -> HashMap<String, usize> {
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| {
+            let parsed = entry
+                .split_once('=')
+                .and_then(|(model, tokens)| Some((model.trim(), tokens.trim().parse().ok()?)));
+            if parsed.is_none() {
+                log::warn!(
+                    "Ignoring malformed RAG_CONTEXT_TOKENS_BY_MODEL entry: {}",
+                    entry
+                );
+            }
+            parsed.map(|(model, tokens): (&str, usize)| (model.to_string(), tokens.max(100)))
+        })
+        .collect()
+}
+
+/// Parse comma-separated IP addresses, skipping malformed entries
+fn parse_trusted_proxies(value: &str) -> Vec<IpAddr> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.parse().ok();
+            if parsed.is_none() {
+                log::warn!("Ignoring malformed TRUSTED_PROXIES entry: {}", entry);
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Read a numeric setting from the environment, falling back to a default
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("Database error: {0}")]
@@ -83,9 +254,36 @@ pub enum AppError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
     #[error("Internal server error: {0}")]
     InternalError(String),
 
+    /// The LLM API failed; `status` is None when no response was received
+    #[error("Upstream API error: {message}")]
+    UpstreamError {
+        status: Option<u16>,
+        message: String,
+    },
+
+    #[error("Upstream API timeout: {0}")]
+    UpstreamTimeout(String),
+
+    /// The LLM API answered 429; `retry_after` is its Retry-After in seconds, if given
+    #[error("Upstream API rate limited: {message}")]
+    UpstreamRateLimited {
+        retry_after: Option<u64>,
+        message: String,
+    },
+
+    /// Calls to the LLM API are failing fast until `retry_after` seconds have passed
+    #[error("Upstream API unavailable: {message}")]
+    UpstreamUnavailable { retry_after: u64, message: String },
+
+    #[error("Invalid upstream API response: {0}")]
+    UpstreamInvalidResponse(String),
+
     #[error("Validation error: {0}")]
     ValidationError(String),
 
@@ -154,6 +352,66 @@ impl ResponseError for AppError {
                     details: None,
                 },
             ),
+            AppError::TooManyRequests(msg) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorResponse {
+                    error: "Too Many Requests".to_string(),
+                    message: Some(msg.clone()),
+                    details: None,
+                },
+            ),
+            AppError::UpstreamError { .. } => {
+                log::error!("{}", self);
+                (
+                    StatusCode::BAD_GATEWAY,
+                    ErrorResponse {
+                        error: "Bad Gateway".to_string(),
+                        message: Some("The LLM API request failed".to_string()),
+                        details: None,
+                    },
+                )
+            }
+            AppError::UpstreamInvalidResponse(_) => {
+                log::error!("{}", self);
+                (
+                    StatusCode::BAD_GATEWAY,
+                    ErrorResponse {
+                        error: "Bad Gateway".to_string(),
+                        message: Some("The LLM API sent an invalid response".to_string()),
+                        details: None,
+                    },
+                )
+            }
+            AppError::UpstreamTimeout(_) => {
+                log::error!("{}", self);
+                (
+                    StatusCode::GATEWAY_TIMEOUT,
+                    ErrorResponse {
+                        error: "Gateway Timeout".to_string(),
+                        message: Some("The LLM API did not respond in time".to_string()),
+                        details: None,
+                    },
+                )
+            }
+            AppError::UpstreamRateLimited { .. } => {
+                log::warn!("{}", self);
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    ErrorResponse {
+                        error: "Service Unavailable".to_string(),
+                        message: Some("The LLM API is rate limited, try again later".to_string()),
+                        details: None,
+                    },
+                )
+            }
+            AppError::UpstreamUnavailable { .. } => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                ErrorResponse {
+                    error: "Service Unavailable".to_string(),
+                    message: Some("The LLM API is temporarily unavailable".to_string()),
+                    details: None,
+                },
+            ),
             AppError::DatabaseError(e) => {
                 log::error!("Database error: {}", e);
                 (
@@ -178,7 +436,18 @@ impl ResponseError for AppError {
             }
         };
 
-        HttpResponse::build(status).json(error_response)
+        let mut response = HttpResponse::build(status);
+        if let AppError::UpstreamRateLimited {
+            retry_after: Some(secs),
+            ..
+        }
+        | AppError::UpstreamUnavailable {
+            retry_after: secs, ..
+        } = self
+        {
+            response.insert_header((RETRY_AFTER, secs.to_string()));
+        }
+        response.json(error_response)
     }
 
     fn status_code(&self) -> StatusCode {
@@ -188,6 +457,14 @@ impl ResponseError for AppError {
             AppError::Forbidden(_) => StatusCode::FORBIDDEN,
             AppError::BadRequest(_) | AppError::ValidationError(_) => StatusCode::BAD_REQUEST,
             AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::UpstreamError { .. } | AppError::UpstreamInvalidResponse(_) => {
+                StatusCode::BAD_GATEWAY
+            }
+            AppError::UpstreamTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::UpstreamRateLimited { .. } | AppError::UpstreamUnavailable { .. } => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }